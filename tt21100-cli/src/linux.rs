@@ -0,0 +1,88 @@
+//! Access to GPIO lines using the Linux userspace interfaces
+//!
+//! The I²C bus itself is accessed using
+//! [I2cdev](linux_embedded_hal::I2cdev), which performs each transaction as a
+//! single `I2C_RDWR` transfer.
+
+use std::{fmt, fs::File, io, os::unix::fs::FileExt as _, path::Path};
+
+use embedded_hal::digital::{self, InputPin};
+
+/// Error reading a GPIO line
+#[derive(Debug)]
+pub struct IoError(pub io::Error);
+
+impl fmt::Display for IoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl digital::Error for IoError {
+    fn kind(&self) -> digital::ErrorKind {
+        digital::ErrorKind::Other
+    }
+}
+
+/// A GPIO line, read through the `value` file exported by sysfs
+///
+/// The file is held open, and read again from its start for each sample.
+pub struct SysfsPin {
+    file: File,
+}
+
+impl SysfsPin {
+    /// Open the GPIO line whose value is exposed at the given path, for
+    /// example `/sys/class/gpio/gpio17/value`
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+
+        Ok(Self { file })
+    }
+
+    fn value(&self) -> io::Result<bool> {
+        let mut value = [0u8; 1];
+        self.file.read_exact_at(&mut value, 0)?;
+
+        Ok(value[0] != b'0')
+    }
+}
+
+impl digital::ErrorType for SysfsPin {
+    type Error = IoError;
+}
+
+impl InputPin for SysfsPin {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        self.value().map_err(IoError)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        self.value().map(|value| !value).map_err(IoError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::*;
+
+    #[test]
+    fn reads_value_again_for_each_sample() {
+        let path = env::temp_dir().join(format!("tt21100-cli-gpio-{}", process::id()));
+        fs::write(&path, "1\n").unwrap();
+        let mut pin = SysfsPin::open(&path).unwrap();
+
+        assert!(pin.is_high().unwrap());
+        fs::write(&path, "0\n").unwrap();
+        assert!(pin.is_low().unwrap());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reports_missing_lines() {
+        assert!(SysfsPin::open("/nonexistent/gpio/value").is_err());
+    }
+}
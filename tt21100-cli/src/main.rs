@@ -0,0 +1,133 @@
+//! Print events from a TT21100 attached to a Linux I²C bus
+//!
+//! Opens the given I²C bus, initializes the device, and prints each event read
+//! from it to standard output as a line of JSON:
+//!
+//! ```text
+//! tt21100-cli <i2c-bus> [--irq <gpio-value-path>] [--poll-interval <ms>]
+//! ```
+//!
+//! When the path to the `value` file of the GPIO line connected to the
+//! interrupt pin is given, for example `/sys/class/gpio/gpio17/value`, the
+//! line is polled while waiting for events; otherwise the device itself is
+//! sampled. Either is polled at the interval given by `--poll-interval`, in
+//! milliseconds, which defaults to one.
+
+#![cfg_attr(not(target_os = "linux"), allow(dead_code, unused_imports))]
+
+use std::{env, fmt::Debug, process};
+
+use embedded_hal::{digital::InputPin, i2c::I2c};
+#[cfg(target_os = "linux")]
+use linux_embedded_hal::{Delay, I2cdev};
+use tt21100::{Config, Error, Event, TT21100};
+
+#[cfg(target_os = "linux")]
+use self::linux::SysfsPin;
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+// Time to wait for an event on the interrupt line before waiting again, in
+// microseconds
+const WAIT_TIMEOUT_US: u32 = 1_000_000;
+
+const USAGE: &str = "usage: tt21100-cli <i2c-bus> [--irq <gpio-value-path>] [--poll-interval <ms>]";
+
+#[cfg(not(target_os = "linux"))]
+fn main() {
+    eprintln!("tt21100-cli is only supported on Linux");
+    process::exit(1);
+}
+
+#[cfg(target_os = "linux")]
+fn main() {
+    let mut args = env::args().skip(1);
+    let mut bus = None;
+    let mut irq = None;
+    let mut poll_interval_ms = 1;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--irq" => irq = args.next(),
+            "--poll-interval" => {
+                poll_interval_ms = args
+                    .next()
+                    .and_then(|ms| ms.parse::<u32>().ok())
+                    .unwrap_or_else(|| usage())
+            }
+            _ => bus = Some(arg),
+        }
+    }
+
+    let bus = bus.unwrap_or_else(|| usage());
+    let i2c = I2cdev::new(&bus).unwrap_or_else(|e| {
+        eprintln!("failed to open {}: {}", bus, e);
+        process::exit(1);
+    });
+    let config = Config {
+        poll_interval_us: poll_interval_ms.saturating_mul(1_000),
+        ..Config::default()
+    };
+
+    let mut delay = Delay;
+    match irq {
+        Some(path) => {
+            let irq = SysfsPin::open(&path).unwrap_or_else(|e| {
+                eprintln!("failed to open {}: {}", path, e);
+                process::exit(1);
+            });
+            let mut driver = TT21100::new_with_config(i2c, irq, config);
+            init(&mut driver);
+            loop {
+                match driver.event_with_timeout(&mut delay, WAIT_TIMEOUT_US) {
+                    Ok(Some(event)) => print(&event),
+                    Ok(None) => {}
+                    Err(e) => fail(e),
+                }
+            }
+        }
+        None => {
+            let mut driver = TT21100::new_without_irq(i2c, config);
+            init(&mut driver);
+            loop {
+                match driver.poll_event(&mut delay) {
+                    Ok(event) => print(&event),
+                    Err(e) => fail(e),
+                }
+            }
+        }
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn init<I2C, IRQ, E>(driver: &mut TT21100<I2C, IRQ>)
+where
+    I2C: I2c<Error = E>,
+    IRQ: InputPin,
+    E: Debug,
+{
+    driver.init().unwrap_or_else(|e| {
+        eprintln!("failed to initialize the device: {}", e);
+        process::exit(1);
+    });
+}
+
+/// Print an event as a single line of JSON
+fn print(event: &Event) {
+    match serde_json::to_string(event) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("failed to serialize event: {}", e),
+    }
+}
+
+fn fail<E>(error: Error<E>) -> !
+where
+    E: Debug,
+{
+    eprintln!("failed to read event: {}", error);
+    process::exit(1);
+}
@@ -0,0 +1,12 @@
+#!/bin/sh
+# Print the size of the `.text` section of the firmware for each feature set
+# listed in the README
+
+set -e
+cd "$(dirname "$0")"
+
+for features in "" "instrumentation" "defaults"; do
+    cargo build --quiet --release --features "$features"
+    text=$(llvm-size -A target/thumbv7em-none-eabihf/release/tt21100-size | awk '$1 == ".text" { print $2 }')
+    printf '%-17s %6s\n' "${features:-(none)}" "$text"
+done
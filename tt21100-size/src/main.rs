@@ -0,0 +1,116 @@
+//! Firmware used to measure the code size of the driver
+//!
+//! Initializes the blocking driver and reads events from it in a loop. The bus,
+//! interrupt pin and delay are stand-ins which access fixed addresses, so that
+//! nothing read from the device is known at compile time. The firmware is only
+//! linked, never run; see `measure.sh`.
+
+#![no_std]
+#![no_main]
+
+use core::{
+    convert::Infallible,
+    panic::PanicInfo,
+    ptr::{read_volatile, write_volatile},
+};
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{self, InputPin},
+    i2c::{self, I2c, Operation},
+};
+use tt21100::TT21100;
+
+/// Address standing in for the data register of the I²C peripheral
+const BUS: *mut u8 = 0x4000_0000 as *mut u8;
+
+/// Address standing in for the input register of the GPIO peripheral
+const GPIO: *const u32 = 0x4000_1000 as *const u32;
+
+/// Address to which the coordinates of each touch record are written
+const OUTPUT: *mut u16 = 0x4000_2000 as *mut u16;
+
+/// Address standing in for a cycle counter
+#[cfg(feature = "instrumentation")]
+const CYCLES: *const u32 = 0x4000_3000 as *const u32;
+
+struct Bus;
+
+impl i2c::ErrorType for Bus {
+    type Error = Infallible;
+}
+
+impl I2c for Bus {
+    fn transaction(
+        &mut self,
+        _address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        for operation in operations {
+            match operation {
+                Operation::Read(buffer) => {
+                    for byte in buffer.iter_mut() {
+                        *byte = unsafe { read_volatile(BUS) };
+                    }
+                }
+                Operation::Write(bytes) => {
+                    for byte in bytes.iter() {
+                        unsafe { write_volatile(BUS, *byte) };
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct Irq;
+
+impl digital::ErrorType for Irq {
+    type Error = Infallible;
+}
+
+impl InputPin for Irq {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(unsafe { read_volatile(GPIO) } & 1 != 0)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.is_high()?)
+    }
+}
+
+struct Delay;
+
+impl DelayNs for Delay {
+    fn delay_ns(&mut self, ns: u32) {
+        for _ in 0..ns {
+            unsafe { read_volatile(GPIO) };
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn _start() -> ! {
+    let mut driver = TT21100::new_with_delay(Bus, Irq, Delay, Default::default());
+    #[cfg(feature = "instrumentation")]
+    driver.set_clock(Some(|| unsafe { read_volatile(CYCLES) }));
+    while driver.init().is_err() {}
+
+    loop {
+        if let Ok(event) = driver.wait_for_event() {
+            for record in event.touches() {
+                unsafe {
+                    write_volatile(OUTPUT, record.x);
+                    write_volatile(OUTPUT, record.y);
+                }
+            }
+        }
+    }
+}
+
+#[panic_handler]
+fn panic(_info: &PanicInfo) -> ! {
+    loop {}
+}
@@ -0,0 +1,58 @@
+//! Visualizer for recorded TT21100 touch traces
+//!
+//! Renders the touch paths, pressure, and button activity of a recorded trace
+//! as an SVG image:
+//!
+//! ```text
+//! tt21100-viz [--lenient] <trace> [output.svg]
+//! ```
+//!
+//! The image is written to standard output if no output path is given. See the
+//! [trace] module for a description of the trace formats.
+
+use std::{env, fs, process};
+
+use tt21100::ParseMode;
+
+mod render;
+mod trace;
+
+fn main() {
+    let mut parsing = ParseMode::Strict;
+    let mut paths = Vec::new();
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--lenient" => parsing = ParseMode::Lenient,
+            _ => paths.push(arg),
+        }
+    }
+
+    let (input, output) = match paths.as_slice() {
+        [input] => (input, None),
+        [input, output] => (input, Some(output)),
+        _ => {
+            eprintln!("usage: tt21100-viz [--lenient] <trace> [output.svg]");
+            process::exit(2);
+        }
+    };
+
+    let trace = fs::read(input).unwrap_or_else(|e| {
+        eprintln!("failed to read {}: {}", input, e);
+        process::exit(1);
+    });
+    let frames = trace::parse(&trace, parsing).unwrap_or_else(|e| {
+        eprintln!("failed to parse {}: {}", input, e);
+        process::exit(1);
+    });
+
+    let svg = render::render(&frames, render::Bounds::of(&frames));
+    match output {
+        Some(output) => {
+            if let Err(e) = fs::write(output, svg) {
+                eprintln!("failed to write {}: {}", output, e);
+                process::exit(1);
+            }
+        }
+        None => print!("{}", svg),
+    }
+}
@@ -0,0 +1,302 @@
+//! Rendering of traces as SVG
+
+use std::fmt::Write;
+
+use tt21100::{Event, Phase, TouchRecord};
+
+use crate::trace::Frame;
+
+/// Width of the rendered image
+const WIDTH: f32 = 800.0;
+/// Height of the pressure plot
+const PRESSURE_HEIGHT: f32 = 120.0;
+/// Height of each button lane
+const LANE_HEIGHT: f32 = 16.0;
+/// Number of buttons reported by the device
+const BUTTONS: usize = 4;
+/// Margin surrounding each section of the image
+const MARGIN: f32 = 20.0;
+
+/// Colours used for each touch ID, in turn
+const PALETTE: [&str; 8] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#17becf",
+];
+
+/// A single sample of a contact
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    timestamp_ms: u32,
+    record: TouchRecord,
+}
+
+/// Consecutive samples of a single contact
+#[derive(Debug, Clone)]
+struct Stroke {
+    touch_id: u8,
+    samples: Vec<Sample>,
+}
+
+/// Native coordinate range of the panel
+#[derive(Debug, Clone, Copy)]
+pub struct Bounds {
+    /// Greatest X coordinate
+    pub max_x: u16,
+    /// Greatest Y coordinate
+    pub max_y: u16,
+}
+
+impl Bounds {
+    /// The smallest bounds containing every contact in the given frames
+    pub fn of(frames: &[Frame]) -> Self {
+        let mut bounds = Bounds { max_x: 1, max_y: 1 };
+        for record in frames.iter().flat_map(|frame| frame.event.touches()) {
+            bounds.max_x = bounds.max_x.max(record.x);
+            bounds.max_y = bounds.max_y.max(record.y);
+        }
+
+        bounds
+    }
+}
+
+/// Render the given frames as an SVG image
+///
+/// The image shows the path taken by each contact across the panel, with the
+/// radius of each sample indicating its pressure, followed by plots of the
+/// pressure of each contact and the state of each button over time.
+pub fn render(frames: &[Frame], bounds: Bounds) -> String {
+    let scale = (WIDTH - 2.0 * MARGIN) / f32::from(bounds.max_x);
+    let panel_height = f32::from(bounds.max_y) * scale + 2.0 * MARGIN;
+    let timeline_top = panel_height;
+    let lanes_top = timeline_top + PRESSURE_HEIGHT + MARGIN;
+    let height = lanes_top + BUTTONS as f32 * LANE_HEIGHT + MARGIN;
+
+    let start = frames.first().map_or(0, |frame| frame.timestamp_ms);
+    let end = frames.last().map_or(0, |frame| frame.timestamp_ms);
+    let duration = end.wrapping_sub(start).max(1) as f32;
+    let time_x = |timestamp_ms: u32| {
+        MARGIN + timestamp_ms.wrapping_sub(start) as f32 / duration * (WIDTH - 2.0 * MARGIN)
+    };
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = WIDTH,
+        h = height
+    );
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+    let _ = writeln!(
+        svg,
+        r##"<rect x="{m}" y="{m}" width="{w}" height="{h}" fill="none" stroke="#999"/>"##,
+        m = MARGIN,
+        w = f32::from(bounds.max_x) * scale,
+        h = f32::from(bounds.max_y) * scale
+    );
+
+    let strokes = strokes(frames);
+    for stroke in &strokes {
+        let colour = PALETTE[usize::from(stroke.touch_id) % PALETTE.len()];
+
+        let points: Vec<String> = stroke
+            .samples
+            .iter()
+            .map(|sample| {
+                format!(
+                    "{:.1},{:.1}",
+                    MARGIN + f32::from(sample.record.x) * scale,
+                    MARGIN + f32::from(sample.record.y) * scale
+                )
+            })
+            .collect();
+        let _ = writeln!(
+            svg,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1.5"/>"#,
+            points.join(" "),
+            colour
+        );
+
+        for sample in &stroke.samples {
+            let (fill, opacity) = match sample.record.phase() {
+                Phase::Touch => (colour, 0.6),
+                Phase::Hover => ("none", 1.0),
+            };
+            let _ = writeln!(
+                svg,
+                r#"<circle cx="{:.1}" cy="{:.1}" r="{:.1}" fill="{}" fill-opacity="{}" stroke="{}"/>"#,
+                MARGIN + f32::from(sample.record.x) * scale,
+                MARGIN + f32::from(sample.record.y) * scale,
+                1.0 + f32::from(sample.record.pressure) / 32.0,
+                fill,
+                opacity,
+                colour
+            );
+        }
+    }
+
+    // Pressure of each contact over time
+    let _ = writeln!(
+        svg,
+        r##"<rect x="{m}" y="{t}" width="{w}" height="{h}" fill="none" stroke="#999"/>"##,
+        m = MARGIN,
+        t = timeline_top,
+        w = WIDTH - 2.0 * MARGIN,
+        h = PRESSURE_HEIGHT
+    );
+    for stroke in &strokes {
+        let colour = PALETTE[usize::from(stroke.touch_id) % PALETTE.len()];
+        let points: Vec<String> = stroke
+            .samples
+            .iter()
+            .map(|sample| {
+                let y = timeline_top + PRESSURE_HEIGHT
+                    - f32::from(sample.record.pressure) / 255.0 * PRESSURE_HEIGHT;
+                format!("{:.1},{:.1}", time_x(sample.timestamp_ms), y)
+            })
+            .collect();
+        let _ = writeln!(
+            svg,
+            r#"<polyline points="{}" fill="none" stroke="{}"/>"#,
+            points.join(" "),
+            colour
+        );
+    }
+
+    // State of each button over time
+    for button in 0..BUTTONS {
+        let top = lanes_top + button as f32 * LANE_HEIGHT;
+        let _ = writeln!(
+            svg,
+            r##"<text x="{}" y="{}" font-size="10" fill="#333">{}</text>"##,
+            2.0,
+            top + LANE_HEIGHT - 4.0,
+            button
+        );
+
+        let mut pressed_at = None;
+        for frame in frames {
+            if let Event::Button(record) = frame.event {
+                let pressed = record.btn_val & (1 << button) != 0;
+                match (pressed, pressed_at) {
+                    (true, None) => pressed_at = Some(frame.timestamp_ms),
+                    (false, Some(since)) => {
+                        button_span(&mut svg, time_x(since), time_x(frame.timestamp_ms), top);
+                        pressed_at = None;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        if let Some(since) = pressed_at {
+            button_span(&mut svg, time_x(since), time_x(end), top);
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn button_span(svg: &mut String, from: f32, to: f32, top: f32) {
+    let _ = writeln!(
+        svg,
+        r##"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="#555"/>"##,
+        from,
+        top + 2.0,
+        (to - from).max(1.0),
+        LANE_HEIGHT - 4.0
+    );
+}
+
+/// Group the samples of each contact into strokes, a stroke ending whenever
+/// its contact is absent from a touch event
+fn strokes(frames: &[Frame]) -> Vec<Stroke> {
+    let mut finished = Vec::new();
+    let mut active: Vec<Stroke> = Vec::new();
+
+    for frame in frames {
+        if let Event::Button(_) = frame.event {
+            continue;
+        }
+
+        let present: Vec<TouchRecord> = frame.event.touches().collect();
+        let (ended, continuing): (Vec<Stroke>, Vec<Stroke>) = active
+            .into_iter()
+            .partition(|stroke| !present.iter().any(|r| r.touch_id == stroke.touch_id));
+        finished.extend(ended);
+        active = continuing;
+
+        for record in present {
+            let sample = Sample {
+                timestamp_ms: frame.timestamp_ms,
+                record,
+            };
+            match active
+                .iter_mut()
+                .find(|stroke| stroke.touch_id == record.touch_id)
+            {
+                Some(stroke) => stroke.samples.push(sample),
+                None => active.push(Stroke {
+                    touch_id: record.touch_id,
+                    samples: vec![sample],
+                }),
+            }
+        }
+    }
+
+    finished.extend(active);
+    finished
+}
+
+#[cfg(test)]
+mod tests {
+    use tt21100::ButtonRecord;
+
+    use super::*;
+
+    fn touch(timestamp_ms: u32, ids: &[u8]) -> Frame {
+        let records: Vec<TouchRecord> = ids
+            .iter()
+            .map(|&id| TouchRecord::new(id, 10 * u16::from(id), 20))
+            .collect();
+
+        Frame {
+            timestamp_ms,
+            event: Event::touch(0, &records),
+        }
+    }
+
+    #[test]
+    fn ends_strokes_when_contacts_are_absent() {
+        let frames = [
+            touch(0, &[1]),
+            touch(10, &[1, 2]),
+            touch(20, &[2]),
+            touch(30, &[1]),
+        ];
+        let strokes: Vec<(u8, usize)> = strokes(&frames)
+            .iter()
+            .map(|stroke| (stroke.touch_id, stroke.samples.len()))
+            .collect();
+
+        assert_eq!(strokes, [(1, 2), (2, 2), (1, 1)]);
+    }
+
+    #[test]
+    fn renders_contacts_and_buttons() {
+        let frames = [
+            touch(0, &[1, 2]),
+            Frame {
+                timestamp_ms: 10,
+                event: Event::Button(ButtonRecord::new(0, 0b0001)),
+            },
+            touch(20, &[1]),
+        ];
+        let bounds = Bounds::of(&frames);
+        assert_eq!((bounds.max_x, bounds.max_y), (20, 20));
+
+        let svg = render(&frames, bounds);
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<circle ").count(), 3);
+        assert_eq!(svg.matches(r##"fill="#555""##).count(), 1);
+    }
+}
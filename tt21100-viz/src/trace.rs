@@ -0,0 +1,190 @@
+//! Loading of recorded traces
+//!
+//! Traces are either in the binary format defined by [tt21100::trace], or are
+//! text files containing one raw frame per line, in the form
+//! `<timestamp_ms> <hex bytes>`, where the bytes are the complete message read
+//! from the device, including its length. Blank lines, and lines beginning
+//! with `#`, are ignored in text traces.
+
+use std::{fmt, num::ParseIntError};
+
+use tt21100::{
+    trace::{TraceReader, MAGIC},
+    Event,
+    ParseMode,
+};
+
+/// A single frame of a trace
+#[derive(Debug, Clone, Copy)]
+pub struct Frame {
+    /// Time at which the frame was captured, in milliseconds
+    pub timestamp_ms: u32,
+    /// Event decoded from the frame
+    pub event: Event,
+}
+
+/// Any error which may occur while loading a trace
+#[derive(Debug)]
+pub enum TraceError {
+    /// A line did not contain both a timestamp and some bytes
+    MissingField(usize),
+    /// A timestamp or byte could not be parsed
+    InvalidNumber(usize, ParseIntError),
+    /// The bytes of a frame contained a character which is not a hex digit
+    InvalidDigit(usize),
+    /// The bytes of a frame contained an odd number of digits
+    OddLength(usize),
+    /// A frame could not be decoded
+    InvalidFrame(usize, tt21100::Error<()>),
+    /// A binary trace was malformed
+    Binary(tt21100::trace::TraceError),
+    /// A text trace was not valid UTF-8
+    NotText,
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceError::MissingField(line) => write!(f, "line {}: missing field", line),
+            TraceError::InvalidNumber(line, e) => write!(f, "line {}: {}", line, e),
+            TraceError::InvalidDigit(line) => write!(f, "line {}: invalid hex digit", line),
+            TraceError::OddLength(line) => write!(f, "line {}: odd number of hex digits", line),
+            TraceError::InvalidFrame(line, e) => write!(f, "line {}: {}", line, e),
+            TraceError::Binary(e) => write!(f, "malformed trace: {:?}", e),
+            TraceError::NotText => write!(f, "trace is neither binary nor text"),
+        }
+    }
+}
+
+impl std::error::Error for TraceError {}
+
+/// Parse the frames of a trace, in either the binary or text format
+///
+/// Frames indicating that no data was available are skipped.
+pub fn parse(trace: &[u8], parsing: ParseMode) -> Result<Vec<Frame>, TraceError> {
+    if trace.starts_with(&MAGIC) {
+        return parse_binary(trace, parsing);
+    }
+
+    let trace = std::str::from_utf8(trace).map_err(|_| TraceError::NotText)?;
+    parse_text(trace, parsing)
+}
+
+fn parse_binary(trace: &[u8], parsing: ParseMode) -> Result<Vec<Frame>, TraceError> {
+    let mut frames = Vec::new();
+    for (index, frame) in TraceReader::new(trace)
+        .map_err(TraceError::Binary)?
+        .enumerate()
+    {
+        let frame = frame.map_err(TraceError::Binary)?;
+        match Event::from_message::<()>(frame.message, parsing) {
+            Ok(event) => frames.push(Frame {
+                timestamp_ms: frame.timestamp_ms,
+                event,
+            }),
+            Err(tt21100::Error::NoDataAvailable) => {}
+            Err(e) => return Err(TraceError::InvalidFrame(index + 1, e)),
+        }
+    }
+
+    Ok(frames)
+}
+
+fn parse_text(trace: &str, parsing: ParseMode) -> Result<Vec<Frame>, TraceError> {
+    let mut frames = Vec::new();
+    for (index, line) in trace.lines().enumerate() {
+        let number = index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (timestamp, bytes) = line
+            .split_once(char::is_whitespace)
+            .ok_or(TraceError::MissingField(number))?;
+        let timestamp_ms = timestamp
+            .parse()
+            .map_err(|e| TraceError::InvalidNumber(number, e))?;
+        let bytes = parse_hex(bytes, number)?;
+
+        match Event::from_message::<()>(&bytes, parsing) {
+            Ok(event) => frames.push(Frame {
+                timestamp_ms,
+                event,
+            }),
+            Err(tt21100::Error::NoDataAvailable) => {}
+            Err(e) => return Err(TraceError::InvalidFrame(number, e)),
+        }
+    }
+
+    Ok(frames)
+}
+
+fn parse_hex(bytes: &str, line: usize) -> Result<Vec<u8>, TraceError> {
+    let digits: String = bytes.chars().filter(|c| !c.is_whitespace()).collect();
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(TraceError::InvalidDigit(line));
+    }
+    if digits.len() % 2 != 0 {
+        return Err(TraceError::OddLength(line));
+    }
+
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&digits[i..][..2], 16)
+                .map_err(|e| TraceError::InvalidNumber(line, e))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOUCH: &str = "1B 00 01 10 27 02 00 \
+                         00 C0 40 01 F0 00 20 08 00 00 \
+                         00 C1 80 00 78 00 18 06 00 00";
+
+    #[test]
+    fn parses_text_traces() {
+        let trace = format!(
+            "# captured on a bench\n\n10 {}\n20 0200\n30 {}\n",
+            TOUCH, TOUCH
+        );
+        let frames = parse(trace.as_bytes(), ParseMode::Strict).unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].timestamp_ms, 10);
+        assert_eq!(frames[1].timestamp_ms, 30);
+        assert!(frames[0]
+            .event
+            .touches()
+            .map(|record| (record.touch_id, record.x, record.y))
+            .eq([(0, 320, 240), (1, 128, 120)]));
+    }
+
+    #[test]
+    fn reports_line_of_malformed_frames() {
+        let parse = |trace: &str| parse(trace.as_bytes(), ParseMode::Strict);
+
+        assert!(matches!(parse("10"), Err(TraceError::MissingField(1))));
+        assert!(matches!(
+            parse("\nten 0200"),
+            Err(TraceError::InvalidNumber(2, _))
+        ));
+        assert!(matches!(
+            parse("10 02 0G"),
+            Err(TraceError::InvalidDigit(1))
+        ));
+        assert!(matches!(parse("10 020"), Err(TraceError::OddLength(1))));
+        assert!(matches!(
+            parse("10 0200\n20 0800 09 00 00 00 00"),
+            Err(TraceError::InvalidFrame(2, _))
+        ));
+        assert!(matches!(
+            super::parse(&[0xFF, 0xFE], ParseMode::Strict),
+            Err(TraceError::NotText)
+        ));
+    }
+}
@@ -0,0 +1,95 @@
+//! Sleep until the panel is touched
+//!
+//! The device is armed to wake on a touch and put to sleep, after which the
+//! host may itself sleep until the interrupt line is asserted. Once woken, the
+//! host wakes the device and continues to read events from it as usual.
+//!
+//! A replayed trace stands in for the device here, so that the example can be
+//! run on the host:
+//!
+//! ```text
+//! cargo run -p tt21100 --example wake_on_touch --features trace
+//! ```
+//!
+//! On a microcontroller, the host would instead configure the interrupt line
+//! as a wake-up source before entering its own low-power state.
+
+use std::{thread, time::Duration};
+
+use embedded_hal::delay::DelayNs;
+use tt21100::{
+    replay::{Asserted, ReplayDevice},
+    trace::TraceWriter,
+    Config,
+    Event,
+    TouchRecord,
+    WakeGesture,
+    TT21100,
+};
+
+/// A delay which sleeps the current thread
+struct Sleep;
+
+impl DelayNs for Sleep {
+    fn delay_ns(&mut self, ns: u32) {
+        thread::sleep(Duration::from_nanos(u64::from(ns)));
+    }
+}
+
+fn main() {
+    // The device acknowledges the command arming it, is later woken by a touch,
+    // and reports a touch once awake again
+    let mut buffer = [0u8; 128];
+    let mut writer = TraceWriter::new(&mut buffer).unwrap_or_else(|_| panic!("buffer too small"));
+    for (timestamp_ms, message) in [
+        (0, vec![6, 0, 0x1F, 0x00, 0x09, 0x00]),
+        (1_000, touch(100)),
+        (1_100, touch(200)),
+    ] {
+        writer
+            .record(timestamp_ms, &message)
+            .unwrap_or_else(|_| panic!("buffer too small"));
+    }
+
+    let device = ReplayDevice::new(writer.as_bytes()).unwrap_or_else(|_| panic!("invalid trace"));
+    let mut driver = TT21100::new_with_delay(&device, Asserted, Sleep, Config::default());
+    driver
+        .init()
+        .unwrap_or_else(|_| panic!("failed to initialize the device"));
+
+    device.advance_to(0);
+    driver
+        .sleep_until(WakeGesture::Touch)
+        .unwrap_or_else(|_| panic!("failed to arm the device"));
+    println!("device armed, host going to sleep");
+
+    // Here the host would sleep until the interrupt line is asserted; replaying
+    // the trace stands in for the touch which asserts it
+    device.advance_to(1_000);
+    println!("interrupt asserted, waking the device");
+    driver
+        .wake()
+        .unwrap_or_else(|_| panic!("failed to wake the device"));
+
+    device.advance_to(1_100);
+    let event = driver
+        .wait_for_event()
+        .unwrap_or_else(|_| panic!("failed to read an event"));
+    for record in event.touches() {
+        println!("touch at ({}, {})", record.x, record.y);
+    }
+}
+
+/// A message reporting a single contact at the given position
+fn touch(x: u16) -> Vec<u8> {
+    let event = Event::touch(0, &[TouchRecord::new(1, x, x)]);
+    let mut message = Vec::new();
+    if let Event::Touch { report, touches } = event {
+        message.extend_from_slice(&report.into_bytes());
+        for record in touches {
+            message.extend_from_slice(&record.into_bytes());
+        }
+    }
+
+    message
+}
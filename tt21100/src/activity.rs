@@ -0,0 +1,237 @@
+//! Detection of periods of inactivity
+
+use crate::{time::Instant, Event};
+
+/// A change in whether the panel is in use
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub enum Activity {
+    /// No input has been received for the configured period
+    Idle,
+    /// Input has been received after a period of inactivity
+    Active,
+    /// Input has been received after a period of inactivity, and is being
+    /// consumed to wake the system rather than being delivered
+    WokeFromIdle,
+}
+
+/// Notifies the application when the panel has gone unused for a period of
+/// time, and when it is next used, for example to dim the display
+///
+/// Every event read from the device counts as input, including those
+/// reporting that contacts or buttons were released. The monitor begins in
+/// the active state.
+///
+/// Optionally, the input which ends a period of inactivity may be consumed,
+/// so that the touch which turns the display back on does not also press
+/// whatever is beneath it. Every event from the first touch or button press
+/// until everything has been released is then consumed, as reported by
+/// [ActivityMonitor::consumed].
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct ActivityMonitor {
+    /// Time without input after which the panel is idle, in milliseconds
+    timeout_ms: u32,
+    /// Time at which input was last received
+    last_input: Instant,
+    /// Is the panel currently idle?
+    idle: bool,
+    /// Is the input which ends a period of inactivity consumed?
+    consume_wake: bool,
+    /// Is input currently being consumed?
+    consuming: bool,
+    /// Was the most recent event consumed?
+    consumed: bool,
+}
+
+impl ActivityMonitor {
+    /// Create a new monitor which considers the panel idle after `timeout_ms`
+    /// milliseconds without input, counting from `now`
+    pub fn new(timeout_ms: u32, now: Instant) -> Self {
+        Self {
+            timeout_ms,
+            last_input: now,
+            idle: false,
+            consume_wake: false,
+            consuming: false,
+            consumed: false,
+        }
+    }
+
+    /// Time without input after which the panel is idle, in milliseconds
+    pub fn timeout_ms(&self) -> u32 {
+        self.timeout_ms
+    }
+
+    /// Replace the time without input after which the panel is idle, taking
+    /// effect from the next poll
+    pub fn set_timeout_ms(&mut self, timeout_ms: u32) {
+        self.timeout_ms = timeout_ms;
+    }
+
+    /// Is the input which ends a period of inactivity consumed?
+    pub fn consumes_wake(&self) -> bool {
+        self.consume_wake
+    }
+
+    /// Set whether the input which ends a period of inactivity is consumed
+    pub fn set_consume_wake(&mut self, consume_wake: bool) {
+        self.consume_wake = consume_wake;
+    }
+
+    /// Was the event most recently passed to [ActivityMonitor::update]
+    /// consumed to wake the system, and so should not be delivered?
+    pub fn consumed(&self) -> bool {
+        self.consumed
+    }
+
+    /// Is the panel currently idle?
+    pub fn is_idle(&self) -> bool {
+        self.idle
+    }
+
+    /// Update the monitor with an event read from the device, returning
+    /// [Activity::Active] or [Activity::WokeFromIdle] if the panel was idle
+    pub fn update(&mut self, event: &Event, now: Instant) -> Option<Activity> {
+        self.last_input = now;
+
+        let activity = if self.idle {
+            self.idle = false;
+            self.consuming = self.consume_wake;
+
+            if self.consume_wake {
+                Some(Activity::WokeFromIdle)
+            } else {
+                Some(Activity::Active)
+            }
+        } else {
+            None
+        };
+
+        self.consumed = self.consuming;
+        if self.consuming && is_released(event) {
+            self.consuming = false;
+        }
+
+        activity
+    }
+
+    /// Check how long it has been since input was last received, returning
+    /// [Activity::Idle] once the panel has become idle
+    ///
+    /// This should be called periodically, as no events are read from the
+    /// device while it is not in use.
+    pub fn poll(&mut self, now: Instant) -> Option<Activity> {
+        if self.idle || now.millis_since(self.last_input) < self.timeout_ms {
+            return None;
+        }
+
+        self.idle = true;
+        Some(Activity::Idle)
+    }
+}
+
+/// Does the event report that every contact and button has been released?
+fn is_released(event: &Event) -> bool {
+    match event {
+        Event::Touch { .. } => event.touches().next().is_none(),
+        Event::Button(record) => record.btn_val & 0xf == 0,
+        // The palm remains on the panel
+        Event::PalmRejected(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ButtonRecord, TouchRecord, TouchReport};
+
+    fn touch() -> Event {
+        Event::touch(0, &[TouchRecord::new(1, 10, 10)])
+    }
+
+    fn release() -> Event {
+        Event::touch(0, &[])
+    }
+
+    #[test]
+    fn becomes_idle_after_timeout() {
+        let mut monitor = ActivityMonitor::new(1_000, Instant::from_millis(0));
+
+        assert!(monitor.poll(Instant::from_millis(999)).is_none());
+        assert!(monitor
+            .update(&touch(), Instant::from_millis(500))
+            .is_none());
+        assert!(monitor.poll(Instant::from_millis(1_499)).is_none());
+        assert!(matches!(
+            monitor.poll(Instant::from_millis(1_500)),
+            Some(Activity::Idle)
+        ));
+        assert!(monitor.is_idle());
+        assert!(monitor.poll(Instant::from_millis(5_000)).is_none());
+    }
+
+    #[test]
+    fn becomes_active_on_next_input() {
+        let mut monitor = ActivityMonitor::new(1_000, Instant::from_millis(0));
+        monitor.poll(Instant::from_millis(1_000));
+
+        assert!(matches!(
+            monitor.update(&release(), Instant::from_millis(2_000)),
+            Some(Activity::Active)
+        ));
+        assert!(!monitor.is_idle());
+        assert!(!monitor.consumed());
+        assert!(monitor.poll(Instant::from_millis(2_999)).is_none());
+    }
+
+    #[test]
+    fn consumes_input_until_released() {
+        let mut monitor = ActivityMonitor::new(1_000, Instant::from_millis(0));
+        monitor.set_consume_wake(true);
+        monitor.poll(Instant::from_millis(1_000));
+
+        assert!(matches!(
+            monitor.update(&touch(), Instant::from_millis(2_000)),
+            Some(Activity::WokeFromIdle)
+        ));
+        assert!(monitor.consumed());
+        assert!(monitor
+            .update(
+                &Event::PalmRejected(TouchReport::new(0, 0)),
+                Instant::from_millis(2_010)
+            )
+            .is_none());
+        assert!(monitor.consumed());
+        assert!(monitor
+            .update(&release(), Instant::from_millis(2_020))
+            .is_none());
+        assert!(monitor.consumed());
+
+        monitor.update(&touch(), Instant::from_millis(2_030));
+        assert!(!monitor.consumed());
+    }
+
+    #[test]
+    fn consumes_button_presses_until_released() {
+        let mut monitor = ActivityMonitor::new(1_000, Instant::from_millis(0));
+        monitor.set_consume_wake(true);
+        monitor.poll(Instant::from_millis(1_000));
+
+        monitor.update(
+            &Event::Button(ButtonRecord::new(0, 0b0001)),
+            Instant::from_millis(2_000),
+        );
+        assert!(monitor.consumed());
+        monitor.update(
+            &Event::Button(ButtonRecord::new(0, 0)),
+            Instant::from_millis(2_010),
+        );
+        assert!(monitor.consumed());
+        monitor.update(
+            &Event::Button(ButtonRecord::new(0, 0b0001)),
+            Instant::from_millis(2_020),
+        );
+        assert!(!monitor.consumed());
+    }
+}
@@ -0,0 +1,1025 @@
+//! Async driver
+//!
+//! Enabled by the `async` feature, which requires Rust 1.75 or later. The
+//! async driver shares its configuration, parsing and validation of messages
+//! with the blocking [TT21100](crate::TT21100), differing only in how it
+//! communicates with the device and waits for data to become available.
+
+use core::fmt::Debug;
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::{
+    delay::DelayNs,
+    i2c::{I2c, Operation},
+};
+#[cfg(feature = "stream")]
+use futures_util::stream::{self, Stream};
+
+use self::source::EventSource;
+#[cfg(feature = "instrumentation")]
+use crate::instrumentation;
+use crate::{
+    command,
+    descriptor,
+    geom::{self, NoRotation, OrientationProvider},
+    state::State,
+    Calibration,
+    Config,
+    Error,
+    Event,
+    NoDelay,
+    NoReset,
+    PanelProfile,
+    Response,
+    Version,
+    WakeGesture,
+    MAX_FLUSHED_MESSAGES,
+    MAX_MESSAGE_LEN,
+    MAX_SKIPPED_MESSAGES,
+    RESET_PULSE_US,
+    RESET_RECOVERY_US,
+    RESPONSE_ATTEMPTS,
+    RESPONSE_INTERVAL_US,
+    SLEEP_COMMAND,
+    WAKE_COMMAND,
+};
+#[cfg(feature = "tracker")]
+use crate::{time, tracker, TouchRecord};
+
+#[cfg(feature = "embassy-sync")]
+pub mod signal;
+pub mod source;
+#[cfg(feature = "embassy-sync")]
+pub mod split;
+
+/// TT21100 driver
+pub struct TT21100<I2C, IRQ, DELAY = NoDelay, RST = NoReset, ORIENT = NoRotation> {
+    /// Underlying I²C peripheral
+    i2c: I2C,
+    /// Source of notification that data may be available
+    irq: IRQ,
+    /// Reset pin, if connected
+    reset: Option<RST>,
+    /// Source of the current orientation of the display
+    orientation: ORIENT,
+    /// State shared with the blocking driver
+    state: State,
+    /// Delay used to enforce the minimum gap between bus transactions
+    delay: DELAY,
+}
+
+impl<I2C, IRQ, E> TT21100<I2C, IRQ>
+where
+    I2C: I2c<Error = E>,
+    IRQ: EventSource,
+    E: Debug,
+{
+    /// Create a new instance of the driver
+    pub fn new(i2c: I2C, irq: IRQ) -> Self {
+        Self::new_with_config(i2c, irq, Config::default())
+    }
+
+    /// Create a new instance of the driver using the provided configuration
+    pub fn new_with_config(i2c: I2C, irq: IRQ, config: Config) -> Self {
+        Self::new_with_delay(i2c, irq, NoDelay, config)
+    }
+
+    /// Create a new instance of the driver for a device at the given address
+    ///
+    /// For use on boards which strap the device to an address other than
+    /// [DEFAULT_ADDRESS](crate::DEFAULT_ADDRESS); the address may also be set
+    /// using [Config::address].
+    pub fn new_with_address(i2c: I2C, irq: IRQ, address: u8) -> Self {
+        Self::new_with_config(
+            i2c,
+            irq,
+            Config {
+                address,
+                ..Config::default()
+            },
+        )
+    }
+}
+
+impl<I2C, IRQ, DELAY, E> TT21100<I2C, IRQ, DELAY>
+where
+    I2C: I2c<Error = E>,
+    IRQ: EventSource,
+    E: Debug,
+    DELAY: DelayNs,
+{
+    /// Create a new instance of the driver using the provided delay and
+    /// configuration
+    ///
+    /// The delay is used to enforce [Config::transaction_gap_us].
+    pub fn new_with_delay(i2c: I2C, irq: IRQ, delay: DELAY, config: Config) -> Self {
+        Self::create(i2c, irq, None, delay, config, NoRotation)
+    }
+}
+
+impl<I2C, IRQ, DELAY, ORIENT, E> TT21100<I2C, IRQ, DELAY, NoReset, ORIENT>
+where
+    I2C: I2c<Error = E>,
+    IRQ: EventSource,
+    E: Debug,
+    DELAY: DelayNs,
+    ORIENT: OrientationProvider,
+{
+    /// Create a new instance of the driver for a display which rotates at
+    /// runtime, using the provided delay and configuration
+    ///
+    /// The coordinates of each touch record then follow the orientation of
+    /// the display, for example as reported by an accelerometer. The provider
+    /// is queried once per touch report, and the rotation it reports is
+    /// applied following the configured [transform](Config::transform).
+    pub fn new_with_orientation(
+        i2c: I2C,
+        irq: IRQ,
+        delay: DELAY,
+        config: Config,
+        provider: ORIENT,
+    ) -> Self {
+        Self::create(i2c, irq, None, delay, config, provider)
+    }
+}
+
+impl<I2C, IRQ, DELAY, RST, E> TT21100<I2C, IRQ, DELAY, RST>
+where
+    I2C: I2c<Error = E>,
+    IRQ: EventSource,
+    E: Debug,
+    DELAY: DelayNs,
+    RST: OutputPin,
+{
+    /// Create a new instance of the driver for a device whose reset pin is
+    /// connected, using the provided delay and configuration
+    ///
+    /// The device is then reset by [init](Self::init) and
+    /// [hard_reset](Self::hard_reset), with the delay used to time the reset
+    /// pulse and the recovery from it, as well as to enforce
+    /// [Config::transaction_gap_us].
+    pub fn new_with_reset(i2c: I2C, irq: IRQ, reset: RST, delay: DELAY, config: Config) -> Self {
+        Self::create(i2c, irq, Some(reset), delay, config, NoRotation)
+    }
+}
+
+impl<I2C, IRQ, DELAY, RST, ORIENT, E> TT21100<I2C, IRQ, DELAY, RST, ORIENT>
+where
+    I2C: I2c<Error = E>,
+    IRQ: EventSource,
+    E: Debug,
+    DELAY: DelayNs,
+    RST: OutputPin,
+    ORIENT: OrientationProvider,
+{
+    /// Create a new instance of the driver for a device whose reset pin is
+    /// connected, on a display which rotates at runtime
+    ///
+    /// The reset pin is used as by [new_with_reset](TT21100::new_with_reset),
+    /// and the orientation provider as by
+    /// [new_with_orientation](TT21100::new_with_orientation).
+    pub fn new_with_reset_and_orientation(
+        i2c: I2C,
+        irq: IRQ,
+        reset: RST,
+        delay: DELAY,
+        config: Config,
+        provider: ORIENT,
+    ) -> Self {
+        Self::create(i2c, irq, Some(reset), delay, config, provider)
+    }
+
+    /// Initialize the device, confirming that it can be communicated with
+    ///
+    /// Initialization takes several bus transactions, so may be skipped where
+    /// boot time is critical. Communication with the device is then instead
+    /// verified by the first event read, which reports any message of
+    /// unexpected length as an error regardless of the configured
+    /// [ParseMode](crate::ParseMode); a device which is absent or unresponsive
+    /// is therefore only detected once the first event is read.
+    ///
+    /// When the driver has been provided with a reset pin the device is reset
+    /// first, ensuring that it starts from a known state.
+    pub async fn init(&mut self) -> Result<(), Error<E>> {
+        self.pulse_reset().await?;
+        self.connect().await
+    }
+
+    /// Has the device been initialized?
+    ///
+    /// The device is initialized by [init](Self::init) or
+    /// [reinit](Self::reinit), or once the first message has been read from it
+    /// successfully.
+    pub fn is_initialized(&self) -> bool {
+        self.state.initialized
+    }
+
+    /// Initialize the device again, for example after it has been reset or
+    /// reconnected
+    ///
+    /// Any state derived from earlier reports, along with any
+    /// [injected](Self::inject) event, is discarded before the handshake is
+    /// performed again. The stored configuration continues to apply.
+    pub async fn reinit(&mut self) -> Result<(), Error<E>> {
+        self.state.reset();
+
+        self.connect().await
+    }
+
+    /// Reset the device using its reset pin, then initialize it again
+    ///
+    /// Any state derived from earlier reports is discarded, as by
+    /// [reinit](Self::reinit), and the device is awake once reset. Without a
+    /// reset pin this is equivalent to [reinit](Self::reinit).
+    pub async fn hard_reset(&mut self) -> Result<(), Error<E>> {
+        self.pulse_reset().await?;
+        self.state.asleep = false;
+
+        self.reinit().await
+    }
+
+    /// Put the device to sleep
+    ///
+    /// The device stops scanning the panel and reports no events while
+    /// asleep. It is woken, and initialized again, by [wake](Self::wake).
+    ///
+    /// The `sleep-on-drop` feature does not apply to the async driver, which
+    /// cannot write to the bus as it is dropped; use
+    /// [release_sleeping](Self::release_sleeping) instead.
+    pub async fn sleep(&mut self) -> Result<(), Error<E>> {
+        self.write_bytes(&SLEEP_COMMAND).await?;
+        self.state.asleep = true;
+
+        Ok(())
+    }
+
+    /// Put the device to sleep, unless it is asleep already, then release the
+    /// I²C peripheral and the source of notifications
+    ///
+    /// This is the async counterpart of dropping the blocking driver with the
+    /// `sleep-on-drop` feature enabled, except that an error writing the
+    /// command is reported rather than ignored.
+    pub async fn release_sleeping(mut self) -> Result<(I2C, IRQ), Error<E>> {
+        if !self.state.asleep {
+            self.sleep().await?;
+        }
+
+        Ok((self.i2c, self.irq))
+    }
+
+    /// Put the device to sleep, arming it to wake when the given gesture is
+    /// performed
+    ///
+    /// The device asserts the interrupt line once the gesture has been
+    /// performed, so the host may itself sleep until then; it should then wake
+    /// the device using [wake](Self::wake). Any events read while waiting for
+    /// the device to acknowledge the command are discarded. The acknowledgement
+    /// is polled for using the driver's delay, so one should have been
+    /// provided using [new_with_delay](Self::new_with_delay).
+    pub async fn sleep_until(&mut self, gesture: WakeGesture) -> Result<(), Error<E>> {
+        self.write_command(command::ENTER_EASY_WAKE, &[u8::from(gesture)])
+            .await?;
+        self.state.asleep = true;
+
+        Ok(())
+    }
+
+    /// Put the device into its lowest-power state
+    ///
+    /// The device is put to sleep, and is then held in reset when the driver
+    /// has been provided with a reset pin, drawing as little power as
+    /// possible. Waking the device with [wake](Self::wake) then takes as long
+    /// as a [hard_reset](Self::hard_reset). Without a reset pin this is
+    /// equivalent to [sleep](Self::sleep).
+    pub async fn deep_sleep(&mut self) -> Result<(), Error<E>> {
+        self.sleep().await?;
+        if let Some(reset) = self.reset.as_mut() {
+            reset.set_low().map_err(|_| Error::IOError)?;
+            self.state.held_in_reset = true;
+        }
+
+        Ok(())
+    }
+
+    /// Wake the device from sleep or deep sleep, initializing it again
+    ///
+    /// Any state derived from reports read before the device was put to sleep
+    /// is discarded, as by [reinit](Self::reinit); the stored configuration
+    /// continues to apply. Events queued while the device was asleep, such as
+    /// the [gesture](Self::sleep_until) which woke it, are discarded too.
+    pub async fn wake(&mut self) -> Result<(), Error<E>> {
+        if self.state.held_in_reset {
+            self.release_reset().await?;
+        } else {
+            self.write_bytes(&WAKE_COMMAND).await?;
+            self.flush_pending().await?;
+        }
+        self.state.asleep = false;
+
+        self.reinit().await
+    }
+
+    /// Has the device been put to sleep?
+    pub fn is_asleep(&self) -> bool {
+        self.state.asleep
+    }
+
+    /// Put the device to sleep
+    #[deprecated(note = "renamed to `sleep`")]
+    pub async fn suspend(&mut self) -> Result<(), Error<E>> {
+        self.sleep().await
+    }
+
+    /// Wake the device from sleep, initializing it again
+    #[deprecated(note = "renamed to `wake`")]
+    pub async fn resume(&mut self) -> Result<(), Error<E>> {
+        self.wake().await
+    }
+
+    /// Has the device been put to sleep?
+    #[deprecated(note = "renamed to `is_asleep`")]
+    pub fn is_suspended(&self) -> bool {
+        self.is_asleep()
+    }
+
+    /// Read the identity of the device and the version of its firmware
+    ///
+    /// These are read from the device's HID descriptor, and are useful when
+    /// diagnosing differences in behaviour between revisions of a panel.
+    pub async fn version(&mut self) -> Result<Version, Error<E>> {
+        let mut descriptor = [0u8; descriptor::DESCRIPTOR_LEN];
+        self.read_register(descriptor::DESCRIPTOR_REGISTER, &mut descriptor)
+            .await?;
+
+        Version::from_descriptor(&descriptor).ok_or(Error::InvalidDescriptor)
+    }
+
+    /// Read from a register of the device
+    ///
+    /// This is an escape hatch for functionality not otherwise provided by the
+    /// driver; reading some registers alters what is returned when the next
+    /// event is read.
+    pub async fn read_register(
+        &mut self,
+        register: u16,
+        buffer: &mut [u8],
+    ) -> Result<(), Error<E>> {
+        self.wait_for_gap().await;
+
+        #[cfg(feature = "instrumentation")]
+        let start = self.state.start_timing();
+        self.i2c
+            .write_read(self.state.config.address, &register.to_le_bytes(), buffer)
+            .await
+            .map_err(|e| Error::BusError(e))?;
+        #[cfg(feature = "instrumentation")]
+        self.state
+            .record_timing(instrumentation::Transaction::RegisterRead, start);
+
+        Ok(())
+    }
+
+    /// Write to a register of the device
+    ///
+    /// This is an escape hatch for functionality not otherwise provided by the
+    /// driver, and may leave the device in a state the driver does not expect.
+    pub async fn write_register(&mut self, register: u16, data: &[u8]) -> Result<(), Error<E>> {
+        self.wait_for_gap().await;
+
+        // Adjacent writes are sent without a restart between them
+        let register = register.to_le_bytes();
+        #[cfg(feature = "instrumentation")]
+        let start = self.state.start_timing();
+        self.i2c
+            .transaction(
+                self.state.config.address,
+                &mut [Operation::Write(&register), Operation::Write(data)],
+            )
+            .await
+            .map_err(|e| Error::BusError(e))?;
+        #[cfg(feature = "instrumentation")]
+        self.state
+            .record_timing(instrumentation::Transaction::RegisterWrite, start);
+
+        Ok(())
+    }
+
+    /// Write a command of the vendor protocol to the device, returning its
+    /// response
+    ///
+    /// This is an escape hatch for functionality not otherwise provided by the
+    /// driver. At most [MAX_COMMAND_PARAMETERS](crate::MAX_COMMAND_PARAMETERS)
+    /// parameters may be given. Any events read while waiting for the response
+    /// are discarded.
+    pub async fn write_command(
+        &mut self,
+        code: u8,
+        parameters: &[u8],
+    ) -> Result<Response, Error<E>> {
+        let command = command::Command::new(code, parameters)
+            .ok_or(Error::CommandTooLong(parameters.len()))?;
+        self.write_bytes(command.as_bytes()).await?;
+
+        let mut response = Response::new();
+        for _ in 0..RESPONSE_ATTEMPTS {
+            self.read_bytes(response.buffer(MAX_MESSAGE_LEN)).await?;
+            response.truncate();
+            if command::is_response(response.as_bytes(), code) {
+                if self.state.config.verify_checksums && !response.has_valid_crc() {
+                    return Err(Error::ChecksumMismatch);
+                }
+
+                return Ok(response);
+            }
+
+            self.delay.delay_us(RESPONSE_INTERVAL_US).await;
+        }
+
+        Err(Error::NoResponse)
+    }
+
+    /// Is there data available to read from the device?
+    pub async fn data_available(&mut self) -> Result<(), Error<E>> {
+        if self.state.has_injected() {
+            return Ok(());
+        }
+
+        self.irq.wait().await.map_err(|_| Error::IOError)
+    }
+
+    /// Does the device have a message pending?
+    ///
+    /// Only the length of the pending message is read, so this is a cheap way
+    /// for a dispatcher to determine whether the device asserted an interrupt
+    /// line it shares with other devices, before reading the event in full.
+    /// An [injected](Self::inject) event is always pending.
+    pub async fn has_pending(&mut self) -> Result<bool, Error<E>> {
+        if self.state.has_injected() {
+            return Ok(true);
+        }
+
+        Ok(self.read_message_length().await? > 2)
+    }
+
+    /// Wait for an event, reading from the device whenever the event source
+    /// indicates that data may be available
+    ///
+    /// When no interrupt line is available, a [Periodic](source::Periodic)
+    /// event source can be used to sample the device at a fixed interval
+    /// instead.
+    pub async fn wait_for_event(&mut self) -> Result<Event, Error<E>> {
+        loop {
+            self.data_available().await?;
+            match self.event().await {
+                Err(Error::NoDataAvailable) => {}
+                result => return result,
+            }
+        }
+    }
+
+    /// Stream of the events read from the device, for use with stream
+    /// combinators and select loops
+    ///
+    /// Each item is the result of [wait_for_event](Self::wait_for_event). The
+    /// stream never ends; errors are yielded as items, after which it may
+    /// continue to be polled.
+    #[cfg(feature = "stream")]
+    pub fn events(&mut self) -> impl Stream<Item = Result<Event, Error<E>>> + '_ {
+        stream::unfold(self, |driver| async move {
+            let event = driver.wait_for_event().await;
+
+            Some((event, driver))
+        })
+    }
+
+    /// Consume the driver, converting it into a stream of the events read from
+    /// the device
+    ///
+    /// See [events](Self::events) for details.
+    #[cfg(feature = "stream")]
+    pub fn into_stream(self) -> impl Stream<Item = Result<Event, Error<E>>> {
+        stream::unfold(self, |mut driver| async move {
+            let event = driver.wait_for_event().await;
+
+            Some((event, driver))
+        })
+    }
+
+    /// Read an event from the device
+    ///
+    /// There are two types of events, [Event::Touch] and [Event::Button],
+    /// with touch events reported as [Event::PalmRejected] while a palm is
+    /// being rejected. Any event which has been [injected](Self::inject) is
+    /// returned ahead of those read from the device.
+    pub async fn event(&mut self) -> Result<Event, Error<E>> {
+        if let Some(event) = self.state.take_injected(&mut self.orientation) {
+            return Ok(event);
+        }
+
+        // Messages which do not result in an event are skipped in favour of the
+        // next message queued on the device
+        for _ in 0..MAX_SKIPPED_MESSAGES {
+            if let Some(event) = self.read_event().await? {
+                return Ok(event);
+            }
+        }
+
+        Err(Error::NoDataAvailable)
+    }
+
+    /// Wait for the contact identified by `handle` to be lifted, returning the
+    /// last record reported for it along with statistics describing its
+    /// lifetime
+    ///
+    /// Every event read while waiting is used to update `tracker`, with `now`
+    /// providing the time at which each was read, so this should not be used
+    /// while events are also being consumed elsewhere. Returns `None` if the
+    /// contact is not currently being tracked.
+    #[cfg(feature = "tracker")]
+    pub async fn wait_released<const N: usize>(
+        &mut self,
+        tracker: &mut tracker::Tracker<N>,
+        handle: tracker::ContactHandle,
+        now: fn() -> time::Instant,
+    ) -> Result<Option<(TouchRecord, tracker::ContactStats)>, Error<E>> {
+        let touch_id = match tracker.contact_by_handle(handle) {
+            Some(contact) => contact.touch_id(),
+            None => return Ok(None),
+        };
+
+        loop {
+            let event = self.wait_for_event().await?;
+            for event in tracker.update(&event, now()) {
+                match event {
+                    tracker::TouchEvent::Up(record, stats) if record.touch_id == touch_id => {
+                        return Ok(Some((record, stats)));
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Discard every event currently queued on the device, along with any
+    /// [injected](Self::inject) event, returning the number of messages
+    /// discarded
+    ///
+    /// This is useful after resuming from sleep or a long pause in handling
+    /// events, when the queue may hold stale touches which would otherwise be
+    /// delivered as phantom input. Messages are discarded without being
+    /// parsed, so malformed messages do not interrupt the flush.
+    pub async fn flush_pending(&mut self) -> Result<usize, Error<E>> {
+        self.state.flush();
+
+        let mut data = [0u8; MAX_MESSAGE_LEN];
+        for flushed in 0..MAX_FLUSHED_MESSAGES {
+            self.read_bytes(&mut data).await?;
+            if u16::from_le_bytes([data[0], data[1]]) <= 2 {
+                return Ok(flushed);
+            }
+        }
+
+        Ok(MAX_FLUSHED_MESSAGES)
+    }
+
+    /// Wait for an event, sampling the device periodically rather than
+    /// waiting on the event source
+    ///
+    /// For use when no interrupt line is available, as an alternative to a
+    /// [Periodic](source::Periodic) event source. The device is sampled every
+    /// [Config::poll_interval_us] while it is in use, backing off to
+    /// [Config::idle_poll_interval_us] once no events have been read for
+    /// [Config::idle_after_us].
+    pub async fn poll_event<D>(&mut self, delay: &mut D) -> Result<Event, Error<E>>
+    where
+        D: DelayNs,
+    {
+        loop {
+            match self.event().await {
+                Err(Error::NoDataAvailable) => {}
+                result => {
+                    self.state.record_sample(true, 0);
+                    return result;
+                }
+            }
+
+            let interval_us = self.state.poll_interval_us();
+            delay.delay_us(interval_us).await;
+            self.state.record_sample(false, interval_us);
+        }
+    }
+
+    /// Read every event currently queued on the device
+    ///
+    /// Events are read until the device reports that no more are available.
+    #[cfg(feature = "std")]
+    pub async fn drain(&mut self) -> Result<Vec<Event>, Error<E>> {
+        let mut events = Vec::new();
+        loop {
+            match self.event().await {
+                Ok(event) => events.push(event),
+                Err(Error::NoDataAvailable) => return Ok(events),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// The most recent message read from the device, exactly as it was read
+    ///
+    /// Passing this to a [TraceWriter](crate::trace::TraceWriter) after each
+    /// event is read records a trace which can later be replayed. Injected
+    /// events are not included.
+    #[cfg(feature = "trace")]
+    pub fn last_frame(&self) -> &[u8] {
+        self.state.last_frame()
+    }
+
+    /// Inject a synthetic event, to be returned by the next read in place of
+    /// an event from the device
+    ///
+    /// Injected events are validated as though they had been read from the
+    /// device, allowing automated tests or remote control to drive any
+    /// processing of events without touching the panel. Returns the event
+    /// previously injected, if it has not yet been read.
+    pub fn inject(&mut self, event: Event) -> Option<Event> {
+        self.state.inject(event)
+    }
+
+    /// The current configuration of the driver
+    pub fn config(&self) -> &Config {
+        &self.state.config
+    }
+
+    /// Replace the configuration of the driver
+    ///
+    /// The new configuration takes effect from the next event read. As the
+    /// driver must be borrowed mutably to do so, the configuration can never
+    /// change part way through reading an event.
+    pub fn set_config(&mut self, config: Config) {
+        self.state.config = config;
+    }
+
+    /// Select the native coordinate range of the panel in use
+    pub fn set_panel(&mut self, panel: PanelProfile) {
+        self.state.config.panel = panel;
+    }
+
+    /// Select the transform applied to the coordinates of each touch record
+    pub fn set_transform(&mut self, transform: geom::Transform) {
+        self.state.config.transform = transform;
+    }
+
+    /// Select the mapping of the panel's coordinates onto those of the
+    /// display, if any
+    pub fn set_calibration(&mut self, calibration: Option<Calibration>) {
+        self.state.config.calibration = calibration;
+    }
+
+    /// Provide a clock used to measure the duration of bus transactions
+    ///
+    /// The clock must return the current time in microseconds; it is permitted
+    /// to wrap around.
+    #[cfg(feature = "instrumentation")]
+    pub fn set_clock(&mut self, clock: Option<fn() -> u32>) {
+        self.state.clock = clock;
+    }
+
+    /// Measured durations of the bus transactions performed by the driver
+    #[cfg(feature = "instrumentation")]
+    pub fn bus_timings(&self) -> &instrumentation::BusTimings {
+        &self.state.timings
+    }
+
+    /// Number of touch records which have been rejected for having coordinates
+    /// outside of the configured range, for appearing during the cool-down
+    /// period following a report of a large object, or while a palm was being
+    /// rejected
+    pub fn rejected_records(&self) -> u32 {
+        self.state.rejected_records()
+    }
+
+    /// Number of touch reports which have been dropped for being affected by
+    /// noise at least as severe as the configured
+    /// [threshold](crate::Config::noise_threshold)
+    #[cfg(feature = "report-processing")]
+    pub fn noisy_reports(&self) -> u32 {
+        self.state.noisy_reports()
+    }
+
+    /// Number of spontaneous resets of the device which have been detected
+    #[cfg(feature = "report-processing")]
+    pub fn resets(&self) -> u32 {
+        self.state.resets()
+    }
+
+    /// Number of touch reports which were missed, having been overwritten by
+    /// the next before they were read
+    ///
+    /// Missed reports are detected using the counter carried by each report,
+    /// which is only two bits wide, so a gap of four or more consecutive
+    /// reports is undercounted. An increasing count indicates that events are
+    /// not being read quickly enough.
+    #[cfg(feature = "report-processing")]
+    pub fn missed_reports(&self) -> u32 {
+        self.state.missed_reports()
+    }
+
+    // -----------------------------------------------------------------------
+    // PRIVATE
+
+    fn create(
+        i2c: I2C,
+        irq: IRQ,
+        reset: Option<RST>,
+        delay: DELAY,
+        config: Config,
+        orientation: ORIENT,
+    ) -> Self {
+        Self {
+            i2c,
+            irq,
+            reset,
+            orientation,
+            state: State::new(config),
+            delay,
+        }
+    }
+
+    async fn connect(&mut self) -> Result<(), Error<E>> {
+        self.handshake().await?;
+        self.state.initialized = true;
+
+        Ok(())
+    }
+
+    async fn pulse_reset(&mut self) -> Result<(), Error<E>> {
+        if let Some(reset) = self.reset.as_mut() {
+            reset.set_low().map_err(|_| Error::IOError)?;
+            self.delay.delay_us(RESET_PULSE_US).await;
+            self.release_reset().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn release_reset(&mut self) -> Result<(), Error<E>> {
+        if let Some(reset) = self.reset.as_mut() {
+            reset.set_high().map_err(|_| Error::IOError)?;
+            self.delay.delay_us(RESET_RECOVERY_US).await;
+        }
+        self.state.held_in_reset = false;
+
+        Ok(())
+    }
+
+    async fn handshake(&mut self) -> Result<(), Error<E>> {
+        // As with the blocking driver, the device reports an empty message with
+        // length 2 when no events are queued, which is used to confirm that it
+        // can be communicated with
+        let mut message_length = 0;
+        for _ in 0..5 {
+            message_length = self.read_message_length().await?;
+            if message_length == 2 {
+                break;
+            }
+        }
+
+        match message_length {
+            2 => Ok(()),
+            n => Err(Error::InvalidMessageLen(n)),
+        }
+    }
+
+    /// Read the next message queued on the device, returning the event it
+    /// carried, if any
+    async fn read_event(&mut self) -> Result<Option<Event>, Error<E>> {
+        // The message is read in its entirety, along with its length, in a single
+        // transaction so that traffic to other devices on a shared bus cannot be
+        // interleaved between the two
+        let mut data = [0u8; MAX_MESSAGE_LEN];
+        #[cfg(feature = "instrumentation")]
+        let start = self.state.start_timing();
+        self.read_bytes(&mut data).await?;
+        #[cfg(feature = "instrumentation")]
+        self.state
+            .record_timing(instrumentation::Transaction::PayloadRead, start);
+
+        let message_length = usize::from(u16::from_le_bytes([data[0], data[1]]));
+        let length = message_length.min(data.len());
+
+        match self
+            .state
+            .process(message_length, &data[0..][..length], &mut self.orientation)
+        {
+            Err(Error::DeviceReset) => self.recover().await,
+            result => result,
+        }
+    }
+
+    async fn recover<T>(&mut self) -> Result<T, Error<E>> {
+        self.reinit().await?;
+
+        Err(Error::DeviceReset)
+    }
+
+    async fn read_message_length(&mut self) -> Result<usize, Error<E>> {
+        let mut buffer = [0u8; 2];
+        #[cfg(feature = "instrumentation")]
+        let start = self.state.start_timing();
+        self.read_bytes(&mut buffer).await?;
+        #[cfg(feature = "instrumentation")]
+        self.state
+            .record_timing(instrumentation::Transaction::LengthRead, start);
+
+        Ok(usize::from(u16::from_le_bytes(buffer)))
+    }
+
+    async fn wait_for_gap(&mut self) {
+        if let Some(gap_us) = self.state.transaction_gap_us() {
+            self.delay.delay_us(gap_us).await;
+        }
+    }
+
+    async fn read_bytes(&mut self, buffer: &mut [u8]) -> Result<(), Error<E>> {
+        self.wait_for_gap().await;
+
+        self.i2c
+            .read(self.state.config.address, buffer)
+            .await
+            .map_err(|e| Error::BusError(e))
+    }
+
+    async fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error<E>> {
+        self.wait_for_gap().await;
+
+        #[cfg(feature = "instrumentation")]
+        let start = self.state.start_timing();
+        self.i2c
+            .write(self.state.config.address, bytes)
+            .await
+            .map_err(|e| Error::BusError(e))?;
+        #[cfg(feature = "instrumentation")]
+        self.state
+            .record_timing(instrumentation::Transaction::CommandWrite, start);
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "tracker"))]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::{mock, tests::touch_message, TouchRecord, TouchReport};
+
+    /// Create a driver whose device holds a touch message for each of the
+    /// given sets of records, and whose interrupt line is asserted
+    fn touch_driver(messages: &[&[TouchRecord]]) -> TT21100<mock::Bus, mock::Pin> {
+        let messages: Vec<_> = messages
+            .iter()
+            .map(|records| {
+                let (message, length) =
+                    touch_message(TouchReport::new(0, records.len() as u8), records);
+                message[0..][..length].to_vec()
+            })
+            .collect();
+        let bus = mock::Bus {
+            messages: messages.into(),
+            ..mock::Bus::default()
+        };
+
+        TT21100::new(
+            bus,
+            mock::Pin {
+                low: true,
+                ..mock::Pin::default()
+            },
+        )
+    }
+
+    #[test]
+    fn waits_for_contact_to_be_released() {
+        let first = TouchRecord::new(1, 10, 10);
+        let second = TouchRecord::new(2, 50, 50);
+        let mut driver = touch_driver(&[&[first.with_pressure(5), second], &[second]]);
+
+        let mut tracker = tracker::Tracker::<4>::new(tracker::TrackerConfig::default());
+        tracker.update(
+            &Event::touch(0, &[first, second]),
+            time::Instant::from_millis(0),
+        );
+        let handle = tracker.contact(1).map(|contact| contact.handle());
+
+        let released = mock::block_on(driver.wait_released(&mut tracker, handle.unwrap(), || {
+            time::Instant::from_millis(40)
+        }));
+        match released {
+            Ok(Some((record, stats))) => {
+                assert!(record == first.with_pressure(5));
+                assert_eq!(stats.duration_ms, 40);
+            }
+            _ => panic!("expected the contact to be released"),
+        }
+        assert!(tracker.contact(1).is_none());
+        assert!(tracker.contact(2).is_some());
+    }
+
+    #[test]
+    fn releases_peripherals_once_asleep() {
+        let driver = touch_driver(&[]);
+
+        let released = mock::block_on(driver.release_sleeping());
+        match released {
+            Ok((bus, _)) => assert_eq!(
+                bus.transactions,
+                [[mock::Op::Write(SLEEP_COMMAND.to_vec())]]
+            ),
+            _ => panic!("expected the peripherals to be released"),
+        }
+
+        let mut driver = touch_driver(&[]);
+        assert!(mock::block_on(driver.sleep()).is_ok());
+        match mock::block_on(driver.release_sleeping()) {
+            Ok((bus, _)) => assert_eq!(bus.transactions.len(), 1),
+            _ => panic!("expected the peripherals to be released"),
+        }
+    }
+
+    #[test]
+    fn initializes_and_reads_events() {
+        let record = TouchRecord::new(1, 10, 20);
+        let mut driver = touch_driver(&[&[record]]);
+        driver.i2c.messages.push_front(std::vec![2, 0]);
+
+        assert!(mock::block_on(driver.init()).is_ok());
+        assert!(driver.is_initialized());
+
+        match mock::block_on(driver.wait_for_event()) {
+            Ok(event) => assert!(event.touches().eq([record])),
+            _ => panic!("expected a touch event"),
+        }
+        assert!(matches!(
+            mock::block_on(driver.event()),
+            Err(Error::NoDataAvailable)
+        ));
+    }
+
+    #[test]
+    fn validates_events_like_blocking_driver() {
+        let mut driver = touch_driver(&[&[TouchRecord::new(1, 400, 300)]]);
+        driver.set_panel(PanelProfile::RES_320X240);
+
+        match mock::block_on(driver.event()) {
+            Ok(event) => assert!(event.touches().eq([TouchRecord::new(1, 319, 239)])),
+            _ => panic!("expected a touch event"),
+        }
+        assert_eq!(driver.rejected_records(), 0);
+    }
+
+    #[cfg(feature = "stream")]
+    #[test]
+    fn streams_events_read_from_device() {
+        use futures_util::StreamExt;
+
+        let first = TouchRecord::new(1, 10, 20);
+        let second = TouchRecord::new(1, 12, 20);
+        let mut driver = touch_driver(&[&[first], &[second]]);
+
+        let events = driver.events();
+        futures_util::pin_mut!(events);
+        match mock::block_on(events.next()) {
+            Some(Ok(event)) => assert!(event.touches().eq([first])),
+            _ => panic!("expected a touch event"),
+        }
+
+        let events = touch_driver(&[&[second]]).into_stream();
+        futures_util::pin_mut!(events);
+        match mock::block_on(events.next()) {
+            Some(Ok(event)) => assert!(event.touches().eq([second])),
+            _ => panic!("expected a touch event"),
+        }
+    }
+
+    #[test]
+    fn verifies_checksums_of_responses() {
+        let mut response = mock::response(0x0A, &[7]);
+        let mut driver = touch_driver(&[]);
+        driver.i2c.messages.push_back(response.clone());
+
+        match mock::block_on(driver.write_command(0x0A, &[])) {
+            Ok(response) => assert_eq!(response.data(), [7]),
+            _ => panic!("expected a response"),
+        }
+
+        response[5] ^= 0x01;
+        driver.i2c.messages.push_back(response);
+
+        assert!(matches!(
+            mock::block_on(driver.write_command(0x0A, &[])),
+            Err(Error::ChecksumMismatch)
+        ));
+    }
+}
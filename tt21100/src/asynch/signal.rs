@@ -0,0 +1,61 @@
+//! Use of an externally raised signal in place of the interrupt pin
+
+use core::convert::Infallible;
+
+use embassy_sync::{blocking_mutex::raw::RawMutex, signal::Signal};
+
+use super::source::EventSource;
+
+/// An event source backed by an [embassy_sync::signal::Signal]
+///
+/// For use on boards where the interrupt pin cannot be handed to the driver,
+/// for example because it is shared with other logic. The user's own interrupt
+/// handler should call [Signal::signal] when the line is asserted.
+pub struct SignalIrq<'a, M>
+where
+    M: RawMutex,
+{
+    signal: &'a Signal<M, ()>,
+}
+
+impl<'a, M> SignalIrq<'a, M>
+where
+    M: RawMutex,
+{
+    /// Create a new event source from the given signal
+    pub fn new(signal: &'a Signal<M, ()>) -> Self {
+        Self { signal }
+    }
+}
+
+impl<M> EventSource for SignalIrq<'_, M>
+where
+    M: RawMutex,
+{
+    type Error = Infallible;
+
+    async fn wait(&mut self) -> Result<(), Self::Error> {
+        self.signal.wait().await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+
+    use super::*;
+    use crate::mock;
+
+    #[test]
+    fn waits_for_raised_signal() {
+        let signal = Signal::<NoopRawMutex, ()>::new();
+        let mut source = SignalIrq::new(&signal);
+
+        signal.signal(());
+
+        assert!(mock::block_on(source.wait()).is_ok());
+        assert!(!signal.signaled());
+        assert!(mock::block_on(source.wait_for_release()).is_ok());
+    }
+}
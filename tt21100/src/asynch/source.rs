@@ -0,0 +1,185 @@
+//! Sources of notification that data may be available to read
+
+use embedded_hal::digital::InputPin;
+use embedded_hal_async::{delay::DelayNs, digital::Wait};
+
+/// Something which can notify the driver that data may be available to read
+/// from the device
+///
+/// Any pin implementing [Wait] is an event source, and is considered to be
+/// notifying the driver for as long as it is held low.
+#[allow(async_fn_in_trait)]
+pub trait EventSource {
+    /// Error type returned while waiting for a notification
+    type Error;
+
+    /// Wait until data may be available to read from the device
+    async fn wait(&mut self) -> Result<(), Self::Error>;
+
+    /// Wait until a notification has been released, ahead of waiting for the
+    /// next
+    ///
+    /// Level-triggered sources remain notifying until the message which
+    /// raised the notification has been read, so waiting on them again
+    /// beforehand would return immediately. Other sources return at once.
+    async fn wait_for_release(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl<P> EventSource for P
+where
+    P: Wait,
+{
+    type Error = P::Error;
+
+    async fn wait(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_low().await
+    }
+
+    async fn wait_for_release(&mut self) -> Result<(), Self::Error> {
+        self.wait_for_high().await
+    }
+}
+
+/// The edge(s) of the interrupt line which signal that data is available
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub enum Edge {
+    /// The line transitions from high to low when data becomes available
+    Falling,
+    /// The line toggles when data becomes available, e.g. when latched through
+    /// external logic
+    Any,
+}
+
+/// An edge-triggered interrupt pin
+///
+/// When waiting for a falling edge, the level of the pin is checked first, as
+/// no further edge will occur if data remained available after the previous
+/// read.
+pub struct EdgeTriggered<P> {
+    pin: P,
+    edge: Edge,
+}
+
+impl<P> EdgeTriggered<P>
+where
+    P: Wait + InputPin,
+{
+    /// Create a new edge-triggered event source from the given pin
+    pub fn new(pin: P, edge: Edge) -> Self {
+        Self { pin, edge }
+    }
+
+    /// Release the underlying pin
+    pub fn release(self) -> P {
+        self.pin
+    }
+}
+
+impl<P> EventSource for EdgeTriggered<P>
+where
+    P: Wait + InputPin,
+{
+    type Error = P::Error;
+
+    async fn wait(&mut self) -> Result<(), Self::Error> {
+        match self.edge {
+            Edge::Falling if self.pin.is_low()? => Ok(()),
+            Edge::Falling => self.pin.wait_for_falling_edge().await,
+            Edge::Any => self.pin.wait_for_any_edge().await,
+        }
+    }
+
+    async fn wait_for_release(&mut self) -> Result<(), Self::Error> {
+        match self.edge {
+            // The level of the pin is checked when waiting for a falling edge
+            Edge::Falling => self.pin.wait_for_high().await,
+            Edge::Any => Ok(()),
+        }
+    }
+}
+
+/// An event source which fires periodically, for polling the device when no
+/// interrupt line is available
+pub struct Periodic<D> {
+    delay: D,
+    interval_us: u32,
+}
+
+impl<D> Periodic<D>
+where
+    D: DelayNs,
+{
+    /// Create a new event source firing every `interval_us` microseconds
+    pub fn new(delay: D, interval_us: u32) -> Self {
+        Self { delay, interval_us }
+    }
+}
+
+impl<D> EventSource for Periodic<D>
+where
+    D: DelayNs,
+{
+    type Error = core::convert::Infallible;
+
+    async fn wait(&mut self) -> Result<(), Self::Error> {
+        self.delay.delay_us(self.interval_us).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::{self, PinWait};
+
+    #[test]
+    fn checks_level_before_waiting_for_falling_edge() {
+        let mut source = EdgeTriggered::new(
+            mock::Pin {
+                low: true,
+                ..mock::Pin::default()
+            },
+            Edge::Falling,
+        );
+
+        assert!(mock::block_on(source.wait()).is_ok());
+        assert!(mock::block_on(source.wait_for_release()).is_ok());
+        assert!(mock::block_on(source.wait()).is_ok());
+        assert_eq!(
+            source.release().waits,
+            [PinWait::High, PinWait::FallingEdge]
+        );
+    }
+
+    #[test]
+    fn waits_for_any_edge_of_latched_lines() {
+        let mut source = EdgeTriggered::new(mock::Pin::default(), Edge::Any);
+
+        assert!(mock::block_on(source.wait()).is_ok());
+        assert!(mock::block_on(source.wait_for_release()).is_ok());
+        assert!(mock::block_on(source.wait()).is_ok());
+        assert_eq!(source.release().waits, [PinWait::AnyEdge, PinWait::AnyEdge]);
+    }
+
+    #[test]
+    fn waits_on_level_of_interrupt_pins() {
+        let mut pin = mock::Pin::default();
+
+        assert!(mock::block_on(EventSource::wait(&mut pin)).is_ok());
+        assert!(pin.low);
+        assert!(mock::block_on(EventSource::wait_for_release(&mut pin)).is_ok());
+        assert_eq!(pin.waits, [PinWait::Low, PinWait::High]);
+    }
+
+    #[test]
+    fn fires_periodically() {
+        let mut source = Periodic::new(mock::Delay::default(), 2_500);
+
+        assert!(mock::block_on(source.wait()).is_ok());
+        assert!(mock::block_on(source.wait()).is_ok());
+        assert_eq!(source.delay.elapsed_ns, 5_000_000);
+    }
+}
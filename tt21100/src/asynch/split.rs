@@ -0,0 +1,158 @@
+//! Splitting the driver for use across multiple cores
+//!
+//! On dual-core targets such as the ESP32-S3 it can be desirable to wait for
+//! the interrupt line on one core while servicing the I²C bus on the other.
+//! [TT21100::split] separates the driver into a [Readiness] half, which owns
+//! the interrupt line, and a [Bus] half, which owns the I²C peripheral and
+//! waits on a shared [Signal] in place of the interrupt line.
+//!
+//! Each half is [Send] whenever the peripheral it owns is, and the signal is
+//! shared by reference, so the halves may be moved to executors running on
+//! different cores. The signal must be [Sync] for this to be the case; use a
+//! [CriticalSectionRawMutex] together with a multi-core aware
+//! `critical-section` implementation.
+//!
+//! [CriticalSectionRawMutex]: embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex
+
+use embassy_sync::{blocking_mutex::raw::RawMutex, signal::Signal};
+
+use super::{signal::SignalIrq, source::EventSource, TT21100};
+use crate::{geom::NoRotation, NoReset};
+
+/// Bus half of a split driver
+///
+/// Owns the I²C peripheral, waiting on the shared signal in place of the
+/// interrupt line.
+pub type Bus<'a, I2C, M, DELAY, RST = NoReset, ORIENT = NoRotation> =
+    TT21100<I2C, SignalIrq<'a, M>, DELAY, RST, ORIENT>;
+
+/// Readiness half of a split driver
+///
+/// Waits for the interrupt line to be asserted, notifying the bus half each
+/// time that it is.
+pub struct Readiness<'a, M, IRQ>
+where
+    M: RawMutex,
+{
+    irq: IRQ,
+    signal: &'a Signal<M, ()>,
+}
+
+impl<'a, M, IRQ> Readiness<'a, M, IRQ>
+where
+    M: RawMutex,
+    IRQ: EventSource,
+{
+    /// Wait for the interrupt line to be asserted once, then notify the bus
+    /// half
+    ///
+    /// Returns once the line has been released, which for a level-triggered
+    /// line is when the bus half has read the message which asserted it, so
+    /// that the bus half is not notified repeatedly for the same message.
+    pub async fn wait(&mut self) -> Result<(), IRQ::Error> {
+        self.irq.wait().await?;
+        self.signal.signal(());
+
+        self.irq.wait_for_release().await
+    }
+
+    /// Notify the bus half each time the interrupt line is asserted, returning
+    /// only if waiting for the interrupt line fails
+    pub async fn run(&mut self) -> IRQ::Error {
+        loop {
+            if let Err(e) = self.wait().await {
+                return e;
+            }
+        }
+    }
+}
+
+impl<I2C, IRQ, DELAY, RST, ORIENT> TT21100<I2C, IRQ, DELAY, RST, ORIENT> {
+    /// Split the driver into a readiness half and a bus half, which
+    /// communicate using the provided signal
+    ///
+    /// Any notification already present in the signal is cleared.
+    #[allow(clippy::type_complexity)]
+    pub fn split<M>(
+        self,
+        signal: &Signal<M, ()>,
+    ) -> (Readiness<'_, M, IRQ>, Bus<'_, I2C, M, DELAY, RST, ORIENT>)
+    where
+        M: RawMutex,
+    {
+        signal.reset();
+        let (bus, irq) = self.replace_irq(SignalIrq::new(signal));
+
+        (Readiness { irq, signal }, bus)
+    }
+
+    // -----------------------------------------------------------------------
+    // PRIVATE
+
+    fn replace_irq<T>(self, irq: T) -> (TT21100<I2C, T, DELAY, RST, ORIENT>, IRQ) {
+        let driver = TT21100 {
+            i2c: self.i2c,
+            irq,
+            reset: self.reset,
+            orientation: self.orientation,
+            state: self.state,
+            delay: self.delay,
+        };
+
+        (driver, self.irq)
+    }
+}
+
+impl<'a, I2C, M, DELAY, RST, ORIENT> Bus<'a, I2C, M, DELAY, RST, ORIENT>
+where
+    M: RawMutex,
+{
+    /// Recombine the two halves of a split driver
+    pub fn unsplit<IRQ>(
+        self,
+        readiness: Readiness<'a, M, IRQ>,
+    ) -> TT21100<I2C, IRQ, DELAY, RST, ORIENT> {
+        self.replace_irq(readiness.irq).0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+
+    use super::*;
+    use crate::{
+        mock::{self, PinWait},
+        tests::touch_message,
+        TouchRecord,
+        TouchReport,
+    };
+
+    #[test]
+    fn notifies_bus_half_of_asserted_line() {
+        let record = TouchRecord::new(1, 10, 20);
+        let (message, length) = touch_message(TouchReport::new(0, 1), &[record]);
+        let driver = TT21100::new(
+            mock::Bus::new(&[&message[0..][..length]]),
+            mock::Pin {
+                low: true,
+                ..mock::Pin::default()
+            },
+        );
+
+        let signal = Signal::<NoopRawMutex, ()>::new();
+        signal.signal(());
+        let (mut readiness, mut bus) = driver.split(&signal);
+        assert!(!signal.signaled());
+
+        assert!(mock::block_on(readiness.wait()).is_ok());
+        assert!(signal.signaled());
+        assert_eq!(readiness.irq.waits, [PinWait::Low, PinWait::High]);
+
+        match mock::block_on(bus.wait_for_event()) {
+            Ok(event) => assert!(event.touches().eq([record])),
+            Err(_) => panic!("expected a touch event"),
+        }
+        assert!(!signal.signaled());
+    }
+}
@@ -0,0 +1,199 @@
+//! On-target micro-benchmarks
+//!
+//! Each benchmark is measured using a cycle counter provided by the caller,
+//! for example the DWT cycle counter on Cortex-M or the `CCOUNT` register on
+//! Xtensa. The counter is permitted to wrap around.
+//!
+//! The `bench` feature requires Rust 1.66 or later.
+
+#![allow(clippy::incompatible_msrv)]
+
+use core::{fmt::Debug, hint::black_box};
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{InputPin, OutputPin},
+    i2c::I2c,
+};
+
+#[cfg(feature = "async")]
+use crate::asynch::{self, source::EventSource};
+#[cfg(feature = "filter")]
+use crate::filter::{Smoother, TouchFilter};
+use crate::{button_event, geom::OrientationProvider, touch_event, TT21100};
+
+/// A touch message containing two touch records
+const TOUCH_MESSAGE: [u8; 27] = [
+    27, 0, 1, 0x10, 0x27, 0x02, 0x00, // report
+    0x00, 0xC0, 0x40, 0x01, 0xF0, 0x00, 0x20, 0x08, 0x00, 0x00, // record 0
+    0x00, 0xC1, 0x80, 0x00, 0x78, 0x00, 0x18, 0x06, 0x00, 0x00, // record 1
+];
+
+/// A button message
+const BUTTON_MESSAGE: [u8; 14] = [14, 0, 3, 0x10, 0x27, 0x01, 0, 1, 0, 2, 0, 3, 0, 4];
+
+/// Results of running a benchmark, in cycles
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BenchResult {
+    /// Number of iterations measured
+    pub iterations: u32,
+    /// Fewest cycles taken by a single iteration
+    pub min: u32,
+    /// Most cycles taken by a single iteration
+    pub max: u32,
+    /// Combined cycles taken by all iterations
+    pub total: u64,
+}
+
+impl BenchResult {
+    /// Average cycles taken by a single iteration
+    pub fn average(&self) -> u32 {
+        if self.iterations == 0 {
+            return 0;
+        }
+
+        (self.total / u64::from(self.iterations)) as u32
+    }
+
+    fn record(&mut self, cycles: u32) {
+        self.min = if self.iterations == 0 {
+            cycles
+        } else {
+            self.min.min(cycles)
+        };
+        self.max = self.max.max(cycles);
+        self.total += u64::from(cycles);
+        self.iterations += 1;
+    }
+}
+
+/// Measure the cycles taken by each of `iterations` calls to `f`
+pub fn measure<F>(cycles: fn() -> u32, iterations: u32, mut f: F) -> BenchResult
+where
+    F: FnMut(),
+{
+    let mut result = BenchResult::default();
+    for _ in 0..iterations {
+        let start = cycles();
+        f();
+        result.record(cycles().wrapping_sub(start));
+    }
+
+    result
+}
+
+/// Measure the cycles taken to parse touch and button messages
+pub fn parse(cycles: fn() -> u32, iterations: u32) -> BenchResult {
+    measure(cycles, iterations, || {
+        let _ = black_box(touch_event::<()>(black_box(&TOUCH_MESSAGE)));
+        let _ = black_box(button_event::<()>(black_box(&BUTTON_MESSAGE)));
+    })
+}
+
+/// Measure the cycles taken to smooth a touch event using the given filter
+#[cfg(feature = "filter")]
+pub fn filter<F>(cycles: fn() -> u32, iterations: u32, filter: F) -> BenchResult
+where
+    F: TouchFilter + Clone,
+{
+    let event = match touch_event::<()>(&TOUCH_MESSAGE) {
+        Ok(event) => event,
+        Err(_) => return BenchResult::default(),
+    };
+
+    let mut smoother = Smoother::new(filter);
+    measure(cycles, iterations, || {
+        black_box(smoother.update(black_box(&event)));
+    })
+}
+
+/// Measure the cycles taken to service an event end-to-end, from reading the
+/// message from the device through to its validation
+///
+/// Iterations in which no event could be read are still measured.
+pub fn service<I2C, IRQ, DELAY, RST, ORIENT, E>(
+    cycles: fn() -> u32,
+    iterations: u32,
+    driver: &mut TT21100<I2C, IRQ, DELAY, RST, ORIENT>,
+) -> BenchResult
+where
+    I2C: I2c<Error = E>,
+    IRQ: InputPin,
+    DELAY: DelayNs,
+    RST: OutputPin,
+    ORIENT: OrientationProvider,
+    E: Debug,
+{
+    measure(cycles, iterations, || {
+        let _ = black_box(driver.event());
+    })
+}
+
+/// Measure the cycles taken to service an event end-to-end using the async
+/// driver, from reading the message from the device through to its validation
+///
+/// Iterations in which no event could be read are still measured.
+#[cfg(feature = "async")]
+pub async fn service_async<I2C, IRQ, DELAY, RST, ORIENT, E>(
+    cycles: fn() -> u32,
+    iterations: u32,
+    driver: &mut asynch::TT21100<I2C, IRQ, DELAY, RST, ORIENT>,
+) -> BenchResult
+where
+    I2C: embedded_hal_async::i2c::I2c<Error = E>,
+    IRQ: EventSource,
+    DELAY: embedded_hal_async::delay::DelayNs,
+    RST: OutputPin,
+    ORIENT: OrientationProvider,
+    E: Debug,
+{
+    let mut result = BenchResult::default();
+    for _ in 0..iterations {
+        let start = cycles();
+        let _ = black_box(driver.event().await);
+        result.record(cycles().wrapping_sub(start));
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn measures_wrapping_cycle_counts() {
+        static CYCLES: AtomicU32 = AtomicU32::new(u32::MAX - 10);
+        fn cycles() -> u32 {
+            CYCLES.load(Ordering::Relaxed)
+        }
+
+        let mut steps = [30, 10, 20].into_iter();
+        let result = measure(cycles, 3, || {
+            CYCLES.fetch_add(steps.next().unwrap_or(0), Ordering::Relaxed);
+        });
+
+        assert_eq!(
+            result,
+            BenchResult {
+                iterations: 3,
+                min: 10,
+                max: 30,
+                total: 60,
+            }
+        );
+        assert_eq!(result.average(), 20);
+        assert_eq!(BenchResult::default().average(), 0);
+    }
+
+    #[test]
+    fn benchmarks_valid_messages() {
+        match touch_event::<()>(&TOUCH_MESSAGE) {
+            Ok(event) => assert_eq!(event.touches().count(), 2),
+            Err(_) => panic!("expected a touch event"),
+        }
+        assert!(button_event::<()>(&BUTTON_MESSAGE).is_ok());
+    }
+}
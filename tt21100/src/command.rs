@@ -0,0 +1,282 @@
+//! Commands of the vendor protocol
+//!
+//! Beyond the power commands written to the command register, the device
+//! accepts commands written to its output register as reports carrying a
+//! command code, any parameters, and a CRC. Each command is acknowledged by a
+//! response report, which is read from the device like any other message. The
+//! format follows that of Parade's TrueTouch host protocol, which the TT21100
+//! implements.
+
+use crate::MAX_MESSAGE_LEN;
+
+/// Output register, to which commands are written
+const OUTPUT_REGISTER: u16 = 0x0004;
+
+/// ID of the report carrying a command
+const COMMAND_REPORT_ID: u8 = 0x2F;
+
+/// ID of the report carrying the response to a command
+pub(crate) const RESPONSE_REPORT_ID: u8 = 0x1F;
+
+/// Greatest number of parameters carried by a command
+pub const MAX_COMMAND_PARAMETERS: usize = 16;
+
+/// Length of a command without any parameters: the output register, the
+/// length, the report ID, a reserved byte, the command code, and the CRC
+const COMMAND_OVERHEAD: usize = 9;
+
+/// Command which arms the device to wake on a gesture, entering a low-power
+/// state until it is performed
+pub(crate) const ENTER_EASY_WAKE: u8 = 0x09;
+
+/// Gesture which wakes the device from sleep
+///
+/// Once armed using [TT21100::sleep_until](crate::TT21100::sleep_until), the
+/// device asserts the interrupt line when the gesture is performed, so that a
+/// sleeping host may be woken by it.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub enum WakeGesture {
+    /// Any touch of the panel
+    Touch,
+    /// Two taps in quick succession
+    DoubleTap,
+    /// Two fingers slid across the panel together
+    TwoFingerSlide,
+    /// A gesture identified by its ID, for firmware supporting others
+    Other(u8),
+}
+
+impl From<WakeGesture> for u8 {
+    fn from(gesture: WakeGesture) -> Self {
+        match gesture {
+            WakeGesture::Touch => 0x00,
+            WakeGesture::DoubleTap => 0x01,
+            WakeGesture::TwoFingerSlide => 0x02,
+            WakeGesture::Other(id) => id,
+        }
+    }
+}
+
+/// A command, serialized ready to be written to the device
+pub(crate) struct Command {
+    bytes: [u8; COMMAND_OVERHEAD + MAX_COMMAND_PARAMETERS],
+    len: usize,
+}
+
+impl Command {
+    /// Serialize the command with the given code and parameters
+    ///
+    /// Returns `None` if there are more than [MAX_COMMAND_PARAMETERS]
+    /// parameters.
+    pub(crate) fn new(code: u8, parameters: &[u8]) -> Option<Self> {
+        if parameters.len() > MAX_COMMAND_PARAMETERS {
+            return None;
+        }
+        let len = COMMAND_OVERHEAD + parameters.len();
+
+        let mut bytes = [0u8; COMMAND_OVERHEAD + MAX_COMMAND_PARAMETERS];
+        bytes[0..2].copy_from_slice(&OUTPUT_REGISTER.to_le_bytes());
+        // The length of the report excludes the output register
+        bytes[2..4].copy_from_slice(&((len - 2) as u16).to_le_bytes());
+        bytes[4] = COMMAND_REPORT_ID;
+        bytes[6] = code;
+        bytes[7..][..parameters.len()].copy_from_slice(parameters);
+
+        let crc = crc16(&bytes[2..len - 2]);
+        bytes[len - 2..len].copy_from_slice(&crc.to_be_bytes());
+
+        Some(Self { bytes, len })
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.bytes[0..][..self.len]
+    }
+}
+
+/// Response to a command, as read from the device
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct Response {
+    bytes: [u8; MAX_MESSAGE_LEN],
+    len: usize,
+}
+
+impl Response {
+    pub(crate) fn new() -> Self {
+        Self {
+            bytes: [0; MAX_MESSAGE_LEN],
+            len: 0,
+        }
+    }
+
+    /// The complete response, beginning with its length
+    ///
+    /// Responses longer than the longest message read by the driver are
+    /// truncated.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[0..][..self.len]
+    }
+
+    /// Data carried by the response, between the echoed command code and the
+    /// CRC
+    pub fn data(&self) -> &[u8] {
+        self.bytes.get(5..self.len.saturating_sub(2)).unwrap_or(&[])
+    }
+
+    /// Buffer into which a message of the given length is read
+    pub(crate) fn buffer(&mut self, len: usize) -> &mut [u8] {
+        self.len = len.min(MAX_MESSAGE_LEN);
+        &mut self.bytes[0..][..self.len]
+    }
+
+    /// Does the CRC carried by the response match its contents?
+    ///
+    /// Responses which were truncated cannot be checked, and are assumed to
+    /// be valid.
+    pub(crate) fn has_valid_crc(&self) -> bool {
+        let len = usize::from(u16::from_le_bytes([self.bytes[0], self.bytes[1]]));
+        if len > self.len {
+            return true;
+        }
+
+        match self.as_bytes().len().checked_sub(2) {
+            Some(end) if end >= 2 => {
+                let (data, crc) = self.as_bytes().split_at(end);
+                crc16(data) == u16::from_be_bytes([crc[0], crc[1]])
+            }
+            _ => false,
+        }
+    }
+
+    /// Truncate the response to the length of the message read into it
+    pub(crate) fn truncate(&mut self) {
+        let len = usize::from(u16::from_le_bytes([self.bytes[0], self.bytes[1]]));
+        self.len = self.len.min(len);
+    }
+}
+
+/// Is `message` the response to the command with the given code?
+pub(crate) fn is_response(message: &[u8], code: u8) -> bool {
+    // The top bit of the echoed command code toggles with each response
+    message.get(2) == Some(&RESPONSE_REPORT_ID)
+        && message.get(4).map_or(false, |echo| echo & 0x7F == code)
+}
+
+/// CRC-16/CCITT-FALSE of `data`, as used by the vendor protocol
+pub(crate) fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0xFFFF, |crc, &byte| {
+        let mut crc = crc ^ (u16::from(byte) << 8);
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+
+        crc
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock;
+
+    /// Read a message into a response, as the driver does
+    fn read(message: &[u8]) -> Response {
+        let mut response = Response::new();
+        let buffer = response.buffer(MAX_MESSAGE_LEN);
+        let len = buffer.len().min(message.len());
+        buffer[0..][..len].copy_from_slice(&message[0..][..len]);
+        response.truncate();
+
+        response
+    }
+
+    #[test]
+    fn accepts_valid_crc() {
+        let response = read(&mock::response(ENTER_EASY_WAKE, &[1, 2, 3]));
+
+        assert!(response.has_valid_crc());
+    }
+
+    #[test]
+    fn rejects_corrupted_crc() {
+        let mut message = mock::response(ENTER_EASY_WAKE, &[1, 2, 3]);
+        message[6] ^= 0x80;
+
+        assert!(!read(&message).has_valid_crc());
+    }
+
+    #[test]
+    fn does_not_check_truncated_responses() {
+        let mut message = mock::response(ENTER_EASY_WAKE, &[0; MAX_MESSAGE_LEN]);
+        message[5] ^= 0x80;
+
+        assert!(read(&message).has_valid_crc());
+    }
+
+    #[test]
+    fn encodes_wake_gestures() {
+        assert_eq!(u8::from(WakeGesture::Touch), 0x00);
+        assert_eq!(u8::from(WakeGesture::DoubleTap), 0x01);
+        assert_eq!(u8::from(WakeGesture::TwoFingerSlide), 0x02);
+        assert_eq!(u8::from(WakeGesture::Other(0x7F)), 0x7F);
+    }
+
+    #[test]
+    fn computes_crc16_ccitt_false() {
+        assert_eq!(crc16(b""), 0xFFFF);
+        assert_eq!(crc16(b"123456789"), 0x29B1);
+    }
+
+    #[test]
+    fn frames_commands_for_output_register() {
+        let command = Command::new(ENTER_EASY_WAKE, &[0x01, 0x02]).unwrap();
+        let bytes = command.as_bytes();
+
+        assert_eq!(bytes.len(), COMMAND_OVERHEAD + 2);
+        assert_eq!(bytes[0..2], OUTPUT_REGISTER.to_le_bytes());
+        assert_eq!(bytes[2..4], 9u16.to_le_bytes());
+        assert_eq!(
+            bytes[4..9],
+            [COMMAND_REPORT_ID, 0, ENTER_EASY_WAKE, 0x01, 0x02]
+        );
+        assert_eq!(bytes[9..11], crc16(&bytes[2..9]).to_be_bytes());
+    }
+
+    #[test]
+    fn rejects_commands_with_too_many_parameters() {
+        assert!(Command::new(ENTER_EASY_WAKE, &[0; MAX_COMMAND_PARAMETERS]).is_some());
+        assert!(Command::new(ENTER_EASY_WAKE, &[0; MAX_COMMAND_PARAMETERS + 1]).is_none());
+    }
+
+    #[test]
+    fn identifies_responses_to_commands() {
+        let mut message = mock::response(ENTER_EASY_WAKE, &[]);
+        assert!(is_response(&message, ENTER_EASY_WAKE));
+        assert!(!is_response(&message, 0x0A));
+
+        // The top bit of the echoed code toggles between responses
+        message[4] |= 0x80;
+        assert!(is_response(&message, ENTER_EASY_WAKE));
+
+        message[2] = 0x01;
+        assert!(!is_response(&message, ENTER_EASY_WAKE));
+        assert!(!is_response(&[2, 0], ENTER_EASY_WAKE));
+    }
+
+    #[test]
+    fn reads_data_of_responses() {
+        assert_eq!(
+            read(&mock::response(ENTER_EASY_WAKE, &[1, 2, 3])).data(),
+            [1, 2, 3]
+        );
+        assert!(read(&mock::response(ENTER_EASY_WAKE, &[]))
+            .data()
+            .is_empty());
+        assert!(Response::new().data().is_empty());
+    }
+}
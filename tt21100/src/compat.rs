@@ -0,0 +1,278 @@
+//! Adapters allowing `embedded-hal` 0.2 peripherals to be used with the
+//! driver
+//!
+//! Each adapter wraps a peripheral implementing the 0.2 traits, and
+//! implements the corresponding 1.0 traits required by
+//! [TT21100](crate::TT21100).
+
+use core::fmt::Debug;
+
+use eh0::{
+    blocking::{
+        delay::DelayUs,
+        i2c::{Read, Write, WriteRead},
+    },
+    digital::v2::InputPin,
+};
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{self, ErrorKind as PinErrorKind},
+    i2c::{self, ErrorKind as BusErrorKind, Operation},
+};
+
+/// Greatest number of bytes which may be written by a single transaction
+/// made up of more than one write, once the writes have been joined
+pub const MAX_JOINED_WRITE_LEN: usize = 64;
+
+/// Error reported by an adapter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error<E> {
+    /// Error reported by the wrapped peripheral
+    ///
+    /// The 0.2 traits place no bounds on their errors, so the error cannot be
+    /// classified further.
+    Peripheral(E),
+    /// The transaction cannot be performed using the 0.2 traits, either
+    /// because of the order of its operations or because its writes, once
+    /// joined, exceed [MAX_JOINED_WRITE_LEN]
+    UnsupportedTransaction,
+}
+
+impl<E> i2c::Error for Error<E>
+where
+    E: Debug,
+{
+    fn kind(&self) -> BusErrorKind {
+        BusErrorKind::Other
+    }
+}
+
+impl<E> digital::Error for Error<E>
+where
+    E: Debug,
+{
+    fn kind(&self) -> PinErrorKind {
+        PinErrorKind::Other
+    }
+}
+
+/// Adapter for an `embedded-hal` 0.2 I²C bus
+///
+/// The 0.2 traits offer no way to combine arbitrary operations, so only those
+/// transactions which they can express are supported: any number of writes,
+/// optionally followed by a single read. Adjacent writes are joined into a
+/// single write, and a read following a write is performed using
+/// [WriteRead]. Any other transaction results in
+/// [Error::UnsupportedTransaction], rather than being split into several.
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct I2c<T>(pub T);
+
+impl<T, E> i2c::ErrorType for I2c<T>
+where
+    T: Read<Error = E> + Write<Error = E> + WriteRead<Error = E>,
+    E: Debug,
+{
+    type Error = Error<E>;
+}
+
+impl<T, E> i2c::I2c for I2c<T>
+where
+    T: Read<Error = E> + Write<Error = E> + WriteRead<Error = E>,
+    E: Debug,
+{
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let (read, writes) = match operations.split_last_mut() {
+            Some((Operation::Read(buffer), writes)) => (Some(buffer), writes),
+            _ => (None, operations),
+        };
+
+        let mut joined = [0u8; MAX_JOINED_WRITE_LEN];
+        let bytes = match writes {
+            [] => None,
+            [Operation::Write(bytes)] => Some(&bytes[..]),
+            writes => Some(join(writes, &mut joined)?),
+        };
+
+        match (bytes, read) {
+            (None, None) => Ok(()),
+            (Some(bytes), None) => self.0.write(address, bytes),
+            (None, Some(buffer)) => self.0.read(address, buffer),
+            (Some(bytes), Some(buffer)) => self.0.write_read(address, bytes, buffer),
+        }
+        .map_err(Error::Peripheral)
+    }
+}
+
+/// Join adjacent writes into a single buffer, returning the bytes written
+fn join<'a, E>(
+    writes: &[Operation<'_>],
+    buffer: &'a mut [u8; MAX_JOINED_WRITE_LEN],
+) -> Result<&'a [u8], Error<E>> {
+    let mut len = 0;
+    for operation in writes {
+        let bytes = match operation {
+            Operation::Write(bytes) => bytes,
+            Operation::Read(_) => return Err(Error::UnsupportedTransaction),
+        };
+        buffer
+            .get_mut(len..len + bytes.len())
+            .ok_or(Error::UnsupportedTransaction)?
+            .copy_from_slice(bytes);
+        len += bytes.len();
+    }
+
+    Ok(&buffer[..len])
+}
+
+/// Adapter for an `embedded-hal` 0.2 input pin
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct Pin<T>(pub T);
+
+impl<T, E> digital::ErrorType for Pin<T>
+where
+    T: InputPin<Error = E>,
+    E: Debug,
+{
+    type Error = Error<E>;
+}
+
+impl<T, E> digital::InputPin for Pin<T>
+where
+    T: InputPin<Error = E>,
+    E: Debug,
+{
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        self.0.is_high().map_err(Error::Peripheral)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        self.0.is_low().map_err(Error::Peripheral)
+    }
+}
+
+/// Adapter for an `embedded-hal` 0.2 delay
+///
+/// Delays are rounded up to the nearest microsecond.
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct Delay<T>(pub T);
+
+impl<T> DelayNs for Delay<T>
+where
+    T: DelayUs<u32>,
+{
+    fn delay_ns(&mut self, ns: u32) {
+        self.0.delay_us(ns / 1_000 + u32::from(ns % 1_000 != 0));
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        self.0.delay_us(us);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::{vec, vec::Vec};
+
+    use embedded_hal::i2c::I2c as _;
+
+    use super::*;
+
+    /// A call made to the wrapped bus
+    #[derive(Debug, PartialEq, Eq)]
+    enum Call {
+        Read(usize),
+        Write(Vec<u8>),
+        WriteRead(Vec<u8>, usize),
+    }
+
+    /// A 0.2 bus recording the calls made to it
+    #[derive(Default)]
+    struct Bus(Vec<Call>);
+
+    impl Read for Bus {
+        type Error = ();
+
+        fn read(&mut self, _address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+            self.0.push(Call::Read(buffer.len()));
+            Ok(())
+        }
+    }
+
+    impl Write for Bus {
+        type Error = ();
+
+        fn write(&mut self, _address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+            self.0.push(Call::Write(bytes.to_vec()));
+            Ok(())
+        }
+    }
+
+    impl WriteRead for Bus {
+        type Error = ();
+
+        fn write_read(
+            &mut self,
+            _address: u8,
+            bytes: &[u8],
+            buffer: &mut [u8],
+        ) -> Result<(), Self::Error> {
+            self.0.push(Call::WriteRead(bytes.to_vec(), buffer.len()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn maps_write_then_read_onto_write_read() {
+        let mut i2c = I2c(Bus::default());
+
+        i2c.write_read(0x24, &[1, 2], &mut [0; 4]).unwrap();
+
+        assert_eq!(i2c.0 .0, vec![Call::WriteRead(vec![1, 2], 4)]);
+    }
+
+    #[test]
+    fn joins_adjacent_writes() {
+        let mut i2c = I2c(Bus::default());
+
+        i2c.transaction(
+            0x24,
+            &mut [Operation::Write(&[1, 2]), Operation::Write(&[3])],
+        )
+        .unwrap();
+
+        assert_eq!(i2c.0 .0, vec![Call::Write(vec![1, 2, 3])]);
+    }
+
+    #[test]
+    fn rejects_transactions_which_cannot_be_expressed() {
+        let mut i2c = I2c(Bus::default());
+        let mut buffer = [0; 2];
+
+        let result = i2c.transaction(
+            0x24,
+            &mut [Operation::Read(&mut buffer), Operation::Write(&[1])],
+        );
+
+        assert_eq!(result, Err(Error::UnsupportedTransaction));
+        assert!(i2c.0 .0.is_empty());
+    }
+
+    #[test]
+    fn rejects_joined_writes_which_are_too_long() {
+        let mut i2c = I2c(Bus::default());
+        let bytes = [0; MAX_JOINED_WRITE_LEN];
+
+        let result = i2c.transaction(
+            0x24,
+            &mut [Operation::Write(&[1]), Operation::Write(&bytes)],
+        );
+
+        assert_eq!(result, Err(Error::UnsupportedTransaction));
+    }
+}
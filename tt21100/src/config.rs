@@ -0,0 +1,484 @@
+//! Driver configuration
+
+use crate::{
+    geom::{Point, Transform},
+    TouchRecord,
+    DEFAULT_ADDRESS,
+};
+#[cfg(feature = "report-processing")]
+use crate::{Event, NoiseEffect, TouchReport, Touches, MAX_TOUCHES};
+
+/// Configuration for the [TT21100](crate::TT21100) driver
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct Config {
+    /// I²C address of the device
+    pub address: u8,
+    /// Native coordinate range of the panel
+    pub panel: PanelProfile,
+    /// How to handle touch records with coordinates outside of the valid range
+    pub out_of_range: OutOfRange,
+    /// Transform applied to the coordinates of each touch record, for example
+    /// when the display is mounted rotated relative to the panel
+    ///
+    /// Coordinates are mirrored within the range of the configured
+    /// [PanelProfile], so a profile matching the panel should be selected
+    /// when mirroring, or within the range of the display when a
+    /// [calibration](Self::calibration) is configured. The transform is
+    /// applied after the coordinates have been validated.
+    pub transform: Transform,
+    /// Mapping of the panel's coordinates onto those of the display, if any
+    ///
+    /// The mapping is applied ahead of the transform, so the width and height
+    /// of the display are measured along the panel's axes.
+    pub calibration: Option<Calibration>,
+    /// How to handle unexpected or malformed messages
+    pub parsing: ParseMode,
+    /// Interval at which the interrupt pin is polled while waiting for an
+    /// event, or at which the device is sampled while in use when there is no
+    /// interrupt line, in microseconds
+    pub poll_interval_us: u32,
+    /// Interval at which the device is sampled while it is idle, when
+    /// sampling rather than waiting on the interrupt line, in microseconds
+    ///
+    /// While contacts or buttons are in use, or have been recently, the
+    /// device is instead sampled every `poll_interval_us`, balancing latency
+    /// against bus traffic and power consumption. Set this equal to
+    /// `poll_interval_us` to sample at a fixed interval.
+    pub idle_poll_interval_us: u32,
+    /// Time without any events after which the device is considered idle,
+    /// in microseconds
+    pub idle_after_us: u32,
+    /// Minimum time to wait between successive bus transactions, in
+    /// microseconds
+    ///
+    /// Some device firmware does not acknowledge a read which follows too
+    /// closely after the previous one. The gap is only enforced when the driver
+    /// has been provided with a delay.
+    pub transaction_gap_us: u32,
+    /// Period following a report of a large object, such as a palm, during
+    /// which new contacts are rejected, in microseconds
+    ///
+    /// The first few reports after a large object is lifted are frequently
+    /// spurious. The period is measured using the timestamps of the reports,
+    /// so is limited to around 6.5 seconds; `0` disables the cool-down.
+    #[cfg(feature = "report-processing")]
+    pub large_object_cooldown_us: u32,
+    /// Suppress the touch records of reports in which the device detects a
+    /// large object, such as a palm
+    ///
+    /// Such reports are returned as [Event::PalmRejected] rather than as touch
+    /// events, so that contacts resting on the panel are seen to be lifted
+    /// rather than reported at spurious positions.
+    #[cfg(feature = "report-processing")]
+    pub reject_palms: bool,
+    /// Severity of noise at which touch reports are dropped, if any
+    ///
+    /// Noise, such as that from a charger, causes the device to report jumpy
+    /// coordinates. Touch reports affected by noise of at least this severity
+    /// are skipped in favour of the next report queued on the device, so that
+    /// each contact holds its previous position. Reports carrying no touch
+    /// records are never dropped, so that lifted contacts are always seen.
+    /// Where noisy reports should instead be flagged, leave this unset and
+    /// check [Event::noise].
+    #[cfg(feature = "report-processing")]
+    pub noise_threshold: Option<NoiseEffect>,
+    /// Detect spontaneous resets of the device, such as those caused by ESD
+    ///
+    /// A reset is assumed when the device reports a message of length zero,
+    /// as it does while initializing, or when the timestamps of successive
+    /// reports jump backwards while a contact is held.
+    #[cfg(feature = "report-processing")]
+    pub detect_resets: bool,
+    /// Verify the CRC carried by each response to a
+    /// [command](crate::TT21100::write_command), rejecting those which do not
+    /// match with [Error::ChecksumMismatch](crate::Error::ChecksumMismatch)
+    ///
+    /// Touch and button reports carry no CRC of their own, so are not checked.
+    pub verify_checksums: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            address: DEFAULT_ADDRESS,
+            panel: PanelProfile::UNBOUNDED,
+            out_of_range: OutOfRange::Clamp,
+            transform: Transform::IDENTITY,
+            calibration: None,
+            parsing: ParseMode::Strict,
+            poll_interval_us: 1_000,
+            idle_poll_interval_us: 50_000,
+            idle_after_us: 500_000,
+            transaction_gap_us: 0,
+            #[cfg(feature = "report-processing")]
+            large_object_cooldown_us: 0,
+            #[cfg(feature = "report-processing")]
+            reject_palms: false,
+            #[cfg(feature = "report-processing")]
+            noise_threshold: None,
+            #[cfg(feature = "report-processing")]
+            detect_resets: false,
+            verify_checksums: true,
+        }
+    }
+}
+
+impl Config {
+    /// Create a configuration for the given panel profile
+    pub fn for_panel(panel: PanelProfile) -> Self {
+        Self {
+            panel,
+            ..Self::default()
+        }
+    }
+
+    /// Validate the coordinates of a touch record against the configured range
+    ///
+    /// Returns `None` if the record should be rejected.
+    pub(crate) fn validate(&self, mut record: TouchRecord) -> Option<TouchRecord> {
+        let PanelProfile { max_x, max_y } = self.panel;
+        if record.x <= max_x && record.y <= max_y {
+            return Some(record);
+        }
+
+        match self.out_of_range {
+            OutOfRange::Clamp => {
+                record.x = record.x.min(max_x);
+                record.y = record.y.min(max_y);
+
+                Some(record)
+            }
+            OutOfRange::Reject => None,
+        }
+    }
+
+    /// Map the coordinates of a validated touch record as configured, using
+    /// the given transform in place of the configured one
+    pub(crate) fn map(&self, mut record: TouchRecord, transform: Transform) -> TouchRecord {
+        let (position, max) = match self.calibration {
+            Some(calibration) => (calibration.apply(record.position()), calibration.max()),
+            None => (
+                record.position(),
+                Point::new(self.panel.max_x, self.panel.max_y),
+            ),
+        };
+        let position = transform.apply(position, max);
+        record.x = position.x;
+        record.y = position.y;
+
+        record
+    }
+}
+
+/// State of the cool-down period following a report of a large object
+#[cfg(feature = "report-processing")]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct Cooldown {
+    /// Timestamp of the most recent report of a large object, while within
+    /// the cool-down period
+    since: Option<u16>,
+    /// IDs of the contacts accepted from the previous report
+    contacts: [Option<u8>; MAX_TOUCHES],
+}
+
+#[cfg(feature = "report-processing")]
+impl Cooldown {
+    /// Update the cool-down period with a new report, returning whether new
+    /// contacts in the report should be rejected
+    pub(crate) fn update(&mut self, report: &TouchReport, cooldown_us: u32) -> bool {
+        if cooldown_us == 0 {
+            self.since = None;
+            return false;
+        }
+
+        if report.large_object != 0 {
+            self.since = Some(report.time_stamp);
+        }
+
+        // Timestamps are in units of 100us
+        let elapsed = self
+            .since
+            .map(|since| u32::from(report.time_stamp.wrapping_sub(since)) * 100);
+        if elapsed.map_or(false, |elapsed| elapsed >= cooldown_us) {
+            self.since = None;
+        }
+
+        self.since.is_some()
+    }
+
+    /// Was the contact with the given ID accepted from the previous report?
+    pub(crate) fn is_known(&self, touch_id: u8) -> bool {
+        self.contacts.contains(&Some(touch_id))
+    }
+
+    /// Record the contacts accepted from the latest report
+    pub(crate) fn set_contacts(&mut self, records: &Touches) {
+        self.contacts = [None; MAX_TOUCHES];
+        for (contact, record) in self.contacts.iter_mut().zip(records.iter()) {
+            *contact = Some(record.touch_id);
+        }
+    }
+}
+
+/// Detection of spontaneous resets of the device
+#[cfg(feature = "report-processing")]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct ResetDetector {
+    /// Timestamp of the previous report, if any contacts were held
+    previous: Option<u16>,
+    /// Number of resets detected
+    count: u32,
+}
+
+#[cfg(feature = "report-processing")]
+impl ResetDetector {
+    /// Update the detector with an event read from the device, returning
+    /// whether its timestamp indicates that the device has reset
+    pub(crate) fn update(&mut self, event: &Event) -> bool {
+        let report = match event {
+            Event::Touch { report, .. } | Event::PalmRejected(report) => report,
+            Event::Button(_) => return false,
+        };
+
+        // Consecutive reports are far closer together than half the range of
+        // the timestamp, so a larger difference means it went backwards
+        let reset = self.previous.map_or(false, |previous| {
+            report.time_stamp.wrapping_sub(previous) >= 0x8000
+        });
+
+        let held = event.touches().next().is_some();
+        self.previous = if held { Some(report.time_stamp) } else { None };
+
+        reset
+    }
+
+    /// Forget the previous report, so that the next is not compared to it
+    pub(crate) fn forget(&mut self) {
+        self.previous = None;
+    }
+
+    /// Record that the device has reset, forgetting the previous report
+    pub(crate) fn record_reset(&mut self) {
+        self.forget();
+        self.count = self.count.wrapping_add(1);
+    }
+
+    /// Number of resets detected
+    pub(crate) fn count(&self) -> u32 {
+        self.count
+    }
+}
+
+/// Detection of touch reports missed by the driver, using the counter carried
+/// by each report
+#[cfg(feature = "report-processing")]
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct MissedReports {
+    /// Counter of the previous touch report, if any
+    previous: Option<u8>,
+    /// Number of reports missed
+    count: u32,
+}
+
+#[cfg(feature = "report-processing")]
+impl MissedReports {
+    /// Update the detector with an event read from the device, returning the
+    /// number of reports missed since the previous one
+    ///
+    /// The counter is only two bits wide, so no more than three consecutive
+    /// missed reports can be detected.
+    pub(crate) fn update(&mut self, event: &Event) -> u32 {
+        let report = match event {
+            Event::Touch { report, .. } | Event::PalmRejected(report) => report,
+            Event::Button(_) => return 0,
+        };
+
+        let counter = report.report_counter & 0b11;
+        let missed = self.previous.map_or(0, |previous| {
+            u32::from(counter.wrapping_sub(previous).wrapping_sub(1) & 0b11)
+        });
+        self.previous = Some(counter);
+        self.count = self.count.wrapping_add(missed);
+
+        missed
+    }
+
+    /// Forget the previous report, so that the next is not compared to it
+    pub(crate) fn forget(&mut self) {
+        self.previous = None;
+    }
+
+    /// Number of reports missed
+    pub(crate) fn count(&self) -> u32 {
+        self.count
+    }
+}
+
+/// Native coordinate range of a touch panel
+///
+/// Different revisions of the ESP32-S3-BOX ship with panels of differing
+/// resolutions; the profile can be selected at runtime using
+/// [TT21100::set_panel](crate::TT21100::set_panel).
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct PanelProfile {
+    /// Largest valid X coordinate reported by the panel
+    pub max_x: u16,
+    /// Largest valid Y coordinate reported by the panel
+    pub max_y: u16,
+}
+
+impl PanelProfile {
+    /// Accept any coordinates reported by the panel
+    pub const UNBOUNDED: Self = Self::new(u16::MAX, u16::MAX);
+    /// 320×240 panel
+    pub const RES_320X240: Self = Self::new(319, 239);
+    /// 480×320 panel
+    pub const RES_480X320: Self = Self::new(479, 319);
+
+    /// Create a new panel profile with the given maximum coordinates
+    pub const fn new(max_x: u16, max_y: u16) -> Self {
+        Self { max_x, max_y }
+    }
+}
+
+/// Mapping of the coordinates reported by a panel onto the pixels of a
+/// display
+///
+/// The driver can be configured to apply the mapping using
+/// [TT21100::set_calibration](crate::TT21100::set_calibration).
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct Calibration {
+    /// Coordinates reported by the panel at the first pixel of the display
+    pub min: Point,
+    /// Coordinates reported by the panel at the last pixel of the display
+    pub max: Point,
+    /// Width of the display, in pixels
+    pub width: u16,
+    /// Height of the display, in pixels
+    pub height: u16,
+}
+
+impl Calibration {
+    /// Create a new calibration, mapping the panel coordinates `min..=max`
+    /// onto a display of the given size
+    pub const fn new(min: Point, max: Point, width: u16, height: u16) -> Self {
+        Self {
+            min,
+            max,
+            width,
+            height,
+        }
+    }
+
+    /// Map a point reported by the panel onto the display
+    ///
+    /// Points outside of the calibrated range are clamped to the edges of the
+    /// display.
+    pub fn apply(&self, point: Point) -> Point {
+        Point::new(
+            scale(point.x, self.min.x, self.max.x, self.width),
+            scale(point.y, self.min.y, self.max.y, self.height),
+        )
+    }
+
+    /// Last pixel of the display
+    fn max(&self) -> Point {
+        Point::new(self.width.saturating_sub(1), self.height.saturating_sub(1))
+    }
+}
+
+/// Scale `value` from the range `min..=max` onto the range `0..size`,
+/// rounding to the nearest value
+fn scale(value: u16, min: u16, max: u16, size: u16) -> u16 {
+    if max <= min || size == 0 {
+        return 0;
+    }
+
+    let offset = u32::from(value.max(min).min(max) - min);
+    let range = u32::from(max - min);
+    let scaled = (offset * u32::from(size - 1) + range / 2) / range;
+
+    scaled as u16
+}
+
+/// Handling of touch records with coordinates outside of the valid range
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub enum OutOfRange {
+    /// Clamp the coordinates to the valid range
+    Clamp,
+    /// Discard the record, incrementing the driver's rejected record counter
+    Reject,
+}
+
+/// Handling of unexpected or malformed messages
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub enum ParseMode {
+    /// Reject any message with an unexpected length, returning an error;
+    /// useful during bring-up
+    Strict,
+    /// Tolerate malformed messages, parsing as much of a touch message as
+    /// possible and otherwise skipping the message as though no data was
+    /// available
+    Lenient,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamps_out_of_range_coordinates() {
+        let config = Config::for_panel(PanelProfile::new(100, 200));
+
+        let record = config.validate(TouchRecord::new(1, 150, 50));
+
+        assert!(record == Some(TouchRecord::new(1, 100, 50)));
+    }
+
+    #[test]
+    fn rejects_out_of_range_coordinates() {
+        let config = Config {
+            out_of_range: OutOfRange::Reject,
+            ..Config::for_panel(PanelProfile::new(100, 200))
+        };
+
+        assert!(config.validate(TouchRecord::new(1, 50, 201)).is_none());
+        assert!(config.validate(TouchRecord::new(1, 100, 200)).is_some());
+    }
+
+    #[test]
+    fn scales_calibrated_range_onto_display() {
+        let calibration =
+            Calibration::new(Point::new(100, 200), Point::new(3_900, 3_800), 320, 240);
+
+        assert!(calibration.apply(Point::new(100, 200)) == Point::new(0, 0));
+        assert!(calibration.apply(Point::new(3_900, 3_800)) == Point::new(319, 239));
+        assert!(calibration.apply(Point::new(2_000, 2_000)) == Point::new(160, 120));
+        assert!(calibration.apply(Point::new(0, 4_000)) == Point::new(0, 239));
+
+        let degenerate = Calibration::new(Point::new(100, 100), Point::new(100, 100), 320, 240);
+        assert!(degenerate.apply(Point::new(200, 200)) == Point::new(0, 0));
+    }
+
+    #[test]
+    fn transforms_within_range_of_calibrated_display() {
+        let config = Config {
+            calibration: Some(Calibration::new(
+                Point::new(0, 0),
+                Point::new(1_000, 1_000),
+                101,
+                51,
+            )),
+            ..Config::default()
+        };
+
+        let record = config.map(TouchRecord::new(1, 100, 200), Transform::ROTATE_180);
+
+        assert!(record.position() == Point::new(90, 40));
+    }
+}
@@ -0,0 +1,76 @@
+//! Identification of the device
+
+/// Register from which the HID descriptor of the device is read
+pub(crate) const DESCRIPTOR_REGISTER: u16 = 0x0001;
+
+/// Length of the HID descriptor, in bytes
+pub(crate) const DESCRIPTOR_LEN: usize = 30;
+
+/// Identity of the device and its firmware, read from its HID descriptor
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Version {
+    /// Vendor ID of the device
+    pub vendor_id: u16,
+    /// Product ID of the device, identifying the chip
+    pub product_id: u16,
+    /// Version of the firmware running on the device
+    pub firmware: u16,
+}
+
+impl Version {
+    /// Parse the identity of the device from its HID descriptor
+    ///
+    /// Returns `None` if the descriptor is not of the expected length.
+    pub(crate) fn from_descriptor(descriptor: &[u8; DESCRIPTOR_LEN]) -> Option<Self> {
+        let field =
+            |offset: usize| u16::from_le_bytes([descriptor[offset], descriptor[offset + 1]]);
+        if usize::from(field(0)) != DESCRIPTOR_LEN {
+            return None;
+        }
+
+        Some(Self {
+            vendor_id: field(20),
+            product_id: field(22),
+            firmware: field(24),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn descriptor(vendor_id: u16, product_id: u16, firmware: u16) -> [u8; DESCRIPTOR_LEN] {
+        let mut descriptor = [0; DESCRIPTOR_LEN];
+        descriptor[0..2].copy_from_slice(&(DESCRIPTOR_LEN as u16).to_le_bytes());
+        descriptor[20..22].copy_from_slice(&vendor_id.to_le_bytes());
+        descriptor[22..24].copy_from_slice(&product_id.to_le_bytes());
+        descriptor[24..26].copy_from_slice(&firmware.to_le_bytes());
+
+        descriptor
+    }
+
+    #[test]
+    fn parses_identity_of_device() {
+        let version = Version::from_descriptor(&descriptor(0x04B4, 0x2110, 0x0102));
+
+        assert!(
+            version
+                == Some(Version {
+                    vendor_id: 0x04B4,
+                    product_id: 0x2110,
+                    firmware: 0x0102,
+                })
+        );
+    }
+
+    #[test]
+    fn rejects_descriptors_of_unexpected_length() {
+        let mut descriptor = descriptor(0x04B4, 0x2110, 0x0102);
+        descriptor[0] = 2;
+
+        assert!(Version::from_descriptor(&descriptor).is_none());
+    }
+}
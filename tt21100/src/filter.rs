@@ -0,0 +1,724 @@
+//! Smoothing of touch coordinates across samples
+
+use crate::{geom::Point, Event, NoiseEffect, TouchRecord, MAX_TOUCHES};
+
+/// A position with sub-pixel precision, in 26.6 fixed-point format
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SubPixelPoint {
+    /// X coordinate, in 1/64ths of a unit
+    pub x: i32,
+    /// Y coordinate, in 1/64ths of a unit
+    pub y: i32,
+}
+
+impl SubPixelPoint {
+    /// Number of fractional bits in each coordinate
+    pub const FRACTIONAL_BITS: u32 = 6;
+
+    /// Create a new point from a whole-unit point
+    pub fn from_point(point: Point) -> Self {
+        Self {
+            x: i32::from(point.x) << Self::FRACTIONAL_BITS,
+            y: i32::from(point.y) << Self::FRACTIONAL_BITS,
+        }
+    }
+
+    /// Round to the nearest whole-unit point
+    pub fn to_point(&self) -> Point {
+        Point::new(round(self.x), round(self.y))
+    }
+}
+
+fn round(value: i32) -> u16 {
+    let half = 1 << (SubPixelPoint::FRACTIONAL_BITS - 1);
+    let value = (value + half) >> SubPixelPoint::FRACTIONAL_BITS;
+
+    value.clamp(0, i32::from(u16::MAX)) as u16
+}
+
+/// The smoothed position of a single contact
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SubPixelTouch {
+    /// ID of the contact, as reported by the device
+    pub touch_id: u8,
+    /// Smoothed position of the contact
+    pub position: SubPixelPoint,
+}
+
+/// A filter which can be installed in a [Smoother]
+///
+/// Each tracked contact is given its own copy of the filter, which is reset
+/// whenever the contact is first seen.
+pub trait TouchFilter {
+    /// Clear any state accumulated from previous samples
+    fn reset(&mut self);
+
+    /// Filter a new sample, `elapsed_us` microseconds after the previous one
+    fn update(&mut self, position: SubPixelPoint, elapsed_us: u32) -> SubPixelPoint;
+
+    /// Inform the filter of the noise affecting the next sample
+    ///
+    /// Filters which do not adapt to noise ignore this.
+    fn set_noise(&mut self, _noise: NoiseEffect) {}
+}
+
+/// Exponential moving average filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExponentialFilter {
+    /// Weight of each new sample, out of 256
+    weight: u16,
+    /// Previous output of the filter
+    previous: Option<SubPixelPoint>,
+}
+
+impl ExponentialFilter {
+    /// Create a new filter, giving each new sample a weight of `weight / 256`
+    pub fn new(weight: u8) -> Self {
+        Self {
+            weight: u16::from(weight).max(1),
+            previous: None,
+        }
+    }
+}
+
+impl Default for ExponentialFilter {
+    fn default() -> Self {
+        Self::new(96)
+    }
+}
+
+impl TouchFilter for ExponentialFilter {
+    fn reset(&mut self) {
+        self.previous = None;
+    }
+
+    fn update(&mut self, position: SubPixelPoint, _elapsed_us: u32) -> SubPixelPoint {
+        let weight = i32::from(self.weight);
+        let output = match self.previous {
+            Some(previous) => SubPixelPoint {
+                x: previous.x + (((position.x - previous.x) * weight) >> 8),
+                y: previous.y + (((position.y - previous.y) * weight) >> 8),
+            },
+            None => position,
+        };
+        self.previous = Some(output);
+
+        output
+    }
+}
+
+/// Exponential moving average filter whose strength follows the noise
+/// reported by the device
+///
+/// Each level of [NoiseEffect] has its own sample weight, along with a
+/// deadzone within which movements are ignored entirely, so that contacts
+/// remain precise in clean conditions but stable when the device reports
+/// heavy noise, for example while charging.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoiseAdaptiveFilter {
+    /// Weight of each new sample out of 256, for each level of noise
+    weights: [u8; 4],
+    /// Distance within which movements are ignored, in 1/64ths of a unit, for
+    /// each level of noise
+    deadzones: [u16; 4],
+    /// Noise affecting the next sample
+    noise: NoiseEffect,
+    /// Previous output of the filter
+    previous: Option<SubPixelPoint>,
+}
+
+impl NoiseAdaptiveFilter {
+    /// Create a new filter using the given sample weights out of 256 and
+    /// deadzones in whole units, each ordered from [NoiseEffect::None] to
+    /// [NoiseEffect::High]
+    pub fn new(weights: [u8; 4], deadzones: [u8; 4]) -> Self {
+        let mut scaled = [0; 4];
+        for (scaled, deadzone) in scaled.iter_mut().zip(deadzones) {
+            *scaled = u16::from(deadzone) << SubPixelPoint::FRACTIONAL_BITS;
+        }
+
+        Self {
+            weights,
+            deadzones: scaled,
+            noise: NoiseEffect::None,
+            previous: None,
+        }
+    }
+
+    fn level(&self) -> usize {
+        match self.noise {
+            NoiseEffect::None => 0,
+            NoiseEffect::Low => 1,
+            NoiseEffect::Moderate => 2,
+            NoiseEffect::High => 3,
+        }
+    }
+}
+
+impl Default for NoiseAdaptiveFilter {
+    fn default() -> Self {
+        Self::new([255, 160, 96, 48], [0, 1, 2, 4])
+    }
+}
+
+impl TouchFilter for NoiseAdaptiveFilter {
+    fn reset(&mut self) {
+        self.previous = None;
+    }
+
+    fn update(&mut self, position: SubPixelPoint, _elapsed_us: u32) -> SubPixelPoint {
+        let level = self.level();
+        let weight = i32::from(self.weights[level].max(1));
+        let deadzone = i32::from(self.deadzones[level]);
+
+        let output = match self.previous {
+            Some(previous)
+                if (position.x - previous.x).abs() <= deadzone
+                    && (position.y - previous.y).abs() <= deadzone =>
+            {
+                previous
+            }
+            Some(previous) => SubPixelPoint {
+                x: previous.x + (((position.x - previous.x) * weight) >> 8),
+                y: previous.y + (((position.y - previous.y) * weight) >> 8),
+            },
+            None => position,
+        };
+        self.previous = Some(output);
+
+        output
+    }
+
+    fn set_noise(&mut self, noise: NoiseEffect) {
+        self.noise = noise;
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Slot<F> {
+    touch_id: u8,
+    time_stamp: u16,
+    filter: F,
+}
+
+/// Smooths the coordinates of each contact across successive touch events
+///
+/// Touch reports carry a timestamp in units of 100us, which is used to
+/// determine the time elapsed between samples.
+#[derive(Debug, Clone)]
+pub struct Smoother<F> {
+    /// Filter from which each contact's filter is cloned
+    filter: F,
+    /// Per-contact filter state
+    slots: [Option<Slot<F>>; MAX_TOUCHES],
+}
+
+impl<F> Smoother<F>
+where
+    F: TouchFilter + Clone,
+{
+    /// Create a new smoother, installing the given filter
+    pub fn new(filter: F) -> Self {
+        Self {
+            filter,
+            slots: Default::default(),
+        }
+    }
+
+    /// Forget all tracked contacts
+    pub fn reset(&mut self) {
+        self.slots = Default::default();
+    }
+
+    /// The filter from which each contact's filter is cloned
+    pub fn filter(&self) -> &F {
+        &self.filter
+    }
+
+    /// Replace the installed filter
+    ///
+    /// Contacts currently being tracked are forgotten, so that their
+    /// smoothing restarts from their next reported position using the new
+    /// filter.
+    pub fn set_filter(&mut self, filter: F) {
+        self.filter = filter;
+        self.reset();
+    }
+
+    /// Smooth the touch records of an event, returning their sub-pixel
+    /// positions in the order in which they were reported
+    ///
+    /// Contacts which are not present in the event are forgotten.
+    pub fn update(&mut self, event: &Event) -> [Option<SubPixelTouch>; MAX_TOUCHES] {
+        let (report, records) = match event {
+            Event::Touch { report, touches } => (report, touches),
+            Event::Button(_) => return Default::default(),
+            Event::PalmRejected(_) => {
+                self.reset();
+                return Default::default();
+            }
+        };
+
+        for slot in self.slots.iter_mut() {
+            let present = slot.as_ref().map_or(false, |slot| {
+                records
+                    .iter()
+                    .any(|record| record.touch_id == slot.touch_id)
+            });
+            if !present {
+                *slot = None;
+            }
+        }
+
+        let mut output: [Option<SubPixelTouch>; MAX_TOUCHES] = Default::default();
+        for (output, record) in output.iter_mut().zip(records.iter()) {
+            *output = self.smooth(record, report.time_stamp, report.noise());
+        }
+
+        output
+    }
+
+    /// Smooth the touch records of an event, replacing their coordinates with
+    /// the smoothed coordinates
+    pub fn apply(&mut self, event: &mut Event) {
+        let smoothed = self.update(event);
+        if let Event::Touch { touches, .. } = event {
+            for (record, smoothed) in touches.iter_mut().zip(smoothed) {
+                if let Some(smoothed) = smoothed {
+                    let Point { x, y } = smoothed.position.to_point();
+                    record.x = x;
+                    record.y = y;
+                }
+            }
+        }
+    }
+
+    fn smooth(
+        &mut self,
+        record: &TouchRecord,
+        time_stamp: u16,
+        noise: NoiseEffect,
+    ) -> Option<SubPixelTouch> {
+        let position = SubPixelPoint::from_point(Point::from(record));
+
+        let index = match self
+            .slots
+            .iter()
+            .position(|slot| matches!(slot, Some(slot) if slot.touch_id == record.touch_id))
+        {
+            Some(index) => index,
+            None => {
+                let index = self.slots.iter().position(|slot| slot.is_none())?;
+
+                let mut filter = self.filter.clone();
+                filter.reset();
+                self.slots[index] = Some(Slot {
+                    touch_id: record.touch_id,
+                    time_stamp,
+                    filter,
+                });
+
+                index
+            }
+        };
+
+        let slot = self.slots[index].as_mut()?;
+        let elapsed_us = u32::from(time_stamp.wrapping_sub(slot.time_stamp)) * 100;
+        slot.time_stamp = time_stamp;
+        slot.filter.set_noise(noise);
+
+        Some(SubPixelTouch {
+            touch_id: record.touch_id,
+            position: slot.filter.update(position, elapsed_us),
+        })
+    }
+}
+
+/// The 1€ filter, an adaptive low-pass filter
+///
+/// At low speeds the filter's cutoff frequency is kept low, reducing jitter,
+/// while at high speeds the cutoff frequency is raised, reducing lag. See
+/// <https://gery.casiez.net/1euro/> for details on tuning its parameters.
+#[cfg(feature = "one-euro")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OneEuroFilter {
+    /// Minimum cutoff frequency, in Hz
+    min_cutoff: f32,
+    /// Speed coefficient
+    beta: f32,
+    /// Cutoff frequency used when estimating speed, in Hz
+    d_cutoff: f32,
+    /// State of the filter for each axis
+    state: Option<[OneEuroAxis; 2]>,
+}
+
+#[cfg(feature = "one-euro")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct OneEuroAxis {
+    /// Previous output of the filter
+    value: f32,
+    /// Previous estimate of the speed
+    speed: f32,
+}
+
+#[cfg(feature = "one-euro")]
+impl OneEuroFilter {
+    /// Create a new filter with the given minimum cutoff frequency (in Hz) and
+    /// speed coefficient
+    pub fn new(min_cutoff: f32, beta: f32) -> Self {
+        Self {
+            min_cutoff,
+            beta,
+            d_cutoff: 1.0,
+            state: None,
+        }
+    }
+
+    /// Set the cutoff frequency used when estimating speed, in Hz
+    pub fn with_derivative_cutoff(mut self, d_cutoff: f32) -> Self {
+        self.d_cutoff = d_cutoff;
+        self
+    }
+
+    fn filter(&self, axis: &mut OneEuroAxis, value: f32, dt: f32) {
+        let speed = (value - axis.value) / dt;
+        axis.speed += smoothing_factor(self.d_cutoff, dt) * (speed - axis.speed);
+
+        let magnitude = if axis.speed < 0.0 {
+            -axis.speed
+        } else {
+            axis.speed
+        };
+        let cutoff = self.min_cutoff + self.beta * magnitude;
+        axis.value += smoothing_factor(cutoff, dt) * (value - axis.value);
+    }
+}
+
+#[cfg(feature = "one-euro")]
+impl Default for OneEuroFilter {
+    fn default() -> Self {
+        Self::new(1.0, 0.007)
+    }
+}
+
+#[cfg(feature = "one-euro")]
+impl TouchFilter for OneEuroFilter {
+    fn reset(&mut self) {
+        self.state = None;
+    }
+
+    fn update(&mut self, position: SubPixelPoint, elapsed_us: u32) -> SubPixelPoint {
+        let scale = (1 << SubPixelPoint::FRACTIONAL_BITS) as f32;
+        let x = position.x as f32 / scale;
+        let y = position.y as f32 / scale;
+
+        let mut state = match self.state {
+            Some(state) => state,
+            None => {
+                self.state = Some([
+                    OneEuroAxis {
+                        value: x,
+                        speed: 0.0,
+                    },
+                    OneEuroAxis {
+                        value: y,
+                        speed: 0.0,
+                    },
+                ]);
+                return position;
+            }
+        };
+
+        if elapsed_us > 0 {
+            let dt = elapsed_us as f32 / 1_000_000.0;
+            self.filter(&mut state[0], x, dt);
+            self.filter(&mut state[1], y, dt);
+            self.state = Some(state);
+        }
+
+        SubPixelPoint {
+            x: (state[0].value * scale) as i32,
+            y: (state[1].value * scale) as i32,
+        }
+    }
+}
+
+#[cfg(feature = "one-euro")]
+fn smoothing_factor(cutoff: f32, dt: f32) -> f32 {
+    let r = 2.0 * core::f32::consts::PI * cutoff * dt;
+    r / (r + 1.0)
+}
+
+/// Constant-velocity Kalman filter
+///
+/// Each axis is modelled as a position and velocity, with the velocity being
+/// allowed to drift between samples. All arithmetic is performed in Q16.16
+/// fixed-point.
+#[cfg(feature = "kalman")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KalmanFilter {
+    /// Variance added to the velocity estimate per millisecond, in Q16.16
+    process_noise: i64,
+    /// Variance of the measured position, in Q16.16
+    measurement_noise: i64,
+    /// State of the filter for each axis
+    state: Option<[KalmanAxis; 2]>,
+}
+
+#[cfg(feature = "kalman")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct KalmanAxis {
+    /// Estimated position, in units (Q16.16)
+    position: i64,
+    /// Estimated velocity, in units per millisecond (Q16.16)
+    velocity: i64,
+    /// Covariance of the estimate (Q16.16)
+    covariance: [[i64; 2]; 2],
+}
+
+#[cfg(feature = "kalman")]
+impl KalmanFilter {
+    /// Create a new filter
+    ///
+    /// `process_noise` is the variance added to the velocity estimate each
+    /// millisecond, in thousandths of a squared unit per squared millisecond.
+    /// `measurement_noise` is the variance of the reported positions, in
+    /// thousandths of a squared unit.
+    pub fn new(process_noise: u32, measurement_noise: u32) -> Self {
+        Self {
+            process_noise: from_thousandths(process_noise),
+            measurement_noise: from_thousandths(measurement_noise).max(1),
+            state: None,
+        }
+    }
+
+    fn filter(&self, axis: &mut KalmanAxis, measurement: i64, dt: i64) {
+        let p = &mut axis.covariance;
+
+        // Predict
+        axis.position += fixed_mul(axis.velocity, dt);
+        p[0][0] += fixed_mul(dt, p[1][0] + p[0][1]) + fixed_mul(fixed_mul(dt, dt), p[1][1]);
+        p[0][1] += fixed_mul(dt, p[1][1]);
+        p[1][0] += fixed_mul(dt, p[1][1]);
+        p[1][1] += fixed_mul(self.process_noise, dt);
+
+        // Update
+        let residual = measurement - axis.position;
+        let innovation = p[0][0] + self.measurement_noise;
+        let gain0 = fixed_div(p[0][0], innovation);
+        let gain1 = fixed_div(p[1][0], innovation);
+
+        axis.position += fixed_mul(gain0, residual);
+        axis.velocity += fixed_mul(gain1, residual);
+
+        let (p00, p01) = (p[0][0], p[0][1]);
+        p[0][0] -= fixed_mul(gain0, p00);
+        p[0][1] -= fixed_mul(gain0, p01);
+        p[1][0] -= fixed_mul(gain1, p00);
+        p[1][1] -= fixed_mul(gain1, p01);
+    }
+}
+
+#[cfg(feature = "kalman")]
+impl Default for KalmanFilter {
+    fn default() -> Self {
+        Self::new(1, 4_000)
+    }
+}
+
+#[cfg(feature = "kalman")]
+impl TouchFilter for KalmanFilter {
+    fn reset(&mut self) {
+        self.state = None;
+    }
+
+    fn update(&mut self, position: SubPixelPoint, elapsed_us: u32) -> SubPixelPoint {
+        // Convert from 26.6 to Q16.16
+        let shift = 16 - SubPixelPoint::FRACTIONAL_BITS;
+        let x = i64::from(position.x) << shift;
+        let y = i64::from(position.y) << shift;
+
+        let mut state = match self.state {
+            Some(state) => state,
+            None => {
+                let axis = |position| KalmanAxis {
+                    position,
+                    velocity: 0,
+                    covariance: [[self.measurement_noise, 0], [0, self.measurement_noise]],
+                };
+                self.state = Some([axis(x), axis(y)]);
+
+                return position;
+            }
+        };
+
+        let dt = (i64::from(elapsed_us) << 16) / 1_000;
+        self.filter(&mut state[0], x, dt);
+        self.filter(&mut state[1], y, dt);
+        self.state = Some(state);
+
+        SubPixelPoint {
+            x: (state[0].position >> shift) as i32,
+            y: (state[1].position >> shift) as i32,
+        }
+    }
+}
+
+#[cfg(feature = "kalman")]
+fn from_thousandths(value: u32) -> i64 {
+    (i64::from(value) << 16) / 1_000
+}
+
+#[cfg(feature = "kalman")]
+fn fixed_mul(a: i64, b: i64) -> i64 {
+    (a * b) >> 16
+}
+
+#[cfg(feature = "kalman")]
+fn fixed_div(a: i64, b: i64) -> i64 {
+    (a << 16) / b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TouchReport;
+
+    fn point(x: u16, y: u16) -> SubPixelPoint {
+        SubPixelPoint::from_point(Point::new(x, y))
+    }
+
+    #[test]
+    fn rounds_sub_pixel_points() {
+        assert_eq!(point(10, 20), SubPixelPoint { x: 640, y: 1280 });
+        assert_eq!(
+            SubPixelPoint { x: 671, y: 672 }.to_point(),
+            Point::new(10, 11)
+        );
+        assert_eq!(SubPixelPoint { x: -64, y: 0 }.to_point(), Point::new(0, 0));
+    }
+
+    #[test]
+    fn smooths_with_sub_pixel_precision() {
+        let mut smoother = Smoother::new(ExponentialFilter::new(128));
+
+        smoother.update(&Event::touch(0, &[TouchRecord::new(1, 10, 10)]));
+        let output = smoother.update(&Event::touch(100, &[TouchRecord::new(1, 11, 10)]));
+
+        assert_eq!(
+            output[0],
+            Some(SubPixelTouch {
+                touch_id: 1,
+                position: SubPixelPoint { x: 672, y: 640 },
+            })
+        );
+    }
+
+    #[test]
+    fn forgets_lifted_contacts() {
+        let mut smoother = Smoother::new(ExponentialFilter::new(128));
+
+        smoother.update(&Event::touch(0, &[TouchRecord::new(1, 10, 10)]));
+        smoother.update(&Event::touch(100, &[]));
+        let output = smoother.update(&Event::touch(200, &[TouchRecord::new(1, 50, 50)]));
+
+        assert_eq!(output[0].map(|touch| touch.position), Some(point(50, 50)));
+    }
+
+    #[test]
+    fn adapts_smoothing_to_noise() {
+        let mut filter = NoiseAdaptiveFilter::new([128, 128, 128, 64], [0, 0, 0, 0]);
+        filter.update(point(0, 0), 0);
+        assert_eq!(
+            filter.update(point(100, 0), 10_000),
+            SubPixelPoint { x: 3200, y: 0 }
+        );
+
+        filter.set_noise(NoiseEffect::High);
+        assert_eq!(
+            filter.update(point(100, 0), 10_000),
+            SubPixelPoint { x: 4000, y: 0 }
+        );
+    }
+
+    #[test]
+    fn ignores_movements_within_deadzone() {
+        let mut filter = NoiseAdaptiveFilter::default();
+        filter.set_noise(NoiseEffect::High);
+        filter.update(point(10, 10), 0);
+
+        assert_eq!(filter.update(point(14, 6), 10_000), point(10, 10));
+        assert_ne!(filter.update(point(15, 10), 10_000), point(10, 10));
+
+        filter.reset();
+        assert_eq!(filter.update(point(50, 50), 0), point(50, 50));
+    }
+
+    #[test]
+    fn passes_noise_of_each_report_to_filters() {
+        let mut smoother = Smoother::new(NoiseAdaptiveFilter::default());
+        let mut report = TouchReport::new(100, 1);
+        report.noise_effect = 5;
+
+        smoother.update(&Event::touch(0, &[TouchRecord::new(1, 10, 10)]));
+        let output = smoother.update(&Event::Touch {
+            report,
+            touches: [TouchRecord::new(1, 12, 10)].into_iter().collect(),
+        });
+
+        assert_eq!(output[0].map(|touch| touch.position), Some(point(10, 10)));
+    }
+
+    #[cfg(feature = "one-euro")]
+    #[test]
+    fn one_euro_filter_passes_first_sample_through() {
+        let mut filter = OneEuroFilter::default();
+
+        assert_eq!(filter.update(point(10, 20), 0), point(10, 20));
+        assert_eq!(filter.update(point(10, 20), 10_000), point(10, 20));
+    }
+
+    #[cfg(feature = "one-euro")]
+    #[test]
+    fn one_euro_filter_follows_fast_movement_more_closely() {
+        let step = |beta| {
+            let mut filter = OneEuroFilter::new(1.0, beta);
+            filter.update(point(0, 0), 0);
+
+            filter.update(point(100, 0), 10_000).x
+        };
+
+        let slow = step(0.0);
+        let fast = step(1.0);
+
+        assert!(0 < slow && slow < fast && fast < point(100, 0).x);
+    }
+
+    #[cfg(feature = "kalman")]
+    #[test]
+    fn kalman_filter_holds_stationary_contacts() {
+        let mut filter = KalmanFilter::default();
+
+        assert_eq!(filter.update(point(10, 20), 0), point(10, 20));
+        for _ in 0..10 {
+            assert_eq!(filter.update(point(10, 20), 10_000), point(10, 20));
+        }
+    }
+
+    #[cfg(feature = "kalman")]
+    #[test]
+    fn kalman_filter_converges_on_moved_contacts() {
+        let mut filter = KalmanFilter::default();
+        filter.update(point(0, 0), 0);
+
+        let first = filter.update(point(100, 0), 10_000).x;
+        assert!(0 < first && first < point(100, 0).x);
+
+        let mut last = first;
+        for _ in 0..50 {
+            last = filter.update(point(100, 0), 10_000).x;
+        }
+        assert!(last > first);
+        assert!((last - point(100, 0).x).abs() < 64);
+    }
+}
@@ -0,0 +1,449 @@
+//! Geometry helpers
+
+use crate::{
+    math::{atan2_cdeg, isqrt},
+    TouchRecord,
+};
+
+/// A position on the panel
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct Point {
+    /// X coordinate
+    pub x: u16,
+    /// Y coordinate
+    pub y: u16,
+}
+
+impl Point {
+    /// Create a new point
+    pub const fn new(x: u16, y: u16) -> Self {
+        Self { x, y }
+    }
+
+    /// Square of the distance between two points
+    pub fn distance_squared(&self, other: Point) -> u32 {
+        let dx = u32::from(self.x.max(other.x) - self.x.min(other.x));
+        let dy = u32::from(self.y.max(other.y) - self.y.min(other.y));
+
+        (dx * dx).saturating_add(dy * dy)
+    }
+
+    /// Distance between two points
+    pub fn distance(&self, other: Point) -> u32 {
+        isqrt(self.distance_squared(other))
+    }
+
+    /// Point halfway between two points
+    pub fn midpoint(&self, other: Point) -> Point {
+        let x = (u32::from(self.x) + u32::from(other.x)) / 2;
+        let y = (u32::from(self.y) + u32::from(other.y)) / 2;
+
+        Point::new(x as u16, y as u16)
+    }
+
+    /// Angle of the line from this point to another, in hundredths of a
+    /// degree
+    ///
+    /// The angle is measured from the positive X axis towards the positive Y
+    /// axis, and is in the range `-18000..=18000`.
+    pub fn angle_to(&self, other: Point) -> i32 {
+        let dx = i32::from(other.x) - i32::from(self.x);
+        let dy = i32::from(other.y) - i32::from(self.y);
+
+        atan2_cdeg(dy, dx)
+    }
+}
+
+impl From<&TouchRecord> for Point {
+    fn from(record: &TouchRecord) -> Self {
+        Point::new(record.x, record.y)
+    }
+}
+
+/// An axis-aligned rectangle
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct Rect {
+    /// Top-left corner of the rectangle
+    pub origin: Point,
+    /// Width of the rectangle
+    pub width: u16,
+    /// Height of the rectangle
+    pub height: u16,
+}
+
+impl Rect {
+    /// Create a new rectangle
+    pub const fn new(origin: Point, width: u16, height: u16) -> Self {
+        Self {
+            origin,
+            width,
+            height,
+        }
+    }
+
+    /// Does the rectangle contain the given point?
+    pub fn contains(&self, point: Point) -> bool {
+        let dx = u32::from(point.x).wrapping_sub(u32::from(self.origin.x));
+        let dy = u32::from(point.y).wrapping_sub(u32::from(self.origin.y));
+
+        dx < u32::from(self.width) && dy < u32::from(self.height)
+    }
+
+    /// Center of the rectangle
+    pub fn center(&self) -> Point {
+        let x = u32::from(self.origin.x) + u32::from(self.width) / 2;
+        let y = u32::from(self.origin.y) + u32::from(self.height) / 2;
+
+        Point::new(
+            x.min(u32::from(u16::MAX)) as u16,
+            y.min(u32::from(u16::MAX)) as u16,
+        )
+    }
+}
+
+/// Physical dimensions of a touch panel, used to convert sizes reported by
+/// the device into physical units
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct PanelGeometry {
+    /// Width of the panel, in panel units
+    pub width: u16,
+    /// Height of the panel, in panel units
+    pub height: u16,
+    /// Width of the panel's active area, in micrometres
+    pub width_um: u32,
+    /// Height of the panel's active area, in micrometres
+    pub height_um: u32,
+}
+
+impl PanelGeometry {
+    /// Create a new panel geometry
+    pub const fn new(width: u16, height: u16, width_um: u32, height_um: u32) -> Self {
+        Self {
+            width,
+            height,
+            width_um,
+            height_um,
+        }
+    }
+
+    /// Convert a length in panel units into micrometres
+    ///
+    /// The length is assumed to have no particular direction, so is scaled by
+    /// the mean of the horizontal and vertical pitch of the panel.
+    pub fn length_um(&self, length: u16) -> u32 {
+        let width = u64::from(self.width.max(1));
+        let height = u64::from(self.height.max(1));
+        let scaled = u64::from(length)
+            * (u64::from(self.width_um) * height + u64::from(self.height_um) * width)
+            / (2 * width * height);
+
+        scaled.min(u64::from(u32::MAX)) as u32
+    }
+
+    /// Length of a contact's major axis, in micrometres
+    pub fn major_axis_um(&self, record: &TouchRecord) -> u32 {
+        self.length_um(record.major_axis_length)
+    }
+
+    /// Approximate area of a contact, in square millimetres
+    ///
+    /// The contact is assumed to be circular, with a diameter equal to the
+    /// length of its major axis.
+    pub fn contact_area_mm2(&self, record: &TouchRecord) -> u32 {
+        let diameter = u64::from(self.major_axis_um(record));
+        // π/4 · d², with d in micrometres
+        let area = diameter * diameter / 1_000_000 * 785_398 / 1_000_000;
+
+        area.min(u64::from(u32::MAX)) as u32
+    }
+}
+
+/// A combination of mirroring and axis swapping, covering rotation in
+/// multiples of 90°
+///
+/// Mirroring is applied before the axes are swapped.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct Transform {
+    /// Mirror the X axis
+    pub mirror_x: bool,
+    /// Mirror the Y axis
+    pub mirror_y: bool,
+    /// Swap the X and Y axes
+    pub swap_xy: bool,
+}
+
+impl Transform {
+    /// Leave coordinates unchanged
+    pub const IDENTITY: Self = Self::new(false, false, false);
+    /// Rotate coordinates 90° clockwise
+    pub const ROTATE_90: Self = Self::new(false, true, true);
+    /// Rotate coordinates 180°
+    pub const ROTATE_180: Self = Self::new(true, true, false);
+    /// Rotate coordinates 270° clockwise
+    pub const ROTATE_270: Self = Self::new(true, false, true);
+
+    /// Create a new transform
+    pub const fn new(mirror_x: bool, mirror_y: bool, swap_xy: bool) -> Self {
+        Self {
+            mirror_x,
+            mirror_y,
+            swap_xy,
+        }
+    }
+
+    /// Apply the transform to a point within the range `(0, 0)..=max`
+    pub fn apply(&self, point: Point, max: Point) -> Point {
+        let mut point = Point::new(point.x.min(max.x), point.y.min(max.y));
+        if self.mirror_x {
+            point.x = max.x - point.x;
+        }
+        if self.mirror_y {
+            point.y = max.y - point.y;
+        }
+        if self.swap_xy {
+            point = Point::new(point.y, point.x);
+        }
+
+        point
+    }
+
+    /// The transform which applies this transform followed by `next`
+    ///
+    /// When applying `next` on its own, its range should be swapped whenever
+    /// this transform swaps the axes; the combined transform accounts for this
+    /// itself.
+    pub const fn then(&self, next: Transform) -> Transform {
+        let (next_x, next_y) = if self.swap_xy {
+            (next.mirror_y, next.mirror_x)
+        } else {
+            (next.mirror_x, next.mirror_y)
+        };
+
+        Transform::new(
+            self.mirror_x ^ next_x,
+            self.mirror_y ^ next_y,
+            self.swap_xy ^ next.swap_xy,
+        )
+    }
+}
+
+/// Rotation of the display from its native orientation
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub enum Orientation {
+    /// The display is in its native orientation
+    Rotate0,
+    /// The display is rotated 90° clockwise
+    Rotate90,
+    /// The display is rotated 180°
+    Rotate180,
+    /// The display is rotated 270° clockwise
+    Rotate270,
+}
+
+impl Default for Orientation {
+    fn default() -> Self {
+        Orientation::Rotate0
+    }
+}
+
+impl Orientation {
+    /// The transform which rotates coordinates to follow the display
+    pub const fn transform(&self) -> Transform {
+        match self {
+            Orientation::Rotate0 => Transform::IDENTITY,
+            Orientation::Rotate90 => Transform::ROTATE_90,
+            Orientation::Rotate180 => Transform::ROTATE_180,
+            Orientation::Rotate270 => Transform::ROTATE_270,
+        }
+    }
+}
+
+/// A source of the current orientation of the display, for example an
+/// accelerometer
+///
+/// The driver queries its provider once per touch report, applying the
+/// rotation reported following its configured
+/// [transform](crate::Config::transform).
+pub trait OrientationProvider {
+    /// The current orientation of the display
+    fn orientation(&mut self) -> Orientation;
+}
+
+impl<F> OrientationProvider for F
+where
+    F: FnMut() -> Orientation,
+{
+    fn orientation(&mut self) -> Orientation {
+        self()
+    }
+}
+
+/// Stand-in for the orientation provider, on displays which do not rotate
+///
+/// Used by the driver when it has not been provided with an orientation
+/// provider; it always reports [Orientation::Rotate0].
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct NoRotation;
+
+impl OrientationProvider for NoRotation {
+    fn orientation(&mut self) -> Orientation {
+        Orientation::Rotate0
+    }
+}
+
+/// Distance between two contacts
+pub fn distance(a: &TouchRecord, b: &TouchRecord) -> u32 {
+    Point::from(a).distance(Point::from(b))
+}
+
+/// Point halfway between two contacts
+pub fn midpoint(a: &TouchRecord, b: &TouchRecord) -> Point {
+    Point::from(a).midpoint(Point::from(b))
+}
+
+/// Angle of the line from contact `a` to contact `b`, in hundredths of a
+/// degree
+///
+/// The angle is measured from the positive X axis towards the positive Y
+/// axis, and is in the range `-18000..=18000`.
+pub fn angle(a: &TouchRecord, b: &TouchRecord) -> i32 {
+    Point::from(a).angle_to(Point::from(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_lengths_into_physical_units() {
+        let geometry = PanelGeometry::new(320, 240, 64_000, 48_000);
+        assert_eq!(geometry.length_um(50), 10_000);
+
+        // Lengths are scaled by the mean pitch of the two axes
+        let geometry = PanelGeometry::new(100, 100, 10_000, 20_000);
+        assert_eq!(geometry.length_um(10), 1_500);
+
+        let geometry = PanelGeometry::new(0, 0, 10_000, 10_000);
+        assert_eq!(geometry.length_um(1), 10_000);
+    }
+
+    #[test]
+    fn estimates_contact_area() {
+        let geometry = PanelGeometry::new(320, 240, 64_000, 48_000);
+        let record = TouchRecord::new(1, 10, 10).with_major_axis_length(50);
+
+        assert_eq!(geometry.major_axis_um(&record), 10_000);
+        assert_eq!(geometry.contact_area_mm2(&record), 78);
+        assert_eq!(geometry.contact_area_mm2(&TouchRecord::new(1, 10, 10)), 0);
+    }
+
+    #[test]
+    fn measures_between_two_contacts() {
+        let a = TouchRecord::new(1, 10, 10);
+        let b = TouchRecord::new(2, 40, 50);
+
+        assert_eq!(distance(&a, &b), 50);
+        assert!(midpoint(&a, &b) == Point::new(25, 30));
+        assert!((angle(&a, &b) - 5_313).abs() <= 25);
+        assert!((angle(&b, &a) + 12_687).abs() <= 25);
+    }
+
+    #[test]
+    fn measures_between_points() {
+        let a = Point::new(40, 10);
+        let b = Point::new(10, 50);
+
+        assert_eq!(a.distance_squared(b), 2_500);
+        assert_eq!(b.distance_squared(a), 2_500);
+        assert_eq!(a.distance(b), 50);
+        assert!(a.midpoint(b) == Point::new(25, 30));
+        assert!(
+            Point::new(u16::MAX, 0).midpoint(Point::new(u16::MAX, 1)) == Point::new(u16::MAX, 0)
+        );
+    }
+
+    #[test]
+    fn contains_points_within_rect() {
+        let rect = Rect::new(Point::new(10, 20), 30, 40);
+
+        assert!(rect.contains(Point::new(10, 20)));
+        assert!(rect.contains(Point::new(39, 59)));
+        assert!(!rect.contains(Point::new(40, 30)));
+        assert!(!rect.contains(Point::new(20, 60)));
+        assert!(!rect.contains(Point::new(9, 30)));
+        assert!(rect.center() == Point::new(25, 40));
+        assert!(Rect::new(Point::new(u16::MAX, 0), 10, 10).center() == Point::new(u16::MAX, 5));
+    }
+
+    #[test]
+    fn composes_transforms() {
+        let max = Point::new(99, 199);
+        let transforms = [false, true].iter().flat_map(|&mirror_x| {
+            [false, true].iter().flat_map(move |&mirror_y| {
+                [false, true]
+                    .iter()
+                    .map(move |&swap_xy| Transform::new(mirror_x, mirror_y, swap_xy))
+            })
+        });
+
+        for first in transforms.clone() {
+            for next in transforms.clone() {
+                let next_max = if first.swap_xy {
+                    Point::new(max.y, max.x)
+                } else {
+                    max
+                };
+
+                for point in [Point::new(0, 0), Point::new(10, 20), Point::new(99, 150)] {
+                    let expected = next.apply(first.apply(point, max), next_max);
+                    assert!(first.then(next).apply(point, max) == expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cancels_opposite_rotations() {
+        assert!(Transform::ROTATE_90.then(Transform::ROTATE_270) == Transform::IDENTITY);
+        assert!(Transform::ROTATE_90.then(Transform::ROTATE_90) == Transform::ROTATE_180);
+        assert!(Transform::ROTATE_180.then(Transform::ROTATE_180) == Transform::IDENTITY);
+    }
+
+    #[test]
+    fn queries_orientation_providers() {
+        let mut provider = || Orientation::Rotate90;
+
+        assert!(provider.orientation() == Orientation::Rotate90);
+        assert!(NoRotation.orientation() == Orientation::Rotate0);
+    }
+
+    #[test]
+    fn rotates_and_mirrors_points() {
+        let max = Point::new(319, 239);
+        let point = Point::new(10, 20);
+
+        assert!(Transform::IDENTITY.apply(point, max) == point);
+        assert!(Transform::ROTATE_90.apply(point, max) == Point::new(219, 10));
+        assert!(Transform::ROTATE_180.apply(point, max) == Point::new(309, 219));
+        assert!(Transform::ROTATE_270.apply(point, max) == Point::new(20, 309));
+        assert!(Transform::new(true, false, false).apply(point, max) == Point::new(309, 20));
+
+        // Points beyond the range are clamped before being transformed
+        assert!(Transform::ROTATE_180.apply(Point::new(400, 300), max) == Point::new(0, 0));
+    }
+
+    #[test]
+    fn transforms_follow_orientation() {
+        assert!(Orientation::default() == Orientation::Rotate0);
+        assert!(Orientation::Rotate0.transform() == Transform::IDENTITY);
+        assert!(Orientation::Rotate90.transform() == Transform::ROTATE_90);
+        assert!(Orientation::Rotate180.transform() == Transform::ROTATE_180);
+        assert!(Orientation::Rotate270.transform() == Transform::ROTATE_270);
+    }
+}
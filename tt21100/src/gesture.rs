@@ -0,0 +1,1850 @@
+//! Recognition of gestures from the events emitted by a
+//! [Tracker](crate::tracker::Tracker)
+//!
+//! Each recognizer may be used on its own, or several may be combined using
+//! an [Arbiter] so that only the intended gesture is emitted when more than
+//! one could match. A [GestureEngine] combines an arbiter with a tracker of
+//! its own, recognizing gestures directly from the events read from the
+//! device.
+//!
+//! This module is only available when the `gesture` feature is enabled.
+
+use crate::{
+    geom::Point,
+    time::Instant,
+    tracker::{TouchEvent, TouchEvents, Tracker, TrackerConfig},
+    Event,
+    MAX_TOUCHES,
+};
+
+/// A recognized gesture
+///
+/// A [TapRecognizer] reports each tap of a sequence as a [Gesture::Tap],
+/// counting the taps made so far. An [Arbiter] instead reports the second tap
+/// of a sequence as a [Gesture::DoubleTap]; use [ArbiterConfig::defer_taps] to
+/// receive only the final tap of each sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    /// The panel was tapped; `count` is the number of taps made in quick
+    /// succession at roughly the same position, including this one
+    ///
+    /// An [Arbiter] reports a tap with a `count` of two as a
+    /// [Gesture::DoubleTap].
+    Tap {
+        /// Position at which the tap was made
+        position: Point,
+        /// Number of taps made in succession
+        count: u8,
+    },
+    /// The panel was tapped twice in quick succession at roughly the same
+    /// position
+    ///
+    /// Only emitted by an [Arbiter].
+    DoubleTap {
+        /// Position at which the second tap was made
+        position: Point,
+    },
+    /// A contact was held in place and then lifted without being dragged
+    LongPress {
+        /// Position at which the contact was held
+        position: Point,
+    },
+    /// A contact was held in place and then dragged
+    HoldDrag {
+        /// Stage of the drag
+        phase: GesturePhase,
+        /// Current position of the contact
+        position: Point,
+    },
+    /// Two contacts were moved towards or away from each other
+    Pinch {
+        /// Stage of the pinch
+        phase: GesturePhase,
+        /// Point halfway between the two contacts
+        center: Point,
+        /// Distance between the contacts relative to that when the pinch
+        /// began, in thousandths
+        scale: u32,
+    },
+    /// Two contacts were rotated about each other
+    Rotate {
+        /// Stage of the rotation
+        phase: GesturePhase,
+        /// Point halfway between the two contacts
+        center: Point,
+        /// Angle through which the contacts have rotated since the rotation
+        /// began, in hundredths of a degree
+        angle: i32,
+        /// Angle through which the contacts have rotated since the previous
+        /// update, in hundredths of a degree
+        delta: i32,
+    },
+    /// A contact was moved quickly across the panel and lifted
+    Swipe {
+        /// Direction in which the contact was moved
+        direction: SwipeDirection,
+        /// Position at which the contact was placed
+        start: Point,
+        /// Position at which the contact was lifted
+        end: Point,
+    },
+}
+
+/// Direction of a [Gesture::Swipe]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwipeDirection {
+    /// Towards smaller X coordinates
+    Left,
+    /// Towards larger X coordinates
+    Right,
+    /// Towards smaller Y coordinates
+    Up,
+    /// Towards larger Y coordinates
+    Down,
+}
+
+/// Stage of a gesture which continues over time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GesturePhase {
+    /// The gesture has begun
+    Start,
+    /// The gesture has progressed
+    Update,
+    /// The gesture has finished
+    End,
+}
+
+/// Configuration for a [TapRecognizer]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TapConfig {
+    /// Greatest distance a contact may move while down for it to count as a
+    /// tap
+    pub max_distance: u16,
+    /// Longest time a contact may remain down for it to count as a tap, in
+    /// milliseconds
+    pub max_duration_ms: u32,
+    /// Longest time between the end of one tap and the start of the next for
+    /// them to be counted together, in milliseconds
+    pub interval_ms: u32,
+    /// Greatest distance between successive taps for them to be counted
+    /// together
+    pub max_spacing: u16,
+}
+
+impl Default for TapConfig {
+    fn default() -> Self {
+        Self {
+            max_distance: 10,
+            max_duration_ms: 250,
+            interval_ms: 300,
+            max_spacing: 30,
+        }
+    }
+}
+
+/// Most recently completed tap in a sequence
+#[derive(Debug, Clone, Copy)]
+struct LastTap {
+    /// Position at which the tap was made
+    position: Point,
+    /// Time at which the contact was lifted
+    time: Instant,
+    /// Number of taps in the sequence so far
+    count: u8,
+}
+
+/// Contact which may become a tap
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    /// ID of the contact
+    touch_id: u8,
+    /// Position at which the contact was placed
+    origin: Point,
+    /// Time at which the contact was placed
+    down_at: Instant,
+}
+
+/// Recognizes taps, counting those made in quick succession
+///
+/// A [Gesture::Tap] is emitted as each tap completes, so a sequence of five
+/// quick taps emits taps with counts one through five. Only taps made with a
+/// single contact are recognized.
+#[derive(Debug, Clone)]
+pub struct TapRecognizer {
+    /// Recognizer configuration
+    config: TapConfig,
+    /// Contact which may become a tap, if any
+    candidate: Option<Candidate>,
+    /// Has a second contact been placed while the candidate was down?
+    cancelled: bool,
+    /// Most recently completed tap, if any
+    last: Option<LastTap>,
+}
+
+impl TapRecognizer {
+    /// Create a new recognizer using the provided configuration
+    pub fn new(config: TapConfig) -> Self {
+        Self {
+            config,
+            candidate: None,
+            cancelled: false,
+            last: None,
+        }
+    }
+
+    /// The configuration of the recognizer
+    pub fn config(&self) -> &TapConfig {
+        &self.config
+    }
+
+    /// Replace the configuration of the recognizer, taking effect from the
+    /// next update
+    pub fn set_config(&mut self, config: TapConfig) {
+        self.config = config;
+    }
+
+    /// Is a contact which may become a tap currently down?
+    pub fn is_pending(&self) -> bool {
+        self.candidate.is_some()
+    }
+
+    /// Abandon any tap in progress and forget any previous taps
+    pub fn reset(&mut self) {
+        self.candidate = None;
+        self.cancelled = false;
+        self.last = None;
+    }
+
+    /// Update the recognizer with an event emitted by a
+    /// [Tracker](crate::tracker::Tracker), returning the tap it completes, if
+    /// any
+    pub fn update(&mut self, event: &TouchEvent, now: Instant) -> Option<Gesture> {
+        match *event {
+            TouchEvent::Down(record) => {
+                if self.candidate.is_some() {
+                    self.cancelled = true;
+                } else {
+                    self.candidate = Some(Candidate {
+                        touch_id: record.touch_id,
+                        origin: Point::from(&record),
+                        down_at: now,
+                    });
+                    self.cancelled = false;
+                }
+
+                None
+            }
+            TouchEvent::Move(_) => None,
+            TouchEvent::Up(record, stats) => {
+                let candidate = self.candidate?;
+                if record.touch_id != candidate.touch_id {
+                    return None;
+                }
+                self.candidate = None;
+
+                let origin = candidate.origin;
+                let position = Point::from(&record);
+                let max_distance = u32::from(self.config.max_distance);
+                if self.cancelled
+                    || stats.duration_ms > self.config.max_duration_ms
+                    || origin.distance_squared(position) > max_distance * max_distance
+                {
+                    self.last = None;
+                    return None;
+                }
+
+                let max_spacing = u32::from(self.config.max_spacing);
+                let count = match self.last {
+                    Some(last)
+                        if candidate.down_at.millis_since(last.time) <= self.config.interval_ms
+                            && last.position.distance_squared(origin)
+                                <= max_spacing * max_spacing =>
+                    {
+                        last.count.saturating_add(1)
+                    }
+                    _ => 1,
+                };
+
+                self.last = Some(LastTap {
+                    position: origin,
+                    time: now,
+                    count,
+                });
+
+                Some(Gesture::Tap {
+                    position: origin,
+                    count,
+                })
+            }
+        }
+    }
+}
+
+/// Configuration for a [LongPressRecognizer]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LongPressConfig {
+    /// Time a contact must be held in place to begin a long press, in
+    /// milliseconds
+    pub duration_ms: u32,
+    /// Greatest distance a contact may move while being held in place
+    pub max_distance: u16,
+}
+
+impl Default for LongPressConfig {
+    fn default() -> Self {
+        Self {
+            duration_ms: 500,
+            max_distance: 10,
+        }
+    }
+}
+
+/// Stage of a press being recognized
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PressState {
+    /// The contact has not yet been held for long enough
+    Pressed,
+    /// The contact has been held for long enough, but not yet dragged
+    Held,
+    /// The contact is being dragged after having been held
+    Dragging,
+}
+
+/// Contact which may become a long press or hold-and-drag
+#[derive(Debug, Clone, Copy)]
+struct Press {
+    /// ID of the contact
+    touch_id: u8,
+    /// Position at which the contact was placed
+    origin: Point,
+    /// Time at which the contact was placed
+    down_at: Instant,
+    /// Stage of the press
+    state: PressState,
+}
+
+/// Recognizes long presses, and drags which begin with a long press
+///
+/// Holding a contact in place and lifting it emits a [Gesture::LongPress],
+/// while holding it in place and then dragging it emits a sequence of
+/// [Gesture::HoldDrag]s instead; the two are never both emitted for a single
+/// contact. A long press is therefore only emitted once the contact is lifted.
+///
+/// [LongPressRecognizer::poll] should be called periodically, as the device
+/// may not report a contact which is held perfectly still.
+#[derive(Debug, Clone)]
+pub struct LongPressRecognizer {
+    /// Recognizer configuration
+    config: LongPressConfig,
+    /// Contact currently being recognized, if any
+    press: Option<Press>,
+}
+
+impl LongPressRecognizer {
+    /// Create a new recognizer using the provided configuration
+    pub fn new(config: LongPressConfig) -> Self {
+        Self {
+            config,
+            press: None,
+        }
+    }
+
+    /// Abandon any press in progress
+    pub fn reset(&mut self) {
+        self.press = None;
+    }
+
+    /// The configuration of the recognizer
+    pub fn config(&self) -> &LongPressConfig {
+        &self.config
+    }
+
+    /// Replace the configuration of the recognizer, taking effect from the
+    /// next update
+    pub fn set_config(&mut self, config: LongPressConfig) {
+        self.config = config;
+    }
+
+    /// Has the current contact been held in place for long enough to begin a
+    /// long press?
+    pub fn is_held(&self) -> bool {
+        self.press
+            .map_or(false, |press| press.state != PressState::Pressed)
+    }
+
+    /// Update the recognizer with an event emitted by a
+    /// [Tracker](crate::tracker::Tracker), returning the resulting gesture, if
+    /// any
+    pub fn update(&mut self, event: &TouchEvent, now: Instant) -> Option<Gesture> {
+        match *event {
+            TouchEvent::Down(record) => {
+                // A second contact cancels a press which has not yet been held
+                match self.press {
+                    None => {
+                        self.press = Some(Press {
+                            touch_id: record.touch_id,
+                            origin: Point::from(&record),
+                            down_at: now,
+                            state: PressState::Pressed,
+                        });
+                    }
+                    Some(press) if press.state == PressState::Pressed => self.press = None,
+                    Some(_) => {}
+                }
+
+                None
+            }
+            TouchEvent::Move(record) => {
+                self.poll(now);
+
+                let max_distance = u32::from(self.config.max_distance);
+                let press = self.press.as_mut()?;
+                if record.touch_id != press.touch_id {
+                    return None;
+                }
+
+                let position = Point::from(&record);
+                let moved = press.origin.distance_squared(position) > max_distance * max_distance;
+                match press.state {
+                    PressState::Pressed if moved => {
+                        self.press = None;
+                        None
+                    }
+                    PressState::Held if moved => {
+                        press.state = PressState::Dragging;
+                        Some(Gesture::HoldDrag {
+                            phase: GesturePhase::Start,
+                            position,
+                        })
+                    }
+                    PressState::Dragging => Some(Gesture::HoldDrag {
+                        phase: GesturePhase::Update,
+                        position,
+                    }),
+                    _ => None,
+                }
+            }
+            TouchEvent::Up(record, _) => {
+                self.poll(now);
+
+                let press = self.press?;
+                if record.touch_id != press.touch_id {
+                    return None;
+                }
+                self.press = None;
+
+                match press.state {
+                    PressState::Pressed => None,
+                    PressState::Held => Some(Gesture::LongPress {
+                        position: press.origin,
+                    }),
+                    PressState::Dragging => Some(Gesture::HoldDrag {
+                        phase: GesturePhase::End,
+                        position: Point::from(&record),
+                    }),
+                }
+            }
+        }
+    }
+
+    /// Advance the press in progress, if any, marking it as held once it has
+    /// remained in place for long enough
+    pub fn poll(&mut self, now: Instant) {
+        if let Some(press) = self.press.as_mut() {
+            if press.state == PressState::Pressed
+                && now.millis_since(press.down_at) >= self.config.duration_ms
+            {
+                press.state = PressState::Held;
+            }
+        }
+    }
+}
+
+/// Configuration for a [SwipeRecognizer]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwipeConfig {
+    /// Least distance a contact must move along the direction of the swipe
+    pub min_distance: u16,
+    /// Longest time a contact may remain down for it to count as a swipe, in
+    /// milliseconds
+    pub max_duration_ms: u32,
+}
+
+impl Default for SwipeConfig {
+    fn default() -> Self {
+        Self {
+            min_distance: 50,
+            max_duration_ms: 300,
+        }
+    }
+}
+
+/// Recognizes swipes left, right, up and down
+///
+/// A [Gesture::Swipe] is emitted once the contact is lifted, in the direction
+/// of the axis along which it moved furthest. Only swipes made with a single
+/// contact are recognized.
+#[derive(Debug, Clone)]
+pub struct SwipeRecognizer {
+    /// Recognizer configuration
+    config: SwipeConfig,
+    /// Contact which may become a swipe, if any
+    candidate: Option<Candidate>,
+    /// Has a second contact been placed while the candidate was down?
+    cancelled: bool,
+}
+
+impl SwipeRecognizer {
+    /// Create a new recognizer using the provided configuration
+    pub fn new(config: SwipeConfig) -> Self {
+        Self {
+            config,
+            candidate: None,
+            cancelled: false,
+        }
+    }
+
+    /// Abandon any swipe in progress
+    pub fn reset(&mut self) {
+        self.candidate = None;
+        self.cancelled = false;
+    }
+
+    /// The configuration of the recognizer
+    pub fn config(&self) -> &SwipeConfig {
+        &self.config
+    }
+
+    /// Replace the configuration of the recognizer, taking effect from the
+    /// next update
+    pub fn set_config(&mut self, config: SwipeConfig) {
+        self.config = config;
+    }
+
+    /// Update the recognizer with an event emitted by a
+    /// [Tracker](crate::tracker::Tracker), returning the swipe it completes,
+    /// if any
+    pub fn update(&mut self, event: &TouchEvent, now: Instant) -> Option<Gesture> {
+        match *event {
+            TouchEvent::Down(record) => {
+                if self.candidate.is_some() {
+                    self.cancelled = true;
+                } else {
+                    self.candidate = Some(Candidate {
+                        touch_id: record.touch_id,
+                        origin: Point::from(&record),
+                        down_at: now,
+                    });
+                    self.cancelled = false;
+                }
+
+                None
+            }
+            TouchEvent::Move(_) => None,
+            TouchEvent::Up(record, stats) => {
+                let candidate = self.candidate?;
+                if record.touch_id != candidate.touch_id {
+                    return None;
+                }
+                self.candidate = None;
+                if self.cancelled || stats.duration_ms > self.config.max_duration_ms {
+                    return None;
+                }
+
+                let start = candidate.origin;
+                let end = Point::from(&record);
+                let dx = i32::from(end.x) - i32::from(start.x);
+                let dy = i32::from(end.y) - i32::from(start.y);
+                let (distance, direction) = if dx.abs() >= dy.abs() {
+                    let direction = if dx < 0 {
+                        SwipeDirection::Left
+                    } else {
+                        SwipeDirection::Right
+                    };
+                    (dx.abs(), direction)
+                } else {
+                    let direction = if dy < 0 {
+                        SwipeDirection::Up
+                    } else {
+                        SwipeDirection::Down
+                    };
+                    (dy.abs(), direction)
+                };
+
+                if distance < i32::from(self.config.min_distance) {
+                    return None;
+                }
+
+                Some(Gesture::Swipe {
+                    direction,
+                    start,
+                    end,
+                })
+            }
+        }
+    }
+}
+
+/// Positions of the two contacts making a two-finger gesture
+#[derive(Debug, Default, Clone, Copy)]
+struct Pair {
+    /// ID and current position of each contact, in the order they were placed
+    contacts: [Option<(u8, Point)>; 2],
+}
+
+impl Pair {
+    /// Update the pair with an event emitted by a
+    /// [Tracker](crate::tracker::Tracker), returning whether either of its
+    /// contacts was affected
+    fn update(&mut self, event: &TouchEvent) -> bool {
+        match *event {
+            TouchEvent::Down(record) => {
+                let slot = self.contacts.iter_mut().find(|slot| slot.is_none());
+                match slot {
+                    Some(slot) => {
+                        *slot = Some((record.touch_id, Point::from(&record)));
+                        true
+                    }
+                    None => false,
+                }
+            }
+            TouchEvent::Move(record) => match self.contact_mut(record.touch_id) {
+                Some(contact) => {
+                    contact.1 = Point::from(&record);
+                    true
+                }
+                None => false,
+            },
+            TouchEvent::Up(record, _) => {
+                let index = self
+                    .contacts
+                    .iter()
+                    .position(|contact| contact.map(|(id, _)| id) == Some(record.touch_id));
+                match index {
+                    Some(index) => {
+                        self.contacts[index] = None;
+                        // Keep the remaining contact first, so that a contact
+                        // placed later becomes the second of the pair
+                        self.contacts.swap(0, 1 - index);
+                        true
+                    }
+                    None => false,
+                }
+            }
+        }
+    }
+
+    /// Positions of both contacts, if both are down
+    fn positions(&self) -> Option<(Point, Point)> {
+        match self.contacts {
+            [Some((_, a)), Some((_, b))] => Some((a, b)),
+            _ => None,
+        }
+    }
+
+    fn contact_mut(&mut self, touch_id: u8) -> Option<&mut (u8, Point)> {
+        self.contacts
+            .iter_mut()
+            .flatten()
+            .find(|(id, _)| *id == touch_id)
+    }
+}
+
+/// Configuration for a [PinchRecognizer]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PinchConfig {
+    /// Least distance between the two contacts for a pinch to be recognized
+    pub min_distance: u16,
+    /// Distance by which the separation of the contacts must change before a
+    /// pinch begins
+    pub min_change: u16,
+}
+
+impl Default for PinchConfig {
+    fn default() -> Self {
+        Self {
+            min_distance: 20,
+            min_change: 10,
+        }
+    }
+}
+
+/// Recognizes two contacts being moved towards or away from each other
+///
+/// Once the separation of the contacts has changed by
+/// [PinchConfig::min_change], a [Gesture::Pinch] is emitted each time either
+/// contact moves, until one of them is lifted. The scale is measured against
+/// the separation of the contacts when the second was placed.
+#[derive(Debug, Clone)]
+pub struct PinchRecognizer {
+    /// Recognizer configuration
+    config: PinchConfig,
+    /// Contacts making the pinch
+    pair: Pair,
+    /// Distance between the contacts when the second was placed, if at least
+    /// [PinchConfig::min_distance]
+    origin: Option<u32>,
+    /// Most recently emitted pinch, while the pinch is in progress
+    last: Option<(Point, u32)>,
+}
+
+impl PinchRecognizer {
+    /// Create a new recognizer using the provided configuration
+    pub fn new(config: PinchConfig) -> Self {
+        Self {
+            config,
+            pair: Pair::default(),
+            origin: None,
+            last: None,
+        }
+    }
+
+    /// Abandon any pinch in progress
+    pub fn reset(&mut self) {
+        self.pair = Pair::default();
+        self.origin = None;
+        self.last = None;
+    }
+
+    /// The configuration of the recognizer
+    pub fn config(&self) -> &PinchConfig {
+        &self.config
+    }
+
+    /// Replace the configuration of the recognizer, taking effect from the
+    /// next update
+    pub fn set_config(&mut self, config: PinchConfig) {
+        self.config = config;
+    }
+
+    /// Is a pinch in progress?
+    pub fn is_pinching(&self) -> bool {
+        self.last.is_some()
+    }
+
+    /// Update the recognizer with an event emitted by a
+    /// [Tracker](crate::tracker::Tracker), returning the resulting pinch, if
+    /// any
+    pub fn update(&mut self, event: &TouchEvent) -> Option<Gesture> {
+        let was_paired = self.pair.positions().is_some();
+        if !self.pair.update(event) {
+            return None;
+        }
+
+        let (a, b) = match self.pair.positions() {
+            Some(positions) => positions,
+            None => {
+                self.origin = None;
+                let (center, scale) = self.last.take()?;
+                return Some(Gesture::Pinch {
+                    phase: GesturePhase::End,
+                    center,
+                    scale,
+                });
+            }
+        };
+
+        let distance = a.distance(b);
+        if !was_paired {
+            let min_distance = u32::from(self.config.min_distance).max(1);
+            self.origin = Some(distance).filter(|&distance| distance >= min_distance);
+            return None;
+        }
+
+        let origin = self.origin?;
+        let phase = if self.last.is_some() {
+            GesturePhase::Update
+        } else if distance.max(origin) - distance.min(origin) >= u32::from(self.config.min_change) {
+            GesturePhase::Start
+        } else {
+            return None;
+        };
+
+        let center = a.midpoint(b);
+        let scale =
+            (u64::from(distance) * 1000 / u64::from(origin)).min(u64::from(u32::MAX)) as u32;
+        self.last = Some((center, scale));
+
+        Some(Gesture::Pinch {
+            phase,
+            center,
+            scale,
+        })
+    }
+}
+
+/// Configuration for a [RotateRecognizer]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RotateConfig {
+    /// Least distance between the two contacts for a rotation to be
+    /// recognized; the angle between closer contacts is too noisy to be
+    /// useful
+    pub min_distance: u16,
+    /// Angle through which the contacts must rotate before a rotation begins,
+    /// in hundredths of a degree
+    pub min_angle: u16,
+}
+
+impl Default for RotateConfig {
+    fn default() -> Self {
+        Self {
+            min_distance: 40,
+            min_angle: 500,
+        }
+    }
+}
+
+/// Recognizes two contacts being rotated about each other
+///
+/// Once the contacts have rotated through [RotateConfig::min_angle], a
+/// [Gesture::Rotate] is emitted each time either contact moves, until one of
+/// them is lifted. Angles are measured clockwise on the panel, and the total
+/// angle is not limited to a single turn.
+#[derive(Debug, Clone)]
+pub struct RotateRecognizer {
+    /// Recognizer configuration
+    config: RotateConfig,
+    /// Contacts making the rotation
+    pair: Pair,
+    /// Angle of the line between the contacts at the previous update, while
+    /// they are far enough apart
+    previous: Option<i32>,
+    /// Total angle rotated through since the second contact was placed
+    angle: i32,
+    /// Has the rotation begun?
+    rotating: bool,
+    /// Center of the most recently emitted rotation
+    center: Point,
+}
+
+impl RotateRecognizer {
+    /// Create a new recognizer using the provided configuration
+    pub fn new(config: RotateConfig) -> Self {
+        Self {
+            config,
+            pair: Pair::default(),
+            previous: None,
+            angle: 0,
+            rotating: false,
+            center: Point::default(),
+        }
+    }
+
+    /// Abandon any rotation in progress
+    pub fn reset(&mut self) {
+        self.pair = Pair::default();
+        self.previous = None;
+        self.angle = 0;
+        self.rotating = false;
+    }
+
+    /// The configuration of the recognizer
+    pub fn config(&self) -> &RotateConfig {
+        &self.config
+    }
+
+    /// Replace the configuration of the recognizer, taking effect from the
+    /// next update
+    pub fn set_config(&mut self, config: RotateConfig) {
+        self.config = config;
+    }
+
+    /// Is a rotation in progress?
+    pub fn is_rotating(&self) -> bool {
+        self.rotating
+    }
+
+    /// Update the recognizer with an event emitted by a
+    /// [Tracker](crate::tracker::Tracker), returning the resulting rotation,
+    /// if any
+    pub fn update(&mut self, event: &TouchEvent) -> Option<Gesture> {
+        let was_paired = self.pair.positions().is_some();
+        if !self.pair.update(event) {
+            return None;
+        }
+
+        let (a, b) = match self.pair.positions() {
+            Some(positions) => positions,
+            None => {
+                let rotating = self.rotating;
+                let angle = self.angle;
+                self.previous = None;
+                self.angle = 0;
+                self.rotating = false;
+
+                return Some(Gesture::Rotate {
+                    phase: GesturePhase::End,
+                    center: self.center,
+                    angle,
+                    delta: 0,
+                })
+                .filter(|_| rotating);
+            }
+        };
+
+        let min_distance = u32::from(self.config.min_distance);
+        let current = Some(a.angle_to(b)).filter(|_| a.distance(b) >= min_distance);
+        let previous = if was_paired { self.previous } else { None };
+        self.previous = current.or(previous);
+
+        let delta = match (previous, current) {
+            (Some(previous), Some(current)) => normalize(current - previous),
+            _ => return None,
+        };
+        self.angle = self.angle.saturating_add(delta);
+
+        let phase = if self.rotating {
+            GesturePhase::Update
+        } else if self.angle.abs() >= i32::from(self.config.min_angle) {
+            self.rotating = true;
+            GesturePhase::Start
+        } else {
+            return None;
+        };
+        self.center = a.midpoint(b);
+
+        Some(Gesture::Rotate {
+            phase,
+            center: self.center,
+            angle: self.angle,
+            delta: if phase == GesturePhase::Start {
+                self.angle
+            } else {
+                delta
+            },
+        })
+    }
+}
+
+/// Wrap an angle difference, in hundredths of a degree, into the range
+/// `-18000..=18000`
+fn normalize(angle: i32) -> i32 {
+    if angle > 18000 {
+        angle - 36000
+    } else if angle < -18000 {
+        angle + 36000
+    } else {
+        angle
+    }
+}
+
+/// A kind of [Gesture], used to configure an [Arbiter]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GestureKind {
+    /// [Gesture::Tap]
+    Tap,
+    /// [Gesture::DoubleTap]
+    DoubleTap,
+    /// [Gesture::LongPress]
+    LongPress,
+    /// [Gesture::HoldDrag]
+    HoldDrag,
+    /// [Gesture::Pinch]
+    Pinch,
+    /// [Gesture::Rotate]
+    Rotate,
+    /// [Gesture::Swipe]
+    Swipe,
+}
+
+impl GestureKind {
+    pub(crate) fn of(gesture: &Gesture) -> Self {
+        match gesture {
+            Gesture::Tap { .. } => GestureKind::Tap,
+            Gesture::DoubleTap { .. } => GestureKind::DoubleTap,
+            Gesture::LongPress { .. } => GestureKind::LongPress,
+            Gesture::HoldDrag { .. } => GestureKind::HoldDrag,
+            Gesture::Pinch { .. } => GestureKind::Pinch,
+            Gesture::Rotate { .. } => GestureKind::Rotate,
+            Gesture::Swipe { .. } => GestureKind::Swipe,
+        }
+    }
+}
+
+/// Configuration for an [Arbiter]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArbiterConfig {
+    /// Configuration of the tap recognizer
+    pub tap: TapConfig,
+    /// Configuration of the long press recognizer
+    pub long_press: LongPressConfig,
+    /// Configuration of the swipe recognizer
+    pub swipe: SwipeConfig,
+    /// Configuration of the pinch recognizer
+    pub pinch: PinchConfig,
+    /// Configuration of the rotation recognizer
+    pub rotate: RotateConfig,
+    /// Kinds of gesture to emit, in decreasing order of priority
+    ///
+    /// When more than one gesture is recognized from a single event, only the
+    /// one with the highest priority is emitted. Kinds which are absent are
+    /// never emitted. Every kind of gesture takes part in the arbitration;
+    /// since a pinch and a rotation are often made by the same movement,
+    /// remove the one which is not wanted so that the other is always
+    /// emitted.
+    pub priority: [Option<GestureKind>; 7],
+    /// Emit only the final tap of each sequence of taps, once no further tap
+    /// can follow it
+    ///
+    /// This prevents a single tap being emitted ahead of a double tap, at the
+    /// cost of delaying every tap by up to [TapConfig::interval_ms].
+    pub defer_taps: bool,
+}
+
+impl Default for ArbiterConfig {
+    fn default() -> Self {
+        Self {
+            tap: TapConfig::default(),
+            long_press: LongPressConfig::default(),
+            swipe: SwipeConfig::default(),
+            pinch: PinchConfig::default(),
+            rotate: RotateConfig::default(),
+            priority: [
+                Some(GestureKind::HoldDrag),
+                Some(GestureKind::Pinch),
+                Some(GestureKind::Rotate),
+                Some(GestureKind::LongPress),
+                Some(GestureKind::Swipe),
+                Some(GestureKind::DoubleTap),
+                Some(GestureKind::Tap),
+            ],
+            defer_taps: false,
+        }
+    }
+}
+
+impl From<GesturePreset> for ArbiterConfig {
+    fn from(preset: GesturePreset) -> Self {
+        preset.config()
+    }
+}
+
+/// Tuned gesture configurations for common kinds of product
+///
+/// Presets are a starting point; individual thresholds of the resulting
+/// [ArbiterConfig] may still be adjusted afterwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GesturePreset {
+    /// Tolerances similar to those of a phone, for use with bare fingers; the
+    /// same as [ArbiterConfig::default]
+    Phone,
+    /// Generous tolerances for public kiosks and gloved hands, where contacts
+    /// are larger, less steady and slower to lift
+    Kiosk,
+    /// Tight tolerances for precise input, such as with a stylus
+    Precise,
+}
+
+impl GesturePreset {
+    /// The gesture configuration of the preset
+    pub fn config(&self) -> ArbiterConfig {
+        let (tap, long_press, swipe) = match self {
+            GesturePreset::Phone => (
+                TapConfig::default(),
+                LongPressConfig::default(),
+                SwipeConfig::default(),
+            ),
+            GesturePreset::Kiosk => (
+                TapConfig {
+                    max_distance: 25,
+                    max_duration_ms: 400,
+                    interval_ms: 450,
+                    max_spacing: 60,
+                },
+                LongPressConfig {
+                    duration_ms: 800,
+                    max_distance: 25,
+                },
+                SwipeConfig {
+                    min_distance: 80,
+                    max_duration_ms: 500,
+                },
+            ),
+            GesturePreset::Precise => (
+                TapConfig {
+                    max_distance: 4,
+                    max_duration_ms: 200,
+                    interval_ms: 250,
+                    max_spacing: 15,
+                },
+                LongPressConfig {
+                    duration_ms: 600,
+                    max_distance: 4,
+                },
+                SwipeConfig {
+                    min_distance: 30,
+                    max_duration_ms: 250,
+                },
+            ),
+        };
+
+        ArbiterConfig {
+            tap,
+            long_press,
+            swipe,
+            ..ArbiterConfig::default()
+        }
+    }
+}
+
+/// Iterator over the [Gesture]s resulting from a single update of an
+/// [Arbiter] or a [GestureEngine]
+#[derive(Debug, Clone)]
+pub struct Gestures {
+    gestures: [Option<Gesture>; 2 * MAX_TOUCHES],
+    len: usize,
+    index: usize,
+}
+
+impl Gestures {
+    fn new() -> Self {
+        Self {
+            gestures: [None; 2 * MAX_TOUCHES],
+            len: 0,
+            index: 0,
+        }
+    }
+
+    fn push(&mut self, gesture: Gesture) {
+        if let Some(slot) = self.gestures.get_mut(self.len) {
+            *slot = Some(gesture);
+            self.len += 1;
+        }
+    }
+}
+
+impl Iterator for Gestures {
+    type Item = Gesture;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let gesture = self.gestures.get(self.index).copied().flatten();
+        if gesture.is_some() {
+            self.index += 1;
+        }
+
+        gesture
+    }
+}
+
+/// Combines the tap, long press, swipe, pinch and rotation recognizers,
+/// ensuring that only the intended gesture is emitted
+///
+/// Once a [Gesture::HoldDrag] has begun it claims its contact, and no other
+/// gesture is emitted until it ends. [Arbiter::poll] should be called
+/// periodically, both to detect contacts which are held perfectly still and to
+/// emit deferred taps.
+#[derive(Debug, Clone)]
+pub struct Arbiter {
+    /// Arbiter configuration
+    config: ArbiterConfig,
+    /// Tap recognizer
+    taps: TapRecognizer,
+    /// Long press recognizer
+    long_press: LongPressRecognizer,
+    /// Swipe recognizer
+    swipes: SwipeRecognizer,
+    /// Pinch recognizer
+    pinch: PinchRecognizer,
+    /// Rotation recognizer
+    rotate: RotateRecognizer,
+    /// Is a hold-and-drag in progress?
+    claimed: bool,
+    /// Tap waiting to be emitted, along with the time at which it was made
+    deferred: Option<(Gesture, Instant)>,
+}
+
+impl Arbiter {
+    /// Create a new arbiter using the provided configuration
+    pub fn new(config: ArbiterConfig) -> Self {
+        Self {
+            config,
+            taps: TapRecognizer::new(config.tap),
+            long_press: LongPressRecognizer::new(config.long_press),
+            swipes: SwipeRecognizer::new(config.swipe),
+            pinch: PinchRecognizer::new(config.pinch),
+            rotate: RotateRecognizer::new(config.rotate),
+            claimed: false,
+            deferred: None,
+        }
+    }
+
+    /// Abandon any gestures in progress, discarding any deferred tap
+    pub fn reset(&mut self) {
+        self.taps.reset();
+        self.long_press.reset();
+        self.swipes.reset();
+        self.pinch.reset();
+        self.rotate.reset();
+        self.claimed = false;
+        self.deferred = None;
+    }
+
+    /// The configuration of the arbiter
+    pub fn config(&self) -> &ArbiterConfig {
+        &self.config
+    }
+
+    /// Replace the configuration of the arbiter and its recognizers, taking
+    /// effect from the next update
+    pub fn set_config(&mut self, config: ArbiterConfig) {
+        self.config = config;
+        self.taps.set_config(config.tap);
+        self.long_press.set_config(config.long_press);
+        self.swipes.set_config(config.swipe);
+        self.pinch.set_config(config.pinch);
+        self.rotate.set_config(config.rotate);
+    }
+
+    /// Update the arbiter with an event emitted by a
+    /// [Tracker](crate::tracker::Tracker), returning the resulting gestures
+    pub fn update(&mut self, event: &TouchEvent, now: Instant) -> Gestures {
+        let mut gestures = Gestures::new();
+
+        let tap = self.taps.update(event, now).map(double_tap);
+        let long_press = self.long_press.update(event, now);
+        let swipe = self.swipes.update(event, now);
+        let pinch = self.pinch.update(event);
+        let rotate = self.rotate.update(event);
+
+        let candidates = if self.claimed {
+            [None, long_press, None, None, None]
+        } else {
+            [tap, long_press, swipe, pinch, rotate]
+        };
+        let gesture = candidates
+            .iter()
+            .flatten()
+            .filter_map(|gesture| self.rank(gesture).map(|rank| (rank, *gesture)))
+            .min_by_key(|(rank, _)| *rank)
+            .map(|(_, gesture)| gesture);
+
+        if let Some(Gesture::HoldDrag { phase, .. }) = gesture {
+            self.claimed = phase != GesturePhase::End;
+        }
+
+        match gesture {
+            Some(tap @ (Gesture::Tap { .. } | Gesture::DoubleTap { .. }))
+                if self.config.defer_taps =>
+            {
+                // A tap which does not continue the deferred sequence ends it
+                if let (Some((deferred, _)), Gesture::Tap { count: 1, .. }) = (self.deferred, tap) {
+                    gestures.push(deferred);
+                }
+                self.deferred = Some((tap, now));
+            }
+            Some(gesture) => {
+                gestures.push(gesture);
+            }
+            None => {}
+        }
+
+        gestures
+    }
+
+    /// Advance any gestures in progress, returning a deferred tap once no
+    /// further tap can follow it
+    pub fn poll(&mut self, now: Instant) -> Option<Gesture> {
+        self.long_press.poll(now);
+
+        let (gesture, time) = self.deferred?;
+        if self.taps.is_pending() || now.millis_since(time) <= self.config.tap.interval_ms {
+            return None;
+        }
+        self.deferred = None;
+
+        Some(gesture)
+    }
+
+    /// Priority of a gesture, where lower values take precedence, or `None` if
+    /// it should not be emitted
+    fn rank(&self, gesture: &Gesture) -> Option<usize> {
+        let kind = GestureKind::of(gesture);
+        self.config
+            .priority
+            .iter()
+            .position(|priority| *priority == Some(kind))
+    }
+}
+
+/// Report the second tap of a sequence as a [Gesture::DoubleTap]
+fn double_tap(gesture: Gesture) -> Gesture {
+    match gesture {
+        Gesture::Tap { position, count: 2 } => Gesture::DoubleTap { position },
+        gesture => gesture,
+    }
+}
+
+/// Recognizes gestures directly from the events read from the device
+///
+/// The events are used to update a [Tracker], whose output is in turn used to
+/// update an [Arbiter]. [GestureEngine::poll] should be called periodically,
+/// as for the arbiter.
+#[derive(Debug, Clone)]
+pub struct GestureEngine<const N: usize> {
+    /// Tracker of the contacts from which gestures are recognized
+    tracker: Tracker<N>,
+    /// Arbiter combining the gesture recognizers
+    arbiter: Arbiter,
+}
+
+impl<const N: usize> GestureEngine<N> {
+    /// Create a new engine using the provided configurations
+    pub fn new(tracker: TrackerConfig, config: ArbiterConfig) -> Self {
+        Self {
+            tracker: Tracker::new(tracker),
+            arbiter: Arbiter::new(config),
+        }
+    }
+
+    /// Forget all tracked contacts and abandon any gestures in progress
+    pub fn reset(&mut self) {
+        self.tracker.reset();
+        self.arbiter.reset();
+    }
+
+    /// The tracker of the contacts from which gestures are recognized
+    pub fn tracker(&self) -> &Tracker<N> {
+        &self.tracker
+    }
+
+    /// The configuration of the gesture recognizers
+    pub fn config(&self) -> &ArbiterConfig {
+        self.arbiter.config()
+    }
+
+    /// Replace the configuration of the gesture recognizers, taking effect
+    /// from the next update
+    ///
+    /// A [GesturePreset] can be selected using its configuration.
+    pub fn set_config(&mut self, config: ArbiterConfig) {
+        self.arbiter.set_config(config);
+    }
+
+    /// Update the engine with an event read from the device, returning the
+    /// resulting gestures
+    pub fn update(&mut self, event: &Event, now: Instant) -> Gestures {
+        let events = self.tracker.update(event, now);
+
+        self.arbitrate(events, now)
+    }
+
+    /// Advance any gestures in progress, returning those which complete
+    pub fn poll(&mut self, now: Instant) -> Gestures {
+        let events = self.tracker.poll(now);
+        let mut gestures = self.arbitrate(events, now);
+        if let Some(gesture) = self.arbiter.poll(now) {
+            gestures.push(gesture);
+        }
+
+        gestures
+    }
+
+    fn arbitrate(&mut self, events: TouchEvents, now: Instant) -> Gestures {
+        let mut gestures = Gestures::new();
+        for event in events {
+            for gesture in self.arbiter.update(&event, now) {
+                gestures.push(gesture);
+            }
+        }
+
+        gestures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tracker::ContactStats, TouchRecord};
+
+    fn down(touch_id: u8, x: u16, y: u16) -> TouchEvent {
+        TouchEvent::Down(TouchRecord::new(touch_id, x, y))
+    }
+
+    fn moved(touch_id: u8, x: u16, y: u16) -> TouchEvent {
+        TouchEvent::Move(TouchRecord::new(touch_id, x, y))
+    }
+
+    fn up(touch_id: u8, x: u16, y: u16, duration_ms: u32) -> TouchEvent {
+        let stats = ContactStats {
+            travel: 0,
+            duration_ms,
+        };
+
+        TouchEvent::Up(TouchRecord::new(touch_id, x, y), stats)
+    }
+
+    fn at(millis: u32) -> Instant {
+        Instant::from_millis(millis)
+    }
+
+    /// Tap the panel at the given time, returning the gestures emitted by the
+    /// arbiter as the contact is lifted
+    fn tap(arbiter: &mut Arbiter, millis: u32) -> Gestures {
+        let record = TouchRecord::new(1, 100, 100);
+        let stats = ContactStats {
+            travel: 0,
+            duration_ms: 50,
+        };
+
+        arbiter.update(&TouchEvent::Down(record), Instant::from_millis(millis));
+        arbiter.update(
+            &TouchEvent::Up(record, stats),
+            Instant::from_millis(millis + 50),
+        )
+    }
+
+    #[test]
+    fn reports_second_tap_as_double_tap() {
+        let mut arbiter = Arbiter::new(ArbiterConfig::default());
+        let position = Point::new(100, 100);
+
+        assert!(tap(&mut arbiter, 0).eq([Gesture::Tap { position, count: 1 }]));
+        assert!(tap(&mut arbiter, 100).eq([Gesture::DoubleTap { position }]));
+        assert!(tap(&mut arbiter, 200).eq([Gesture::Tap { position, count: 3 }]));
+    }
+
+    #[test]
+    fn defers_double_taps() {
+        let mut arbiter = Arbiter::new(ArbiterConfig {
+            defer_taps: true,
+            ..ArbiterConfig::default()
+        });
+        let position = Point::new(100, 100);
+
+        assert_eq!(tap(&mut arbiter, 0).count(), 0);
+        assert_eq!(tap(&mut arbiter, 100).count(), 0);
+        assert_eq!(arbiter.poll(Instant::from_millis(200)), None);
+        assert_eq!(
+            arbiter.poll(Instant::from_millis(500)),
+            Some(Gesture::DoubleTap { position })
+        );
+        assert_eq!(arbiter.poll(Instant::from_millis(1000)), None);
+    }
+
+    #[test]
+    fn counts_taps_in_quick_succession() {
+        let mut taps = TapRecognizer::new(TapConfig::default());
+        let mut count = |x, millis| {
+            taps.update(&down(1, x, 100), at(millis));
+            match taps.update(&up(1, x, 100, 50), at(millis + 50)) {
+                Some(Gesture::Tap { count, .. }) => count,
+                _ => 0,
+            }
+        };
+
+        assert_eq!(count(100, 0), 1);
+        assert_eq!(count(110, 200), 2);
+        assert_eq!(count(100, 400), 3);
+        assert_eq!(count(100, 600), 4);
+        assert_eq!(count(100, 800), 5);
+        // Too far from the previous tap, and then too long after it
+        assert_eq!(count(200, 1000), 1);
+        assert_eq!(count(200, 1400), 1);
+    }
+
+    #[test]
+    fn restarts_count_after_failed_tap() {
+        let mut taps = TapRecognizer::new(TapConfig::default());
+
+        taps.update(&down(1, 100, 100), at(0));
+        taps.update(&up(1, 100, 100, 50), at(50));
+        taps.update(&down(1, 100, 100), at(100));
+        assert_eq!(taps.update(&up(1, 100, 100, 400), at(500)), None);
+
+        taps.update(&down(1, 100, 100), at(600));
+        assert_eq!(
+            taps.update(&up(1, 100, 100, 50), at(650)),
+            Some(Gesture::Tap {
+                position: Point::new(100, 100),
+                count: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn ignores_taps_with_two_contacts() {
+        let mut taps = TapRecognizer::new(TapConfig::default());
+
+        taps.update(&down(1, 100, 100), at(0));
+        taps.update(&down(2, 150, 100), at(10));
+        taps.update(&up(2, 150, 100, 40), at(50));
+
+        assert_eq!(taps.update(&up(1, 100, 100, 60), at(60)), None);
+        assert!(!taps.is_pending());
+    }
+
+    #[test]
+    fn drags_after_long_press() {
+        let mut presses = LongPressRecognizer::new(LongPressConfig::default());
+
+        presses.update(&down(1, 100, 100), at(0));
+        assert_eq!(presses.update(&moved(1, 105, 100), at(300)), None);
+        presses.poll(at(500));
+        assert!(presses.is_held());
+
+        assert_eq!(
+            presses.update(&moved(1, 120, 100), at(600)),
+            Some(Gesture::HoldDrag {
+                phase: GesturePhase::Start,
+                position: Point::new(120, 100),
+            })
+        );
+        assert_eq!(
+            presses.update(&moved(1, 140, 100), at(650)),
+            Some(Gesture::HoldDrag {
+                phase: GesturePhase::Update,
+                position: Point::new(140, 100),
+            })
+        );
+        assert_eq!(
+            presses.update(&up(1, 150, 100, 700), at(700)),
+            Some(Gesture::HoldDrag {
+                phase: GesturePhase::End,
+                position: Point::new(150, 100),
+            })
+        );
+    }
+
+    #[test]
+    fn does_not_drag_before_long_press() {
+        let mut presses = LongPressRecognizer::new(LongPressConfig::default());
+
+        presses.update(&down(1, 100, 100), at(0));
+        assert_eq!(presses.update(&moved(1, 120, 100), at(300)), None);
+        assert_eq!(presses.update(&moved(1, 140, 100), at(600)), None);
+        assert_eq!(presses.update(&up(1, 140, 100, 700), at(700)), None);
+    }
+
+    #[test]
+    fn claims_contacts_while_dragging() {
+        let mut arbiter = Arbiter::new(ArbiterConfig::default());
+
+        arbiter.update(&down(1, 100, 100), at(0));
+        arbiter.poll(at(500));
+        assert!(matches!(
+            arbiter.update(&moved(1, 120, 100), at(510)).next(),
+            Some(Gesture::HoldDrag { .. })
+        ));
+
+        arbiter.update(&down(2, 200, 100), at(520));
+        // Would otherwise begin a pinch
+        assert!(arbiter.update(&moved(2, 300, 100), at(530)).eq([]));
+        assert!(arbiter.update(&up(2, 300, 100, 30), at(550)).eq([]));
+
+        assert!(matches!(
+            arbiter.update(&up(1, 120, 100, 560), at(560)).next(),
+            Some(Gesture::HoldDrag {
+                phase: GesturePhase::End,
+                ..
+            })
+        ));
+    }
+
+    /// Rotate and spread two contacts, which begins both a pinch and a
+    /// rotation, returning the gestures emitted by the arbiter
+    fn pinch_and_rotate(arbiter: &mut Arbiter) -> Gestures {
+        arbiter.update(&down(1, 100, 100), at(0));
+        arbiter.update(&down(2, 200, 100), at(10));
+
+        arbiter.update(&moved(2, 100, 250), at(20))
+    }
+
+    #[test]
+    fn emits_gesture_with_highest_priority() {
+        let mut arbiter = Arbiter::new(ArbiterConfig::default());
+        let mut gestures = pinch_and_rotate(&mut arbiter);
+
+        assert!(matches!(gestures.next(), Some(Gesture::Pinch { .. })));
+        assert_eq!(gestures.next(), None);
+    }
+
+    #[test]
+    fn emits_gesture_of_lower_priority_when_other_is_absent() {
+        let mut config = ArbiterConfig::default();
+        config.priority[1] = None;
+        let mut arbiter = Arbiter::new(config);
+        let mut gestures = pinch_and_rotate(&mut arbiter);
+
+        assert!(matches!(
+            gestures.next(),
+            Some(Gesture::Rotate {
+                phase: GesturePhase::Start,
+                ..
+            })
+        ));
+        assert_eq!(gestures.next(), None);
+    }
+
+    #[test]
+    fn never_emits_absent_kinds() {
+        let mut arbiter = Arbiter::new(ArbiterConfig {
+            priority: [Some(GestureKind::Swipe), None, None, None, None, None, None],
+            ..ArbiterConfig::default()
+        });
+
+        assert_eq!(tap(&mut arbiter, 0).count(), 0);
+        assert_eq!(pinch_and_rotate(&mut arbiter).count(), 0);
+    }
+
+    #[test]
+    fn phone_preset_matches_default() {
+        assert_eq!(GesturePreset::Phone.config(), ArbiterConfig::default());
+        assert_eq!(
+            ArbiterConfig::from(GesturePreset::Kiosk),
+            GesturePreset::Kiosk.config()
+        );
+    }
+
+    #[test]
+    fn presets_scale_tolerances() {
+        let tolerance = |preset: GesturePreset| {
+            let config = preset.config();
+            (
+                config.tap.max_distance,
+                config.long_press.max_distance,
+                config.swipe.min_distance,
+            )
+        };
+
+        let (phone, kiosk, precise) = (
+            tolerance(GesturePreset::Phone),
+            tolerance(GesturePreset::Kiosk),
+            tolerance(GesturePreset::Precise),
+        );
+        assert!(precise.0 < phone.0 && phone.0 < kiosk.0);
+        assert!(precise.1 < phone.1 && phone.1 < kiosk.1);
+        assert!(precise.2 < phone.2 && phone.2 < kiosk.2);
+    }
+
+    #[test]
+    fn kiosk_preset_accepts_slower_taps() {
+        let mut arbiter = Arbiter::new(ArbiterConfig::default());
+        let mut kiosk = Arbiter::new(GesturePreset::Kiosk.into());
+
+        for arbiter in [&mut arbiter, &mut kiosk] {
+            arbiter.update(&down(1, 100, 100), at(0));
+        }
+
+        assert_eq!(arbiter.update(&up(1, 100, 100, 300), at(300)).count(), 0);
+        assert!(kiosk
+            .update(&up(1, 100, 100, 300), at(300))
+            .eq([Gesture::Tap {
+                position: Point::new(100, 100),
+                count: 1,
+            }]));
+    }
+
+    #[test]
+    fn reports_long_press_once_lifted() {
+        let mut presses = LongPressRecognizer::new(LongPressConfig::default());
+
+        assert_eq!(presses.update(&down(1, 100, 100), at(0)), None);
+        presses.poll(at(499));
+        assert!(!presses.is_held());
+        presses.poll(at(500));
+        assert!(presses.is_held());
+
+        assert_eq!(presses.update(&moved(1, 105, 105), at(600)), None);
+        assert_eq!(
+            presses.update(&up(1, 105, 105, 700), at(700)),
+            Some(Gesture::LongPress {
+                position: Point::new(100, 100),
+            })
+        );
+        assert!(!presses.is_held());
+    }
+
+    #[test]
+    fn cancels_long_press_before_held() {
+        let mut presses = LongPressRecognizer::new(LongPressConfig::default());
+
+        // Moving too far before being held
+        presses.update(&down(1, 100, 100), at(0));
+        presses.update(&moved(1, 120, 100), at(100));
+        assert_eq!(presses.update(&up(1, 120, 100, 600), at(600)), None);
+
+        // Placing a second contact before being held
+        presses.update(&down(1, 100, 100), at(1_000));
+        presses.update(&down(2, 200, 200), at(1_100));
+        presses.poll(at(1_600));
+        assert!(!presses.is_held());
+        assert_eq!(presses.update(&up(1, 100, 100, 600), at(1_600)), None);
+    }
+
+    #[test]
+    fn rejects_slow_or_moved_taps() {
+        let mut taps = TapRecognizer::new(TapConfig::default());
+
+        taps.update(&down(1, 100, 100), at(0));
+        assert_eq!(taps.update(&up(1, 100, 100, 300), at(300)), None);
+
+        taps.update(&down(1, 100, 100), at(1_000));
+        assert_eq!(taps.update(&up(1, 150, 100, 50), at(1_050)), None);
+
+        taps.update(&down(1, 100, 100), at(2_000));
+        assert_eq!(
+            taps.update(&up(1, 102, 100, 50), at(2_050)),
+            Some(Gesture::Tap {
+                position: Point::new(100, 100),
+                count: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn swipes_along_axis_of_greatest_movement() {
+        let mut swipes = SwipeRecognizer::new(SwipeConfig::default());
+        let mut swipe = |x, y| {
+            swipes.update(&down(1, 100, 100), at(0));
+            match swipes.update(&up(1, x, y, 200), at(200)) {
+                Some(Gesture::Swipe { direction, .. }) => Some(direction),
+                _ => None,
+            }
+        };
+
+        assert_eq!(swipe(200, 130), Some(SwipeDirection::Right));
+        assert_eq!(swipe(40, 80), Some(SwipeDirection::Left));
+        assert_eq!(swipe(120, 30), Some(SwipeDirection::Up));
+        assert_eq!(swipe(70, 160), Some(SwipeDirection::Down));
+        assert_eq!(swipe(140, 130), None);
+    }
+
+    #[test]
+    fn reports_start_and_end_of_swipes() {
+        let mut swipes = SwipeRecognizer::new(SwipeConfig::default());
+
+        swipes.update(&down(1, 100, 100), at(0));
+        swipes.update(&moved(1, 150, 100), at(100));
+
+        assert_eq!(
+            swipes.update(&up(1, 200, 100, 200), at(200)),
+            Some(Gesture::Swipe {
+                direction: SwipeDirection::Right,
+                start: Point::new(100, 100),
+                end: Point::new(200, 100),
+            })
+        );
+    }
+
+    #[test]
+    fn ignores_slow_or_multi_contact_swipes() {
+        let mut swipes = SwipeRecognizer::new(SwipeConfig::default());
+
+        swipes.update(&down(1, 100, 100), at(0));
+        assert_eq!(swipes.update(&up(1, 200, 100, 400), at(400)), None);
+
+        swipes.update(&down(1, 100, 100), at(1_000));
+        swipes.update(&down(2, 100, 200), at(1_050));
+        assert_eq!(swipes.update(&up(2, 200, 200, 100), at(1_150)), None);
+        assert_eq!(swipes.update(&up(1, 200, 100, 200), at(1_200)), None);
+
+        // The next swipe is recognized once both contacts have been lifted
+        swipes.update(&down(1, 100, 100), at(2_000));
+        assert!(swipes.update(&up(1, 200, 100, 200), at(2_200)).is_some());
+    }
+
+    #[test]
+    fn pinches_once_separation_changes() {
+        let mut pinches = PinchRecognizer::new(PinchConfig::default());
+
+        assert_eq!(pinches.update(&down(1, 100, 100)), None);
+        assert_eq!(pinches.update(&down(2, 200, 100)), None);
+        assert_eq!(pinches.update(&moved(2, 205, 100)), None);
+        assert_eq!(
+            pinches.update(&moved(2, 300, 100)),
+            Some(Gesture::Pinch {
+                phase: GesturePhase::Start,
+                center: Point::new(200, 100),
+                scale: 2000,
+            })
+        );
+        assert!(pinches.is_pinching());
+        assert_eq!(
+            pinches.update(&moved(1, 150, 100)),
+            Some(Gesture::Pinch {
+                phase: GesturePhase::Update,
+                center: Point::new(225, 100),
+                scale: 1500,
+            })
+        );
+
+        // Further contacts are not part of the pinch
+        assert_eq!(pinches.update(&down(3, 10, 10)), None);
+        assert_eq!(
+            pinches.update(&up(2, 300, 100, 100)),
+            Some(Gesture::Pinch {
+                phase: GesturePhase::End,
+                center: Point::new(225, 100),
+                scale: 1500,
+            })
+        );
+        assert!(!pinches.is_pinching());
+    }
+
+    #[test]
+    fn does_not_pinch_contacts_placed_close_together() {
+        let mut pinches = PinchRecognizer::new(PinchConfig::default());
+
+        pinches.update(&down(1, 100, 100));
+        pinches.update(&down(2, 110, 100));
+
+        assert_eq!(pinches.update(&moved(2, 200, 100)), None);
+        assert_eq!(pinches.update(&up(2, 200, 100, 100)), None);
+    }
+
+    /// Phase and total angle of a rotation, if any
+    fn rotation(gesture: Option<Gesture>) -> Option<(GesturePhase, i32)> {
+        match gesture {
+            Some(Gesture::Rotate { phase, angle, .. }) => Some((phase, angle)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn accumulates_rotation_beyond_half_turn() {
+        let mut rotations = RotateRecognizer::new(RotateConfig::default());
+        let near = |angle: i32, expected: i32| (angle - expected).abs() <= 50;
+
+        rotations.update(&down(1, 100, 100));
+        rotations.update(&down(2, 200, 100));
+        assert_eq!(rotations.update(&moved(2, 200, 104)), None);
+
+        let rotated = rotations.update(&moved(2, 100, 200));
+        assert!(matches!(
+            rotated,
+            Some(Gesture::Rotate {
+                phase: GesturePhase::Start,
+                center: Point { x: 100, y: 150 },
+                ..
+            })
+        ));
+        assert!(matches!(
+            rotation(rotated),
+            Some((GesturePhase::Start, angle)) if near(angle, 9_000)
+        ));
+        assert!(matches!(
+            rotation(rotations.update(&moved(2, 0, 100))),
+            Some((GesturePhase::Update, angle)) if near(angle, 18_000)
+        ));
+        assert!(matches!(
+            rotation(rotations.update(&moved(2, 100, 0))),
+            Some((GesturePhase::Update, angle)) if near(angle, 27_000)
+        ));
+        assert!(matches!(
+            rotation(rotations.update(&up(1, 100, 100, 100))),
+            Some((GesturePhase::End, angle)) if near(angle, 27_000)
+        ));
+        assert!(!rotations.is_rotating());
+    }
+
+    #[test]
+    fn does_not_rotate_contacts_close_together() {
+        let mut rotations = RotateRecognizer::new(RotateConfig::default());
+
+        rotations.update(&down(1, 100, 100));
+        rotations.update(&down(2, 120, 100));
+
+        assert_eq!(rotations.update(&moved(2, 100, 120)), None);
+        assert_eq!(rotations.update(&up(2, 100, 120, 100)), None);
+    }
+}
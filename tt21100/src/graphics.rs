@@ -0,0 +1,94 @@
+//! Interoperability with `embedded-graphics`
+//!
+//! Enabled by the `embedded-graphics` feature. Positions reported by the
+//! driver are in display space once it has been configured with a
+//! [Calibration](crate::Calibration) and, where the display is mounted rotated
+//! relative to the panel, a [Transform](crate::geom::Transform); they can then
+//! be used directly with drawing primitives.
+
+use embedded_graphics_core::{
+    geometry::{Point, Size},
+    primitives::Rectangle,
+};
+
+use crate::{geom, Event, TouchRecord};
+
+impl From<geom::Point> for Point {
+    fn from(point: geom::Point) -> Self {
+        Point::new(i32::from(point.x), i32::from(point.y))
+    }
+}
+
+impl From<&TouchRecord> for Point {
+    fn from(record: &TouchRecord) -> Self {
+        Point::new(i32::from(record.x), i32::from(record.y))
+    }
+}
+
+impl From<TouchRecord> for Point {
+    fn from(record: TouchRecord) -> Self {
+        Point::from(&record)
+    }
+}
+
+impl From<geom::Rect> for Rectangle {
+    fn from(rect: geom::Rect) -> Self {
+        Rectangle::new(
+            Point::from(rect.origin),
+            Size::new(u32::from(rect.width), u32::from(rect.height)),
+        )
+    }
+}
+
+impl TouchRecord {
+    /// Position of the contact on the display
+    pub fn display_point(&self) -> Point {
+        Point::from(self)
+    }
+}
+
+impl Event {
+    /// Positions on the display of the contacts carried by the event, in the
+    /// order in which they were reported
+    ///
+    /// Button events carry no touch records.
+    pub fn display_points(&self) -> impl Iterator<Item = Point> {
+        self.touches().map(Point::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ButtonRecord;
+
+    #[test]
+    fn converts_positions_and_regions() {
+        let record = TouchRecord::new(1, 10, 20);
+
+        assert_eq!(Point::from(geom::Point::new(10, 20)), Point::new(10, 20));
+        assert_eq!(record.display_point(), Point::new(10, 20));
+        assert_eq!(
+            Rectangle::from(geom::Rect::new(geom::Point::new(5, 6), 30, 40)),
+            Rectangle::new(Point::new(5, 6), Size::new(30, 40))
+        );
+    }
+
+    #[test]
+    fn lists_display_points_of_events() {
+        let event = Event::touch(
+            0,
+            &[TouchRecord::new(1, 10, 20), TouchRecord::new(2, 30, 40)],
+        );
+
+        assert!(event
+            .display_points()
+            .eq([Point::new(10, 20), Point::new(30, 40)]));
+        assert_eq!(
+            Event::Button(ButtonRecord::new(0, 1))
+                .display_points()
+                .count(),
+            0
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! Hooks for haptic feedback
+
+#[cfg(feature = "gesture")]
+use crate::gesture::{Gesture, GestureKind, GesturePhase};
+use crate::{keys::NUM_BUTTONS, tracker::TouchEvent, Event};
+
+/// A kind of input which warrants haptic feedback
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HapticEvent {
+    /// A contact was placed on the panel
+    TouchDown,
+    /// A gesture was recognized
+    #[cfg(feature = "gesture")]
+    Gesture(GestureKind),
+    /// A capacitive button was pressed; carries the index of the button
+    ButtonPress(u8),
+}
+
+/// A receiver of haptic feedback requests, for example a driver for a
+/// DRV2605 or a PWM-driven vibration motor
+pub trait HapticSink {
+    /// Play the feedback appropriate for the given event
+    fn feedback(&mut self, event: HapticEvent);
+}
+
+impl<F> HapticSink for F
+where
+    F: FnMut(HapticEvent),
+{
+    fn feedback(&mut self, event: HapticEvent) {
+        self(event)
+    }
+}
+
+/// Invokes a [HapticSink] for the input passed through it
+///
+/// Events read from the device, events emitted by a
+/// [Tracker](crate::tracker::Tracker) and recognized gestures are each
+/// observed as they are handled by the application, without otherwise being
+/// affected.
+#[derive(Debug)]
+pub struct Haptics<S> {
+    /// Receiver of feedback requests
+    sink: S,
+    /// Buttons which were pressed in the last button event, one bit per
+    /// button
+    buttons: u8,
+}
+
+impl<S> Haptics<S>
+where
+    S: HapticSink,
+{
+    /// Create a new hook invoking the given sink
+    pub fn new(sink: S) -> Self {
+        Self { sink, buttons: 0 }
+    }
+
+    /// Observe an event read from the device, requesting feedback for each
+    /// button which has been pressed since the last button event
+    pub fn observe_event(&mut self, event: &Event) {
+        let buttons = match event {
+            Event::Button(record) => record.btn_val & ((1 << NUM_BUTTONS) - 1),
+            Event::Touch { .. } | Event::PalmRejected(_) => return,
+        };
+
+        let pressed = buttons & !self.buttons;
+        self.buttons = buttons;
+
+        for button in 0..NUM_BUTTONS as u8 {
+            if pressed & (1 << button) != 0 {
+                self.sink.feedback(HapticEvent::ButtonPress(button));
+            }
+        }
+    }
+
+    /// Observe an event emitted by a tracker, requesting feedback when a
+    /// contact is placed on the panel
+    pub fn observe_touch(&mut self, event: &TouchEvent) {
+        if let TouchEvent::Down(_) = event {
+            self.sink.feedback(HapticEvent::TouchDown);
+        }
+    }
+
+    /// Observe a recognized gesture, requesting feedback when it is
+    /// recognized
+    ///
+    /// Gestures which continue over time only request feedback when they
+    /// begin.
+    #[cfg(feature = "gesture")]
+    pub fn observe_gesture(&mut self, gesture: &Gesture) {
+        match gesture {
+            Gesture::HoldDrag {
+                phase: GesturePhase::Update | GesturePhase::End,
+                ..
+            } => {}
+            _ => self
+                .sink
+                .feedback(HapticEvent::Gesture(GestureKind::of(gesture))),
+        }
+    }
+
+    /// The sink being invoked
+    pub fn sink(&mut self) -> &mut S {
+        &mut self.sink
+    }
+
+    /// Release the sink
+    pub fn release(self) -> S {
+        self.sink
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::{vec, vec::Vec};
+
+    use super::*;
+    #[cfg(feature = "gesture")]
+    use crate::geom::Point;
+    use crate::{tracker::ContactStats, ButtonRecord, TouchRecord};
+
+    #[test]
+    fn requests_feedback_for_newly_pressed_buttons() {
+        let mut events = Vec::new();
+        let mut haptics = Haptics::new(|event| events.push(event));
+
+        haptics.observe_event(&Event::Button(ButtonRecord::new(0, 0b0101)));
+        haptics.observe_event(&Event::Button(ButtonRecord::new(0, 0b0111)));
+        haptics.observe_event(&Event::Button(ButtonRecord::new(0, 0)));
+        haptics.observe_event(&Event::touch(0, &[TouchRecord::new(1, 10, 10)]));
+
+        assert_eq!(
+            events,
+            vec![
+                HapticEvent::ButtonPress(0),
+                HapticEvent::ButtonPress(2),
+                HapticEvent::ButtonPress(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn requests_feedback_when_contacts_are_placed() {
+        let mut events = Vec::new();
+        let mut haptics = Haptics::new(|event| events.push(event));
+        let record = TouchRecord::new(1, 10, 10);
+
+        haptics.observe_touch(&TouchEvent::Down(record));
+        haptics.observe_touch(&TouchEvent::Move(record));
+        haptics.observe_touch(&TouchEvent::Up(record, ContactStats::default()));
+
+        assert_eq!(events, vec![HapticEvent::TouchDown]);
+    }
+
+    #[cfg(feature = "gesture")]
+    #[test]
+    fn requests_feedback_when_gestures_begin() {
+        let mut events = Vec::new();
+        let mut haptics = Haptics::new(|event| events.push(event));
+        let position = Point::new(10, 10);
+
+        haptics.observe_gesture(&Gesture::Tap { position, count: 1 });
+        for phase in [GesturePhase::Start, GesturePhase::Update, GesturePhase::End] {
+            haptics.observe_gesture(&Gesture::HoldDrag { phase, position });
+        }
+
+        assert_eq!(
+            events,
+            vec![
+                HapticEvent::Gesture(GestureKind::Tap),
+                HapticEvent::Gesture(GestureKind::HoldDrag),
+            ]
+        );
+    }
+}
@@ -0,0 +1,376 @@
+//! Hit-testing of contacts against application-defined regions
+
+use crate::{
+    geom::{Point, Rect},
+    tracker::TouchEvent,
+    MAX_TOUCHES,
+};
+
+/// Shape of a region which contacts are tested against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    /// A rectangle
+    Rect(Rect),
+    /// A rectangle whose corners are rounded
+    RoundedRect {
+        /// Bounds of the shape
+        rect: Rect,
+        /// Radius of each corner
+        radius: u16,
+    },
+}
+
+impl Shape {
+    /// Does the shape contain the given point?
+    pub fn contains(&self, point: Point) -> bool {
+        match *self {
+            Shape::Rect(rect) => rect.contains(point),
+            Shape::RoundedRect { rect, radius } => {
+                if !rect.contains(point) {
+                    return false;
+                }
+
+                let radius = radius.min(rect.width / 2).min(rect.height / 2);
+                let left = rect.origin.x.saturating_add(radius);
+                let top = rect.origin.y.saturating_add(radius);
+                let right = rect.origin.x.saturating_add(rect.width - 1 - radius);
+                let bottom = rect.origin.y.saturating_add(rect.height - 1 - radius);
+
+                let nearest = Point::new(
+                    point.x.clamp(left, right.max(left)),
+                    point.y.clamp(top, bottom.max(top)),
+                );
+                let radius = u32::from(radius);
+
+                nearest.distance_squared(point) <= radius * radius
+            }
+        }
+    }
+}
+
+/// Output of a [HitRegistry]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitEvent<Id> {
+    /// A contact entered a region
+    Enter {
+        /// ID of the region
+        id: Id,
+        /// ID of the contact
+        touch_id: u8,
+    },
+    /// A contact left a region, either by moving or by being lifted
+    Leave {
+        /// ID of the region
+        id: Id,
+        /// ID of the contact
+        touch_id: u8,
+    },
+    /// An event emitted by the tracker, along with the region the contact is
+    /// within, if any
+    Touch {
+        /// The event
+        event: TouchEvent,
+        /// ID of the region the contact is within
+        hit: Option<Id>,
+    },
+}
+
+/// Iterator over the [HitEvent]s resulting from a single update of a
+/// [HitRegistry]
+#[derive(Debug, Clone)]
+pub struct HitEvents<Id> {
+    events: [Option<HitEvent<Id>>; 3],
+    len: usize,
+    index: usize,
+}
+
+impl<Id> HitEvents<Id>
+where
+    Id: Copy,
+{
+    fn new() -> Self {
+        Self {
+            events: [None; 3],
+            len: 0,
+            index: 0,
+        }
+    }
+
+    fn push(&mut self, event: HitEvent<Id>) {
+        if let Some(slot) = self.events.get_mut(self.len) {
+            *slot = Some(event);
+            self.len += 1;
+        }
+    }
+}
+
+impl<Id> Iterator for HitEvents<Id>
+where
+    Id: Copy,
+{
+    type Item = HitEvent<Id>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let event = self.events.get(self.index).copied().flatten();
+        if event.is_some() {
+            self.index += 1;
+        }
+
+        event
+    }
+}
+
+/// A registry of up to `N` regions, each keyed by an ID, which contacts are
+/// hit-tested against
+///
+/// Where regions overlap, the most recently added region is hit.
+#[derive(Debug, Clone)]
+pub struct HitRegistry<Id, const N: usize> {
+    /// Registered regions, in the order they were added
+    regions: [Option<(Id, Shape)>; N],
+    /// ID of each contact, along with the region it is within, if any
+    contacts: [Option<(u8, Option<Id>)>; MAX_TOUCHES],
+}
+
+impl<Id, const N: usize> Default for HitRegistry<Id, N>
+where
+    Id: Copy + PartialEq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Id, const N: usize> HitRegistry<Id, N>
+where
+    Id: Copy + PartialEq,
+{
+    /// Create a new, empty registry
+    pub fn new() -> Self {
+        Self {
+            regions: [None; N],
+            contacts: [None; MAX_TOUCHES],
+        }
+    }
+
+    /// Add a region to the registry, replacing any existing region with the
+    /// same ID
+    ///
+    /// If the registry is full the region is returned as an error.
+    pub fn add(&mut self, id: Id, shape: Shape) -> Result<(), (Id, Shape)> {
+        self.remove(id);
+
+        match self.regions.iter_mut().find(|slot| slot.is_none()) {
+            Some(slot) => {
+                *slot = Some((id, shape));
+                Ok(())
+            }
+            None => Err((id, shape)),
+        }
+    }
+
+    /// Remove a region from the registry, returning its shape if it was
+    /// present
+    ///
+    /// Contacts within the region leave it upon their next update.
+    pub fn remove(&mut self, id: Id) -> Option<Shape> {
+        let index = self
+            .regions
+            .iter()
+            .position(|slot| slot.map_or(false, |(region, _)| region == id))?;
+        let (_, shape) = self.regions[index].take()?;
+
+        // Keep the remaining regions in the order they were added
+        self.regions[index..].rotate_left(1);
+
+        Some(shape)
+    }
+
+    /// Remove all regions from the registry
+    pub fn clear(&mut self) {
+        self.regions = [None; N];
+    }
+
+    /// The registered regions, in the order they were added
+    pub fn regions(&self) -> impl Iterator<Item = &(Id, Shape)> {
+        self.regions.iter().flatten()
+    }
+
+    /// ID of the region containing the given point, if any
+    pub fn hit_test(&self, point: Point) -> Option<Id> {
+        self.regions
+            .iter()
+            .rev()
+            .flatten()
+            .find(|(_, shape)| shape.contains(point))
+            .map(|(id, _)| *id)
+    }
+
+    /// Update the registry with an event emitted by a
+    /// [Tracker](crate::tracker::Tracker), returning the event annotated with
+    /// the region it hit, along with any resulting enter and leave
+    /// notifications
+    ///
+    /// Notifications precede the event itself, except when the contact is
+    /// lifted, in which case it leaves its region after the event.
+    pub fn update(&mut self, event: &TouchEvent) -> HitEvents<Id> {
+        let mut events = HitEvents::new();
+
+        let (record, lifted) = match *event {
+            TouchEvent::Down(record) | TouchEvent::Move(record) => (record, false),
+            TouchEvent::Up(record, _) => (record, true),
+        };
+        let touch_id = record.touch_id;
+        let hit = self.hit_test(Point::from(&record));
+
+        let slot = self
+            .contacts
+            .iter()
+            .position(|slot| slot.map_or(false, |(id, _)| id == touch_id))
+            .or_else(|| self.contacts.iter().position(|slot| slot.is_none()));
+        let previous = slot
+            .and_then(|slot| self.contacts[slot])
+            .and_then(|(_, hit)| hit);
+
+        if lifted {
+            events.push(HitEvent::Touch { event: *event, hit });
+            if let Some(id) = previous {
+                events.push(HitEvent::Leave { id, touch_id });
+            }
+            if let Some(slot) = slot {
+                self.contacts[slot] = None;
+            }
+
+            return events;
+        }
+
+        if previous != hit {
+            if let Some(id) = previous {
+                events.push(HitEvent::Leave { id, touch_id });
+            }
+            if let Some(id) = hit {
+                events.push(HitEvent::Enter { id, touch_id });
+            }
+        }
+        events.push(HitEvent::Touch { event: *event, hit });
+
+        if let Some(slot) = slot {
+            self.contacts[slot] = Some((touch_id, hit));
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tracker::ContactStats, TouchRecord};
+
+    const BUTTON: Shape = Shape::Rect(Rect::new(Point::new(0, 0), 100, 50));
+
+    #[test]
+    fn rounds_corners_of_shapes() {
+        let shape = Shape::RoundedRect {
+            rect: Rect::new(Point::new(0, 0), 100, 100),
+            radius: 20,
+        };
+
+        assert!(shape.contains(Point::new(50, 0)));
+        assert!(shape.contains(Point::new(20, 20)));
+        assert!(shape.contains(Point::new(6, 6)));
+        assert!(!shape.contains(Point::new(0, 0)));
+        assert!(!shape.contains(Point::new(99, 99)));
+        assert!(!shape.contains(Point::new(100, 50)));
+    }
+
+    #[test]
+    fn hits_most_recently_added_region() {
+        let mut registry = HitRegistry::<u8, 2>::new();
+        registry.add(1, BUTTON).unwrap();
+        registry
+            .add(2, Shape::Rect(Rect::new(Point::new(50, 0), 100, 50)))
+            .unwrap();
+
+        assert_eq!(registry.hit_test(Point::new(10, 10)), Some(1));
+        assert_eq!(registry.hit_test(Point::new(60, 10)), Some(2));
+        assert_eq!(registry.hit_test(Point::new(10, 60)), None);
+    }
+
+    #[test]
+    fn replaces_and_removes_regions() {
+        let mut registry = HitRegistry::<u8, 2>::new();
+        registry.add(1, BUTTON).unwrap();
+        registry.add(2, BUTTON).unwrap();
+
+        assert_eq!(registry.add(3, BUTTON), Err((3, BUTTON)));
+        assert_eq!(registry.add(1, BUTTON), Ok(()));
+        assert!(registry.regions().map(|(id, _)| *id).eq([2, 1]));
+
+        assert_eq!(registry.remove(2), Some(BUTTON));
+        assert_eq!(registry.remove(2), None);
+        assert!(registry.regions().map(|(id, _)| *id).eq([1]));
+
+        registry.clear();
+        assert_eq!(registry.regions().count(), 0);
+    }
+
+    #[test]
+    fn notifies_contacts_entering_and_leaving_regions() {
+        let mut registry = HitRegistry::<u8, 2>::new();
+        registry.add(1, BUTTON).unwrap();
+
+        let outside = TouchEvent::Down(TouchRecord::new(4, 10, 80));
+        let inside = TouchEvent::Move(TouchRecord::new(4, 10, 20));
+        let lifted = TouchEvent::Up(
+            TouchRecord::new(4, 10, 20),
+            ContactStats {
+                travel: 60,
+                duration_ms: 100,
+            },
+        );
+
+        assert!(registry.update(&outside).eq([HitEvent::Touch {
+            event: outside,
+            hit: None,
+        }]));
+        assert!(registry.update(&inside).eq([
+            HitEvent::Enter { id: 1, touch_id: 4 },
+            HitEvent::Touch {
+                event: inside,
+                hit: Some(1),
+            },
+        ]));
+        assert!(registry.update(&inside).eq([HitEvent::Touch {
+            event: inside,
+            hit: Some(1),
+        }]));
+        assert!(registry.update(&lifted).eq([
+            HitEvent::Touch {
+                event: lifted,
+                hit: Some(1),
+            },
+            HitEvent::Leave { id: 1, touch_id: 4 },
+        ]));
+    }
+
+    #[test]
+    fn leaves_removed_regions_on_next_update() {
+        let mut registry = HitRegistry::<u8, 2>::new();
+        registry.add(1, BUTTON).unwrap();
+
+        let placed = TouchEvent::Down(TouchRecord::new(4, 10, 20));
+        let moved = TouchEvent::Move(TouchRecord::new(4, 12, 20));
+
+        registry.update(&placed);
+        registry.remove(1);
+
+        assert!(registry.update(&moved).eq([
+            HitEvent::Leave { id: 1, touch_id: 4 },
+            HitEvent::Touch {
+                event: moved,
+                hit: None,
+            },
+        ]));
+    }
+}
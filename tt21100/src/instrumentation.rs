@@ -0,0 +1,80 @@
+//! Timing of bus transactions
+
+/// Timing statistics for a single kind of bus transaction
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct TransactionStats {
+    /// Number of transactions measured
+    pub count: u32,
+    /// Duration of the most recent transaction, in microseconds
+    pub last_us: u32,
+    /// Duration of the longest transaction, in microseconds
+    pub max_us: u32,
+    /// Combined duration of all measured transactions, in microseconds
+    pub total_us: u64,
+}
+
+impl TransactionStats {
+    /// Average duration of the measured transactions, in microseconds
+    pub fn average_us(&self) -> u32 {
+        if self.count == 0 {
+            return 0;
+        }
+
+        (self.total_us / u64::from(self.count)) as u32
+    }
+
+    fn record(&mut self, duration_us: u32) {
+        self.count = self.count.wrapping_add(1);
+        self.last_us = duration_us;
+        self.max_us = self.max_us.max(duration_us);
+        self.total_us = self.total_us.wrapping_add(u64::from(duration_us));
+    }
+}
+
+/// Kinds of bus transaction performed by the driver
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub enum Transaction {
+    /// Reading the length of the next message
+    LengthRead,
+    /// Reading a message in its entirety, along with its length
+    PayloadRead,
+    /// Writing a command to the device
+    CommandWrite,
+    /// Reading from a register of the device
+    RegisterRead,
+    /// Writing to a register of the device
+    RegisterWrite,
+}
+
+/// Timing statistics for each kind of bus transaction performed by the driver
+///
+/// Durations are measured using the clock provided to the driver via
+/// `set_clock`; no measurements are taken until a clock has been provided.
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct BusTimings {
+    /// Reading the length of the next message
+    pub length_read: TransactionStats,
+    /// Reading a message in its entirety, along with its length
+    pub payload_read: TransactionStats,
+    /// Writing a command to the device
+    pub command_write: TransactionStats,
+    /// Reading from a register of the device
+    pub register_read: TransactionStats,
+    /// Writing to a register of the device
+    pub register_write: TransactionStats,
+}
+
+impl BusTimings {
+    pub(crate) fn record(&mut self, transaction: Transaction, duration_us: u32) {
+        match transaction {
+            Transaction::LengthRead => self.length_read.record(duration_us),
+            Transaction::PayloadRead => self.payload_read.record(duration_us),
+            Transaction::CommandWrite => self.command_write.record(duration_us),
+            Transaction::RegisterRead => self.register_read.record(duration_us),
+            Transaction::RegisterWrite => self.register_write.record(duration_us),
+        }
+    }
+}
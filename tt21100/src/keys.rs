@@ -0,0 +1,200 @@
+//! Mapping of the capacitive buttons to key events
+
+use crate::Event;
+
+/// Number of capacitive buttons supported by the device
+pub const NUM_BUTTONS: usize = 4;
+
+/// A change in the state of a mapped key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEvent<K> {
+    /// The button mapped to the key was pressed
+    KeyDown(K),
+    /// The button mapped to the key was released
+    KeyUp(K),
+}
+
+/// Iterator over the [KeyEvent]s resulting from a single update of a [Keys]
+#[derive(Debug, Clone)]
+pub struct KeyEvents<K> {
+    events: [Option<KeyEvent<K>>; NUM_BUTTONS],
+    len: usize,
+    index: usize,
+}
+
+impl<K> KeyEvents<K>
+where
+    K: Copy,
+{
+    fn new() -> Self {
+        Self {
+            events: [None; NUM_BUTTONS],
+            len: 0,
+            index: 0,
+        }
+    }
+
+    fn push(&mut self, event: KeyEvent<K>) {
+        if let Some(slot) = self.events.get_mut(self.len) {
+            *slot = Some(event);
+            self.len += 1;
+        }
+    }
+}
+
+impl<K> Iterator for KeyEvents<K>
+where
+    K: Copy,
+{
+    type Item = KeyEvent<K>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let event = self.events.get(self.index).copied().flatten();
+        if event.is_some() {
+            self.index += 1;
+        }
+
+        event
+    }
+}
+
+/// Converts button events into key events, using a user-defined key code for
+/// each button
+///
+/// Key codes may be of any type, for example USB HID usage IDs. Buttons
+/// which are not mapped to a key are ignored.
+#[derive(Debug, Clone)]
+pub struct Keys<K> {
+    /// Key code mapped to each button, indexed by bit in the button value
+    map: [Option<K>; NUM_BUTTONS],
+    /// Buttons which are currently pressed, one bit per button
+    pressed: u8,
+}
+
+impl<K> Keys<K>
+where
+    K: Copy,
+{
+    /// Create a new mapping, where `map` holds the key code for each button,
+    /// indexed by bit in the button value
+    pub fn new(map: [Option<K>; NUM_BUTTONS]) -> Self {
+        Self { map, pressed: 0 }
+    }
+
+    /// Forget which buttons are pressed, without emitting key events
+    pub fn reset(&mut self) {
+        self.pressed = 0;
+    }
+
+    /// The key code mapped to each button
+    pub fn map(&self) -> &[Option<K>; NUM_BUTTONS] {
+        &self.map
+    }
+
+    /// Replace the key code mapped to each button, taking effect from the
+    /// next update
+    ///
+    /// Buttons which are held at the time are released using their new key
+    /// code.
+    pub fn set_map(&mut self, map: [Option<K>; NUM_BUTTONS]) {
+        self.map = map;
+    }
+
+    /// Is the given button currently pressed?
+    pub fn is_pressed(&self, button: usize) -> bool {
+        button < NUM_BUTTONS && self.pressed & (1 << button) != 0
+    }
+
+    /// Update the mapping with an event read from the device, returning the
+    /// resulting key events
+    ///
+    /// Touch events are ignored.
+    pub fn update(&mut self, event: &Event) -> KeyEvents<K> {
+        let mut events = KeyEvents::new();
+
+        let buttons = match event {
+            Event::Button(record) => record.btn_val & ((1 << NUM_BUTTONS) - 1),
+            Event::Touch { .. } | Event::PalmRejected(_) => return events,
+        };
+
+        let changed = buttons ^ self.pressed;
+        self.pressed = buttons;
+
+        for (button, key) in self.map.iter().enumerate() {
+            let mask = 1 << button;
+            if changed & mask == 0 {
+                continue;
+            }
+
+            if let Some(key) = *key {
+                if buttons & mask != 0 {
+                    events.push(KeyEvent::KeyDown(key));
+                } else {
+                    events.push(KeyEvent::KeyUp(key));
+                }
+            }
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ButtonRecord, TouchRecord};
+
+    fn buttons(btn_val: u8) -> Event {
+        Event::Button(ButtonRecord::new(0, btn_val))
+    }
+
+    #[test]
+    fn emits_changes_of_mapped_buttons() {
+        let mut keys = Keys::new([Some('a'), None, Some('c'), Some('d')]);
+
+        assert!(keys.update(&buttons(0b0011)).eq([KeyEvent::KeyDown('a')]));
+        assert!(keys.is_pressed(0));
+        assert!(keys.is_pressed(1));
+        assert!(!keys.is_pressed(2));
+
+        assert!(keys.update(&buttons(0b1110)).eq([
+            KeyEvent::KeyUp('a'),
+            KeyEvent::KeyDown('c'),
+            KeyEvent::KeyDown('d')
+        ]));
+        assert_eq!(keys.update(&buttons(0b1110)).count(), 0);
+        assert!(keys.update(&buttons(0b1010)).eq([KeyEvent::KeyUp('c')]));
+    }
+
+    #[test]
+    fn ignores_touches_and_unsupported_buttons() {
+        let mut keys = Keys::new([Some(1u8); NUM_BUTTONS]);
+
+        assert_eq!(
+            keys.update(&Event::touch(0, &[TouchRecord::new(1, 10, 10)]))
+                .count(),
+            0
+        );
+        assert_eq!(keys.update(&buttons(0b1_0000)).count(), 0);
+        assert!(!keys.is_pressed(4));
+    }
+
+    #[test]
+    fn releases_held_buttons_with_new_map() {
+        let mut keys = Keys::new([Some('a'), None, None, None]);
+        keys.update(&buttons(0b0001));
+        keys.set_map([Some('b'), None, None, None]);
+
+        assert!(keys.update(&buttons(0)).eq([KeyEvent::KeyUp('b')]));
+    }
+
+    #[test]
+    fn forgets_pressed_buttons_on_reset() {
+        let mut keys = Keys::new([Some('a'), None, None, None]);
+        keys.update(&buttons(0b0001));
+        keys.reset();
+
+        assert!(!keys.is_pressed(0));
+        assert_eq!(keys.update(&buttons(0)).count(), 0);
+    }
+}
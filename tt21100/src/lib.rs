@@ -1,31 +1,136 @@
 //! TT21100 Multi-Touch Touchscreen Controller
 
-#![no_std]
+#![cfg_attr(not(feature = "std"), no_std)]
 
 use core::{array::TryFromSliceError, fmt::Debug};
 
 use bondrewd::Bitfields;
 use embedded_hal::{
-    blocking::i2c::{Write, WriteRead},
-    digital::v2::InputPin,
+    delay::DelayNs,
+    digital::{ErrorType, InputPin, OutputPin},
+    i2c::{I2c, Operation},
 };
 
-// Default I²C address for the TT21100
-const I2C_ADDR: u8 = 0x24;
+use self::geom::{NoRotation, OrientationProvider};
+pub use self::{
+    command::{Response, WakeGesture, MAX_COMMAND_PARAMETERS},
+    config::{Calibration, Config, OutOfRange, PanelProfile, ParseMode},
+    descriptor::Version,
+};
+
+pub mod activity;
+// The async driver has an MSRV of its own
+#[cfg(feature = "async")]
+#[clippy::msrv = "1.75"]
+pub mod asynch;
+#[cfg(feature = "bench")]
+pub mod bench;
+mod command;
+#[cfg(feature = "eh0")]
+pub mod compat;
+mod config;
+mod descriptor;
+#[cfg(feature = "filter")]
+pub mod filter;
+pub mod geom;
+#[cfg(feature = "gesture")]
+pub mod gesture;
+#[cfg(feature = "embedded-graphics")]
+pub mod graphics;
+#[cfg(feature = "haptic")]
+pub mod haptic;
+#[cfg(feature = "hit")]
+pub mod hit;
+#[cfg(feature = "instrumentation")]
+pub mod instrumentation;
+#[cfg(feature = "keys")]
+pub mod keys;
+mod math;
+#[cfg(test)]
+mod mock;
+#[cfg(feature = "pattern")]
+pub mod pattern;
+#[cfg(feature = "pointer")]
+pub mod pointer;
+#[cfg(feature = "trace")]
+pub mod replay;
+#[cfg(feature = "scroll")]
+pub mod scroll;
+mod state;
+#[cfg(feature = "stroke")]
+pub mod stroke;
+pub mod time;
+#[cfg(feature = "trace")]
+pub mod trace;
+#[cfg(feature = "tracker")]
+pub mod tracker;
+
+/// Maximum number of touch records reported in a single event
+///
+/// Any further records reported by the device are discarded.
+pub const MAX_TOUCHES: usize = 5;
+
+/// Default I²C address of the TT21100
+pub const DEFAULT_ADDRESS: u8 = 0x24;
+
+// Length of the longest message read from the device: a touch report carrying
+// the greatest number of records
+const MAX_MESSAGE_LEN: usize = TouchReport::SIZE + MAX_TOUCHES * TouchRecord::SIZE;
+
+// Commands which put the device to sleep and wake it, written to the command
+// register at 0x0005: the power state to enter, followed by the opcode of the
+// command which sets it
+const SLEEP_COMMAND: [u8; 4] = [0x05, 0x00, 0x01, 0x08];
+const WAKE_COMMAND: [u8; 4] = [0x05, 0x00, 0x00, 0x08];
+
+// Time for which the reset pin is held low, and thereafter the time allowed for
+// the device to boot, in microseconds; matching the ESP-BOX reference code
+const RESET_PULSE_US: u32 = 10_000;
+const RESET_RECOVERY_US: u32 = 10_000;
+
+// Number of times, and the interval in microseconds at which, the device is
+// sampled while waiting for the response to a command
+const RESPONSE_ATTEMPTS: usize = 20;
+const RESPONSE_INTERVAL_US: u32 = 1_000;
+
+// Greatest number of messages discarded by a single flush, guarding against a
+// device or bus which never reports that its queue is empty
+const MAX_FLUSHED_MESSAGES: usize = 64;
+
+// Greatest number of messages skipped while reading a single event, guarding
+// against a device which never reports a message that can be parsed
+const MAX_SKIPPED_MESSAGES: usize = 64;
 
 /// Any type of error which may occur while interacting with the device
-#[derive(Debug)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Error<E> {
     /// Some error originating from the communication bus
     BusError(E),
     /// The message length did not match the expected value
     InvalidMessageLen(usize),
+    /// The device was detected to have reset, and the driver has been
+    /// re-initialized; the message which revealed the reset was discarded
+    DeviceReset,
     /// Reading a GPIO pin resulted in an error
     IOError,
     /// Tried to read a touch point, but no data was available
     NoDataAvailable,
     /// Error converting a slice to an array
     TryFromSliceError,
+    /// The message carried a report ID which is not supported
+    UnknownReport(u8),
+    /// The device did not respond to a command
+    NoResponse,
+    /// The CRC carried by a message did not match its contents, and the
+    /// message was discarded
+    ChecksumMismatch,
+    /// A command was given more than
+    /// [MAX_COMMAND_PARAMETERS](crate::MAX_COMMAND_PARAMETERS) parameters;
+    /// carries the number given
+    CommandTooLong(usize),
+    /// The descriptor read from the device was not of the expected length
+    InvalidDescriptor,
 }
 
 impl<E> From<TryFromSliceError> for Error<E> {
@@ -34,29 +139,267 @@ impl<E> From<TryFromSliceError> for Error<E> {
     }
 }
 
+#[cfg(feature = "debug")]
+impl<E> core::fmt::Display for Error<E>
+where
+    E: Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::BusError(e) => write!(f, "bus error: {:?}", e),
+            Error::InvalidMessageLen(n) => write!(f, "invalid message length: {}", n),
+            Error::DeviceReset => write!(f, "the device was reset"),
+            Error::IOError => write!(f, "error reading the interrupt pin"),
+            Error::NoDataAvailable => write!(f, "no data available"),
+            Error::TryFromSliceError => write!(f, "error converting a slice to an array"),
+            Error::UnknownReport(id) => write!(f, "unknown report ID: {}", id),
+            Error::NoResponse => write!(f, "the device did not respond to a command"),
+            Error::ChecksumMismatch => write!(f, "checksum does not match the message"),
+            Error::CommandTooLong(n) => write!(f, "too many command parameters: {}", n),
+            Error::InvalidDescriptor => write!(f, "invalid device descriptor"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E> std::error::Error for Error<E> where E: Debug {}
+
 /// An event emitted by the device
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Event {
     /// A touch event
     Touch {
         report: TouchReport,
-        touches: (Option<TouchRecord>, Option<TouchRecord>),
+        touches: Touches,
     },
     /// A button press event
     Button(ButtonRecord),
+    /// A touch event whose touch records were suppressed, as the device
+    /// detected a large object such as a palm; see
+    /// [Config::reject_palms](crate::Config::reject_palms)
+    PalmRejected(TouchReport),
+}
+
+impl Event {
+    /// The touch records carried by the event, in the order in which they
+    /// were reported
+    ///
+    /// Button events, and touch events rejected as a palm, carry no touch
+    /// records.
+    pub fn touches(&self) -> impl Iterator<Item = TouchRecord> {
+        let touches = match self {
+            Event::Touch { touches, .. } => *touches,
+            Event::Button(_) | Event::PalmRejected(_) => Touches::new(),
+        };
+
+        touches.into_iter()
+    }
+
+    /// Severity of the noise affecting the event
+    ///
+    /// Button events are not affected by noise.
+    pub fn noise(&self) -> NoiseEffect {
+        match self {
+            Event::Touch { report, .. } | Event::PalmRejected(report) => report.noise(),
+            Event::Button(_) => NoiseEffect::None,
+        }
+    }
+
+    /// Parse an event from a complete message, as read from the device
+    ///
+    /// The length of the message is taken from its first two bytes. Messages
+    /// indicating that no data is available, and those skipped by
+    /// [ParseMode::Lenient] parsing, result in [Error::NoDataAvailable].
+    pub fn from_message<E>(message: &[u8], parsing: ParseMode) -> Result<Event, Error<E>>
+    where
+        E: Debug,
+    {
+        if message.len() < 2 {
+            return Err(Error::InvalidMessageLen(message.len()));
+        }
+
+        let message_length = usize::from(u16::from_le_bytes([message[0], message[1]]));
+        let length = message_length.min(MAX_MESSAGE_LEN);
+        if message.len() < length {
+            return Err(Error::InvalidMessageLen(message.len()));
+        }
+
+        match parse_message(message_length, &message[0..][..length], parsing)? {
+            Parsed::Event(event) => Ok(event),
+            Parsed::Empty | Parsed::Skipped => Err(Error::NoDataAvailable),
+        }
+    }
+
+    /// Create a touch event carrying the given records
+    ///
+    /// Any records beyond the first [MAX_TOUCHES] are discarded.
+    pub fn touch(time_stamp: u16, records: &[TouchRecord]) -> Self {
+        let touches: Touches = records.iter().copied().collect();
+
+        Event::Touch {
+            report: TouchReport::new(time_stamp, touches.len() as u8),
+            touches,
+        }
+    }
+}
+
+/// The touch records carried by a touch event, in the order in which they
+/// were reported
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Touches {
+    /// Records held by the collection, all preceding any empty slots
+    records: [Option<TouchRecord>; MAX_TOUCHES],
+}
+
+impl Touches {
+    /// Create an empty collection
+    pub const fn new() -> Self {
+        Self {
+            records: [None; MAX_TOUCHES],
+        }
+    }
+
+    /// Number of records held
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Are no records held?
+    pub fn is_empty(&self) -> bool {
+        self.records[0].is_none()
+    }
+
+    /// The record at the given position, if any
+    pub fn get(&self, index: usize) -> Option<&TouchRecord> {
+        self.records.get(index)?.as_ref()
+    }
+
+    /// Append a record, returning it if the collection already holds
+    /// [MAX_TOUCHES] records
+    pub fn push(&mut self, record: TouchRecord) -> Result<(), TouchRecord> {
+        match self.records.iter_mut().find(|slot| slot.is_none()) {
+            Some(slot) => {
+                *slot = Some(record);
+                Ok(())
+            }
+            None => Err(record),
+        }
+    }
+
+    /// Retain only the records for which `predicate` returns `true`,
+    /// preserving their order
+    pub fn retain<F>(&mut self, mut predicate: F)
+    where
+        F: FnMut(&TouchRecord) -> bool,
+    {
+        *self = self
+            .into_iter()
+            .filter(|record| predicate(record))
+            .collect();
+    }
+
+    /// Iterate over the records held
+    pub fn iter(&self) -> impl Iterator<Item = &TouchRecord> {
+        self.records.iter().flatten()
+    }
+
+    /// Iterate mutably over the records held
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut TouchRecord> {
+        self.records.iter_mut().flatten()
+    }
+}
+
+impl IntoIterator for Touches {
+    type Item = TouchRecord;
+    type IntoIter = core::iter::Flatten<core::array::IntoIter<Option<TouchRecord>, MAX_TOUCHES>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.records.into_iter().flatten()
+    }
+}
+
+impl FromIterator<TouchRecord> for Touches {
+    /// Collect records into a collection, discarding any beyond the first
+    /// [MAX_TOUCHES]
+    fn from_iter<I>(records: I) -> Self
+    where
+        I: IntoIterator<Item = TouchRecord>,
+    {
+        let mut touches = Touches::new();
+        for (slot, record) in touches.records.iter_mut().zip(records) {
+            *slot = Some(record);
+        }
+
+        touches
+    }
+}
+
+/// Type of a report, identified by the third byte of each message
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReportId {
+    /// A [TouchReport], followed by zero or more [TouchRecord]s
+    Touch,
+    /// A [ButtonRecord]
+    Button,
+    /// A report of some other type
+    Unknown(u8),
+}
+
+impl ReportId {
+    /// Report ID of a touch report
+    pub const TOUCH: u8 = 1;
+    /// Report ID of a button report
+    pub const BUTTON: u8 = 3;
+
+    /// Read the report ID of a complete message, as read from the device
+    pub fn of_message(message: &[u8]) -> Option<Self> {
+        message.get(2).copied().map(ReportId::from)
+    }
+}
+
+impl From<u8> for ReportId {
+    fn from(id: u8) -> Self {
+        match id {
+            ReportId::TOUCH => ReportId::Touch,
+            ReportId::BUTTON => ReportId::Button,
+            n => ReportId::Unknown(n),
+        }
+    }
+}
+
+impl From<ReportId> for u8 {
+    fn from(id: ReportId) -> Self {
+        match id {
+            ReportId::Touch => ReportId::TOUCH,
+            ReportId::Button => ReportId::BUTTON,
+            ReportId::Unknown(n) => n,
+        }
+    }
 }
 
 /// Prelude data for one or more touch events
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Bitfields)]
+#[derive(Clone, Copy, PartialEq, Eq, Bitfields)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[bondrewd(default_endianness = "le")]
 pub struct TouchReport {
-    /// Total length of the data; should be 7, 17, or 27
+    /// Total length of the data; 7, plus 10 for each touch record
     pub data_len: u16,
     /// ID of the report
     pub report_id: u8,
     /// Timestamp
     pub time_stamp: u16,
     #[bondrewd(bit_length = 2)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     padding0: u8,
     #[bondrewd(bit_length = 1)]
     pub large_object: u8,
@@ -65,21 +408,134 @@ pub struct TouchReport {
     #[bondrewd(bit_length = 2)]
     pub report_counter: u8,
     #[bondrewd(bit_length = 3)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     padding1: u8,
+    /// Severity of the noise affecting the report; see [TouchReport::noise]
     #[bondrewd(bit_length = 3)]
     pub noise_effect: u8,
 }
 
+impl TouchReport {
+    /// Length of the serialized report prelude, in bytes
+    pub const SIZE: usize = 7;
+
+    /// Deserialize a report prelude from the bytes read from the device
+    pub fn from_bytes(bytes: [u8; 7]) -> Self {
+        <Self as Bitfields<7>>::from_bytes(bytes)
+    }
+
+    /// Serialize the report prelude into the bytes sent by the device
+    pub fn into_bytes(self) -> [u8; 7] {
+        <Self as Bitfields<7>>::into_bytes(self)
+    }
+
+    /// Create a report prelude for the given number of touch records
+    pub const fn new(time_stamp: u16, record_num: u8) -> Self {
+        Self {
+            data_len: 7 + 10 * record_num as u16,
+            report_id: ReportId::TOUCH,
+            time_stamp,
+            padding0: 0,
+            large_object: 0,
+            record_num,
+            report_counter: 0,
+            padding1: 0,
+            noise_effect: 0,
+        }
+    }
+
+    /// Decoded severity of the noise affecting this report
+    pub fn noise(&self) -> NoiseEffect {
+        NoiseEffect::from(self.noise_effect)
+    }
+}
+
+impl Default for TouchReport {
+    fn default() -> Self {
+        Self::new(0, 0)
+    }
+}
+
+/// Severity of the noise affecting a touch report
+///
+/// Decoded from the 3-bit `noise_effect` field of a [TouchReport], where `0`
+/// indicates that no noise was detected and larger values indicate
+/// increasingly severe noise.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum NoiseEffect {
+    /// No noise was detected
+    None,
+    /// Low levels of noise; levels `1` and `2`
+    Low,
+    /// Moderate levels of noise; levels `3` and `4`
+    Moderate,
+    /// High levels of noise; levels `5` through `7`
+    High,
+}
+
+impl NoiseEffect {
+    /// Can the coordinates of the report be trusted?
+    pub fn is_reliable(&self) -> bool {
+        *self < NoiseEffect::High
+    }
+
+    /// The amount of filtering which should be applied to the coordinates of
+    /// the report
+    pub fn recommended_filtering(&self) -> FilterStrength {
+        match self {
+            NoiseEffect::None => FilterStrength::None,
+            NoiseEffect::Low => FilterStrength::Light,
+            NoiseEffect::Moderate => FilterStrength::Heavy,
+            NoiseEffect::High => FilterStrength::Discard,
+        }
+    }
+}
+
+impl From<u8> for NoiseEffect {
+    fn from(level: u8) -> Self {
+        match level & 0b111 {
+            0 => NoiseEffect::None,
+            1 | 2 => NoiseEffect::Low,
+            3 | 4 => NoiseEffect::Moderate,
+            _ => NoiseEffect::High,
+        }
+    }
+}
+
+/// Amount of filtering recommended for a given [NoiseEffect]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FilterStrength {
+    /// Coordinates can be used as-is
+    None,
+    /// Light smoothing is sufficient
+    Light,
+    /// Heavy smoothing should be applied
+    Heavy,
+    /// Coordinates should be ignored altogether
+    Discard,
+}
+
 /// Data for a touch event
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Bitfields)]
+#[derive(Clone, Copy, PartialEq, Eq, Bitfields)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[bondrewd(default_endianness = "le")]
 pub struct TouchRecord {
     #[bondrewd(bit_length = 5)]
+    #[cfg_attr(feature = "serde", serde(skip))]
     padding0: u8,
+    /// Type of the contact; see [TouchRecord::kind]
     #[bondrewd(bit_length = 3)]
     pub touch_type: u8,
+    /// Whether the contact is touching the panel; see [TouchRecord::phase]
     #[bondrewd(bit_length = 1)]
     pub tip: u8,
+    /// Stage of the contact's lifecycle; see [TouchRecord::touch_phase]
     #[bondrewd(bit_length = 2)]
     pub event_id: u8,
     #[bondrewd(bit_length = 5)]
@@ -91,8 +547,198 @@ pub struct TouchRecord {
     pub orientation: u8,
 }
 
+impl TouchRecord {
+    /// Length of the serialized record, in bytes
+    pub const SIZE: usize = 10;
+
+    /// Deserialize a record from the bytes read from the device
+    pub fn from_bytes(bytes: [u8; 10]) -> Self {
+        <Self as Bitfields<10>>::from_bytes(bytes)
+    }
+
+    /// Serialize the record into the bytes sent by the device
+    pub fn into_bytes(self) -> [u8; 10] {
+        <Self as Bitfields<10>>::into_bytes(self)
+    }
+
+    /// Create a record for a finger touching the panel at the given position
+    ///
+    /// All other fields are zero; they may be set using the `with_*` methods.
+    pub const fn new(touch_id: u8, x: u16, y: u16) -> Self {
+        Self {
+            padding0: 0,
+            touch_type: 0,
+            tip: 1,
+            event_id: 0,
+            touch_id,
+            x,
+            y,
+            pressure: 0,
+            major_axis_length: 0,
+            orientation: 0,
+        }
+    }
+
+    /// Set the type of the contact
+    pub fn with_touch_type(mut self, touch_type: u8) -> Self {
+        self.touch_type = touch_type;
+        self
+    }
+
+    /// Set whether the contact is touching the panel or hovering above it
+    pub fn with_phase(mut self, phase: Phase) -> Self {
+        self.tip = match phase {
+            Phase::Touch => 1,
+            Phase::Hover => 0,
+        };
+        self
+    }
+
+    /// Set the event ID of the record
+    pub fn with_event_id(mut self, event_id: u8) -> Self {
+        self.event_id = event_id;
+        self
+    }
+
+    /// Set the pressure of the contact
+    pub fn with_pressure(mut self, pressure: u8) -> Self {
+        self.pressure = pressure;
+        self
+    }
+
+    /// Set the length of the contact's major axis
+    pub fn with_major_axis_length(mut self, major_axis_length: u16) -> Self {
+        self.major_axis_length = major_axis_length;
+        self
+    }
+
+    /// Set the orientation of the contact
+    pub fn with_orientation(mut self, orientation: u8) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Position of the contact
+    pub fn position(&self) -> geom::Point {
+        geom::Point::new(self.x, self.y)
+    }
+
+    /// Is the contact touching the panel, or hovering above it?
+    pub fn phase(&self) -> Phase {
+        if self.tip != 0 {
+            Phase::Touch
+        } else {
+            Phase::Hover
+        }
+    }
+
+    /// Decoded meaning of the record's `event_id`
+    pub fn touch_phase(&self) -> TouchPhase {
+        TouchPhase::from(self.event_id)
+    }
+
+    /// Decoded type of the contact, from the record's `touch_type`
+    pub fn kind(&self) -> TouchType {
+        TouchType::from(self.touch_type)
+    }
+}
+
+/// Type of object making a contact, decoded from the `touch_type` of a
+/// [TouchRecord]
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TouchType {
+    /// A finger
+    Finger,
+    /// A passive stylus
+    Stylus,
+    /// A gloved finger
+    Glove,
+    /// An unrecognized value was reported
+    Unknown(u8),
+}
+
+impl From<u8> for TouchType {
+    fn from(touch_type: u8) -> Self {
+        match touch_type {
+            0 => TouchType::Finger,
+            1 => TouchType::Stylus,
+            2 => TouchType::Glove,
+            n => TouchType::Unknown(n),
+        }
+    }
+}
+
+impl From<TouchType> for u8 {
+    fn from(touch_type: TouchType) -> Self {
+        match touch_type {
+            TouchType::Finger => 0,
+            TouchType::Stylus => 1,
+            TouchType::Glove => 2,
+            TouchType::Unknown(n) => n,
+        }
+    }
+}
+
+/// Whether a contact is touching the panel or hovering above it
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Phase {
+    /// The contact is touching the panel
+    Touch,
+    /// The contact is hovering above the panel; its tip is not down
+    Hover,
+}
+
+/// Stage of a contact's lifecycle, decoded from the `event_id` of a
+/// [TouchRecord]
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TouchPhase {
+    /// No event was reported for the contact
+    None,
+    /// The contact has touched down
+    Down,
+    /// The contact has moved
+    Move,
+    /// The contact has lifted off
+    Up,
+    /// An unrecognized value was reported
+    Unknown(u8),
+}
+
+impl From<u8> for TouchPhase {
+    fn from(event_id: u8) -> Self {
+        match event_id {
+            0 => TouchPhase::None,
+            1 => TouchPhase::Down,
+            2 => TouchPhase::Move,
+            3 => TouchPhase::Up,
+            n => TouchPhase::Unknown(n),
+        }
+    }
+}
+
+impl From<TouchPhase> for u8 {
+    fn from(phase: TouchPhase) -> Self {
+        match phase {
+            TouchPhase::None => 0,
+            TouchPhase::Down => 1,
+            TouchPhase::Move => 2,
+            TouchPhase::Up => 3,
+            TouchPhase::Unknown(n) => n,
+        }
+    }
+}
+
 /// Data for a button press event
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Bitfields)]
+#[derive(Clone, Copy, PartialEq, Eq, Bitfields)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[bondrewd(default_endianness = "le")]
 pub struct ButtonRecord {
     /// Length of the record; always `14`
@@ -107,132 +753,2130 @@ pub struct ButtonRecord {
     pub btn_signal: [u16; 4],
 }
 
-/// TT21100 driver
-pub struct TT21100<I2C, IRQ> {
-    /// Underlying I²C peripheral
-    i2c: I2C,
-    /// Interrupt pin
-    irq: IRQ,
-}
+impl ButtonRecord {
+    /// Length of the serialized record, in bytes
+    pub const SIZE: usize = 14;
 
-impl<I2C, IRQ, E> TT21100<I2C, IRQ>
-where
-    I2C: Write<Error = E> + WriteRead<Error = E>,
-    IRQ: InputPin,
-    E: Debug,
-{
-    /// Create a new instance of the driver and initialize the device
-    pub fn new(i2c: I2C, irq: IRQ) -> Result<Self, Error<E>> {
-        let mut me = Self { i2c, irq };
+    /// Deserialize a record from the bytes read from the device
+    pub fn from_bytes(bytes: [u8; 14]) -> Self {
+        <Self as Bitfields<14>>::from_bytes(bytes)
+    }
 
-        // I'm honestly not entirely sure what is going on here (would be *really* nice
-        // if I had a datasheet!).
-        //
-        // As far as I can tell, when no events are queued on device for reading it will
-        // always return an empty message with length 2, so we're just sort of
-        // making sure we can talk to the device.
-        //
-        // Each driver I referenced seems to perform this step:
-        //
-        // https://github.com/espressif/esp-box/blob/147cd8d/components/i2c_devices/touch_panel/tt21100.c#L56-L60
-        // https://github.com/SuGlider/Adafruit_ESP32S3_BOX/blob/a9884ac/src/ESP32_S3_Box_TouchScreen.cpp#L15-L20
-        // https://github.com/adafruit/Adafruit_CircuitPython_TT21100/blob/b3113a4/adafruit_tt21100.py#L59-L63
-        let mut message_length = 0;
-        for _ in 0..5 {
-            message_length = me.read_message_length()?;
-            if message_length == 2 {
-                break;
-            }
-        }
+    /// Serialize the record into the bytes sent by the device
+    pub fn into_bytes(self) -> [u8; 14] {
+        <Self as Bitfields<14>>::into_bytes(self)
+    }
 
-        match message_length {
-            2 => Ok(me),
-            n => Err(Error::InvalidMessageLen(n)),
+    /// Create a record for the given button state
+    pub const fn new(time_stamp: u16, btn_val: u8) -> Self {
+        Self {
+            length: 14,
+            report_id: ReportId::BUTTON,
+            time_stamp,
+            btn_val,
+            btn_signal: [0; 4],
         }
     }
+}
 
-    /// Is there data available to read from the device?
-    pub fn data_available(&self) -> Result<bool, Error<E>> {
-        self.irq.is_low().map_err(|_| Error::IOError)
+impl Default for ButtonRecord {
+    fn default() -> Self {
+        Self::new(0, 0)
     }
+}
 
-    /// Read an event from the device
-    ///
-    /// There are two types of events, [Event::Touch] and [Event::Button].
-    pub fn event(&mut self) -> Result<Event, Error<E>> {
-        let message_length = self.read_message_length()?;
+/// A delay which does not wait
+///
+/// Used by the driver when it has not been provided with a delay.
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct NoDelay;
 
-        let mut data = [0u8; 32];
-        self.read_bytes(&mut data[0..][..message_length])?;
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
 
-        match message_length {
-            2 => Err(Error::NoDataAvailable),
-            7 | 17 | 27 => touch_event(&data[0..][..message_length]),
-            14 => button_event(&data[0..][..message_length]),
-            n => Err(Error::InvalidMessageLen(n)),
-        }
+#[cfg(feature = "async")]
+impl embedded_hal_async::delay::DelayNs for NoDelay {
+    async fn delay_ns(&mut self, _ns: u32) {}
+}
+
+/// Stand-in for the interrupt pin, on boards where it is not connected
+///
+/// The pin always reads as asserted, so the driver reads the length of the
+/// pending message from the device whenever it would otherwise have checked
+/// the interrupt pin; [TT21100::poll_event] samples the device at a more
+/// moderate rate. The async driver should instead be used with a `Periodic`
+/// event source.
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct NoIrq;
+
+impl ErrorType for NoIrq {
+    type Error = core::convert::Infallible;
+}
+
+impl InputPin for NoIrq {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(false)
     }
 
-    // -----------------------------------------------------------------------
-    // PRIVATE
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+}
 
-    fn read_message_length(&mut self) -> Result<usize, Error<E>> {
-        let mut buffer = [0u8; 2];
-        self.read_bytes(&mut buffer)?;
+/// Stand-in for the reset pin, on boards where it is not connected
+///
+/// Used by the driver when it has not been provided with a reset pin; it is
+/// never driven.
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct NoReset;
 
-        let message_length = u16::from_le_bytes(buffer);
+impl ErrorType for NoReset {
+    type Error = core::convert::Infallible;
+}
 
-        Ok(message_length as usize)
+impl OutputPin for NoReset {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Ok(())
     }
 
-    fn read_bytes(&mut self, buffer: &mut [u8]) -> Result<(), Error<E>> {
-        self.i2c
-            .write_read(I2C_ADDR, &[], buffer)
-            .map_err(|e| Error::BusError(e))
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Ok(())
     }
 }
 
-fn touch_event<E>(message: &[u8]) -> Result<Event, Error<E>>
+/// TT21100 driver
+pub struct TT21100<I2C, IRQ, DELAY = NoDelay, RST = NoReset, ORIENT = NoRotation> {
+    /// Underlying I²C peripheral
+    i2c: I2C,
+    /// Interrupt pin
+    irq: IRQ,
+    /// Reset pin, if connected
+    reset: Option<RST>,
+    /// Source of the current orientation of the display
+    orientation: ORIENT,
+    /// State shared with the async driver
+    state: state::State,
+    /// Delay used to enforce the minimum gap between bus transactions
+    delay: DELAY,
+    /// Puts the device to sleep when the driver is dropped
+    #[cfg(feature = "sleep-on-drop")]
+    sleep: fn(&mut I2C, u8),
+    /// Callback invoked between polls of the interrupt pin
+    idle_hook: Option<fn()>,
+}
+
+impl<I2C, IRQ, E> TT21100<I2C, IRQ>
 where
+    I2C: I2c<Error = E>,
+    IRQ: InputPin,
     E: Debug,
 {
-    debug_assert!(message.len() == 7 || message.len() == 17 || message.len() == 27);
-
-    let report = message[0..][..7].try_into()?;
-    let report = TouchReport::from_bytes(report);
-
-    let record0 = if message.len() >= 17 {
-        let record = message[7..][..10].try_into()?;
-        let record = TouchRecord::from_bytes(record);
-
-        Some(record)
-    } else {
-        None
-    };
-
-    let record1 = if message.len() == 27 {
-        let record = message[17..][..10].try_into()?;
-        let record = TouchRecord::from_bytes(record);
+    /// Create a new instance of the driver
+    pub fn new(i2c: I2C, irq: IRQ) -> Self {
+        Self::new_with_config(i2c, irq, Config::default())
+    }
 
-        Some(record)
-    } else {
-        None
-    };
+    /// Create a new instance of the driver using the provided configuration
+    pub fn new_with_config(i2c: I2C, irq: IRQ, config: Config) -> Self {
+        Self::new_with_delay(i2c, irq, NoDelay, config)
+    }
 
-    Ok(Event::Touch {
-        report,
-        touches: (record0, record1),
-    })
+    /// Create a new instance of the driver for a device at the given address
+    ///
+    /// For use on boards which strap the device to an address other than
+    /// [DEFAULT_ADDRESS](crate::DEFAULT_ADDRESS); the address may also be set
+    /// using [Config::address].
+    pub fn new_with_address(i2c: I2C, irq: IRQ, address: u8) -> Self {
+        Self::new_with_config(
+            i2c,
+            irq,
+            Config {
+                address,
+                ..Config::default()
+            },
+        )
+    }
 }
 
-fn button_event<E>(message: &[u8]) -> Result<Event, Error<E>>
+impl<I2C, E> TT21100<I2C, NoIrq>
 where
+    I2C: I2c<Error = E>,
     E: Debug,
 {
-    debug_assert_eq!(message.len(), 14);
-
-    let message = message.try_into()?;
-    let record = ButtonRecord::from_bytes(message);
+    /// Create a new instance of the driver for a board on which the interrupt
+    /// pin is not connected
+    ///
+    /// Events should then be read using [poll_event](Self::poll_event), which
+    /// samples the device periodically.
+    pub fn new_without_irq(i2c: I2C, config: Config) -> Self {
+        Self::new_with_config(i2c, NoIrq, config)
+    }
+}
 
-    Ok(Event::Button(record))
+impl<I2C, IRQ, DELAY, E> TT21100<I2C, IRQ, DELAY>
+where
+    I2C: I2c<Error = E>,
+    IRQ: InputPin,
+    E: Debug,
+    DELAY: DelayNs,
+{
+    /// Create a new instance of the driver using the provided delay and
+    /// configuration
+    ///
+    /// The delay is used to enforce [Config::transaction_gap_us].
+    pub fn new_with_delay(i2c: I2C, irq: IRQ, delay: DELAY, config: Config) -> Self {
+        Self::create(i2c, irq, None, delay, config, NoRotation)
+    }
+}
+
+impl<I2C, IRQ, DELAY, ORIENT, E> TT21100<I2C, IRQ, DELAY, NoReset, ORIENT>
+where
+    I2C: I2c<Error = E>,
+    IRQ: InputPin,
+    E: Debug,
+    DELAY: DelayNs,
+    ORIENT: OrientationProvider,
+{
+    /// Create a new instance of the driver for a display which rotates at
+    /// runtime, using the provided delay and configuration
+    ///
+    /// The coordinates of each touch record then follow the orientation of
+    /// the display, for example as reported by an accelerometer. The provider
+    /// is queried once per touch report, and the rotation it reports is
+    /// applied following the configured [transform](Config::transform).
+    pub fn new_with_orientation(
+        i2c: I2C,
+        irq: IRQ,
+        delay: DELAY,
+        config: Config,
+        provider: ORIENT,
+    ) -> Self {
+        Self::create(i2c, irq, None, delay, config, provider)
+    }
+}
+
+impl<I2C, IRQ, DELAY, RST, E> TT21100<I2C, IRQ, DELAY, RST>
+where
+    I2C: I2c<Error = E>,
+    IRQ: InputPin,
+    E: Debug,
+    DELAY: DelayNs,
+    RST: OutputPin,
+{
+    /// Create a new instance of the driver for a device whose reset pin is
+    /// connected, using the provided delay and configuration
+    ///
+    /// The device is then reset by [init](Self::init) and
+    /// [hard_reset](Self::hard_reset), with the delay used to time the reset
+    /// pulse and the recovery from it, as well as to enforce
+    /// [Config::transaction_gap_us].
+    pub fn new_with_reset(i2c: I2C, irq: IRQ, reset: RST, delay: DELAY, config: Config) -> Self {
+        Self::create(i2c, irq, Some(reset), delay, config, NoRotation)
+    }
+}
+
+impl<I2C, IRQ, DELAY, RST, ORIENT, E> TT21100<I2C, IRQ, DELAY, RST, ORIENT>
+where
+    I2C: I2c<Error = E>,
+    IRQ: InputPin,
+    E: Debug,
+    DELAY: DelayNs,
+    RST: OutputPin,
+    ORIENT: OrientationProvider,
+{
+    /// Create a new instance of the driver for a device whose reset pin is
+    /// connected, on a display which rotates at runtime
+    ///
+    /// The reset pin is used as by [new_with_reset](TT21100::new_with_reset),
+    /// and the orientation provider as by
+    /// [new_with_orientation](TT21100::new_with_orientation).
+    pub fn new_with_reset_and_orientation(
+        i2c: I2C,
+        irq: IRQ,
+        reset: RST,
+        delay: DELAY,
+        config: Config,
+        provider: ORIENT,
+    ) -> Self {
+        Self::create(i2c, irq, Some(reset), delay, config, provider)
+    }
+
+    /// Initialize the device, confirming that it can be communicated with
+    ///
+    /// Initialization takes several bus transactions, so may be skipped where
+    /// boot time is critical. Communication with the device is then instead
+    /// verified by the first event read, which reports any message of
+    /// unexpected length as an error regardless of the configured
+    /// [ParseMode]; a device which is absent or unresponsive is therefore only
+    /// detected once the first event is read.
+    ///
+    /// When the driver has been provided with a reset pin the device is reset
+    /// first, ensuring that it starts from a known state, for example after a
+    /// brown-out.
+    pub fn init(&mut self) -> Result<(), Error<E>> {
+        self.pulse_reset()?;
+        self.connect()
+    }
+
+    /// Has the device been initialized?
+    ///
+    /// The device is initialized by [init](Self::init) or
+    /// [reinit](Self::reinit), or once the first message has been read from it
+    /// successfully.
+    pub fn is_initialized(&self) -> bool {
+        self.state.initialized
+    }
+
+    /// Initialize the device again, for example after it has been reset or
+    /// reconnected
+    ///
+    /// Any state derived from earlier reports, along with any
+    /// [injected](Self::inject) event, is discarded before the handshake is
+    /// performed again. The stored configuration continues to apply.
+    pub fn reinit(&mut self) -> Result<(), Error<E>> {
+        self.state.reset();
+
+        self.connect()
+    }
+
+    /// Reset the device using its reset pin, then initialize it again
+    ///
+    /// Any state derived from earlier reports is discarded, as by
+    /// [reinit](Self::reinit), and the device is awake once reset. Without a
+    /// reset pin this is equivalent to [reinit](Self::reinit).
+    pub fn hard_reset(&mut self) -> Result<(), Error<E>> {
+        self.pulse_reset()?;
+        self.state.asleep = false;
+
+        self.reinit()
+    }
+
+    /// Put the device to sleep
+    ///
+    /// The device stops scanning the panel and reports no events while
+    /// asleep. It is woken, and initialized again, by [wake](Self::wake).
+    ///
+    /// With the `sleep-on-drop` feature enabled, the device is also put to
+    /// sleep when the driver is dropped, unless it is asleep already. The
+    /// feature applies only to this, the blocking driver; the async driver
+    /// cannot write to the bus as it is dropped, so provides
+    /// `release_sleeping` instead.
+    pub fn sleep(&mut self) -> Result<(), Error<E>> {
+        self.write_bytes(&SLEEP_COMMAND)?;
+        self.state.asleep = true;
+
+        Ok(())
+    }
+
+    /// Put the device to sleep, arming it to wake when the given gesture is
+    /// performed
+    ///
+    /// The device asserts the interrupt line once the gesture has been
+    /// performed, so the host may itself sleep until then; it should then wake
+    /// the device using [wake](Self::wake). Any events read while waiting for
+    /// the device to acknowledge the command are discarded. The acknowledgement
+    /// is polled for using the driver's delay, so one should have been
+    /// provided using [new_with_delay](TT21100::new_with_delay).
+    pub fn sleep_until(&mut self, gesture: WakeGesture) -> Result<(), Error<E>> {
+        self.write_command(command::ENTER_EASY_WAKE, &[u8::from(gesture)])?;
+        self.state.asleep = true;
+
+        Ok(())
+    }
+
+    /// Put the device into its lowest-power state
+    ///
+    /// The device is put to sleep, and is then held in reset when the driver
+    /// has been provided with a reset pin, drawing as little power as
+    /// possible. Waking the device with [wake](Self::wake) then takes as long
+    /// as a [hard_reset](Self::hard_reset). Without a reset pin this is
+    /// equivalent to [sleep](Self::sleep).
+    pub fn deep_sleep(&mut self) -> Result<(), Error<E>> {
+        self.sleep()?;
+        if let Some(reset) = self.reset.as_mut() {
+            reset.set_low().map_err(|_| Error::IOError)?;
+            self.state.held_in_reset = true;
+        }
+
+        Ok(())
+    }
+
+    /// Wake the device from sleep or deep sleep, initializing it again
+    ///
+    /// Any state derived from reports read before the device was put to sleep
+    /// is discarded, as by [reinit](Self::reinit); the stored configuration
+    /// continues to apply. Events queued while the device was asleep, such as
+    /// the [gesture](Self::sleep_until) which woke it, are discarded too.
+    pub fn wake(&mut self) -> Result<(), Error<E>> {
+        if self.state.held_in_reset {
+            self.release_reset()?;
+        } else {
+            self.write_bytes(&WAKE_COMMAND)?;
+            self.flush_pending()?;
+        }
+        self.state.asleep = false;
+
+        self.reinit()
+    }
+
+    /// Has the device been put to sleep?
+    pub fn is_asleep(&self) -> bool {
+        self.state.asleep
+    }
+
+    /// Put the device to sleep
+    #[deprecated(note = "renamed to `sleep`")]
+    pub fn suspend(&mut self) -> Result<(), Error<E>> {
+        self.sleep()
+    }
+
+    /// Wake the device from sleep, initializing it again
+    #[deprecated(note = "renamed to `wake`")]
+    pub fn resume(&mut self) -> Result<(), Error<E>> {
+        self.wake()
+    }
+
+    /// Has the device been put to sleep?
+    #[deprecated(note = "renamed to `is_asleep`")]
+    pub fn is_suspended(&self) -> bool {
+        self.is_asleep()
+    }
+
+    /// Read the identity of the device and the version of its firmware
+    ///
+    /// These are read from the device's HID descriptor, and are useful when
+    /// diagnosing differences in behaviour between revisions of a panel.
+    pub fn version(&mut self) -> Result<Version, Error<E>> {
+        let mut descriptor = [0u8; descriptor::DESCRIPTOR_LEN];
+        self.read_register(descriptor::DESCRIPTOR_REGISTER, &mut descriptor)?;
+
+        Version::from_descriptor(&descriptor).ok_or(Error::InvalidDescriptor)
+    }
+
+    /// Read from a register of the device
+    ///
+    /// This is an escape hatch for functionality not otherwise provided by the
+    /// driver; reading some registers alters what is returned when the next
+    /// event is read.
+    pub fn read_register(&mut self, register: u16, buffer: &mut [u8]) -> Result<(), Error<E>> {
+        self.wait_for_gap();
+
+        #[cfg(feature = "instrumentation")]
+        let start = self.state.start_timing();
+        self.i2c
+            .write_read(self.state.config.address, &register.to_le_bytes(), buffer)
+            .map_err(|e| Error::BusError(e))?;
+        #[cfg(feature = "instrumentation")]
+        self.state
+            .record_timing(instrumentation::Transaction::RegisterRead, start);
+
+        Ok(())
+    }
+
+    /// Write to a register of the device
+    ///
+    /// This is an escape hatch for functionality not otherwise provided by the
+    /// driver, and may leave the device in a state the driver does not expect.
+    pub fn write_register(&mut self, register: u16, data: &[u8]) -> Result<(), Error<E>> {
+        self.wait_for_gap();
+
+        // Adjacent writes are sent without a restart between them
+        let register = register.to_le_bytes();
+        #[cfg(feature = "instrumentation")]
+        let start = self.state.start_timing();
+        self.i2c
+            .transaction(
+                self.state.config.address,
+                &mut [Operation::Write(&register), Operation::Write(data)],
+            )
+            .map_err(|e| Error::BusError(e))?;
+        #[cfg(feature = "instrumentation")]
+        self.state
+            .record_timing(instrumentation::Transaction::RegisterWrite, start);
+
+        Ok(())
+    }
+
+    /// Write a command of the vendor protocol to the device, returning its
+    /// response
+    ///
+    /// This is an escape hatch for functionality not otherwise provided by the
+    /// driver. At most [MAX_COMMAND_PARAMETERS](crate::MAX_COMMAND_PARAMETERS)
+    /// parameters may be given. Any events read while waiting for the response
+    /// are discarded.
+    pub fn write_command(&mut self, code: u8, parameters: &[u8]) -> Result<Response, Error<E>> {
+        let command = command::Command::new(code, parameters)
+            .ok_or(Error::CommandTooLong(parameters.len()))?;
+        self.write_bytes(command.as_bytes())?;
+
+        let mut response = Response::new();
+        for _ in 0..RESPONSE_ATTEMPTS {
+            self.read_bytes(response.buffer(MAX_MESSAGE_LEN))?;
+            response.truncate();
+            if command::is_response(response.as_bytes(), code) {
+                if self.state.config.verify_checksums && !response.has_valid_crc() {
+                    return Err(Error::ChecksumMismatch);
+                }
+
+                return Ok(response);
+            }
+
+            self.delay.delay_us(RESPONSE_INTERVAL_US);
+        }
+
+        Err(Error::NoResponse)
+    }
+
+    /// Is there data available to read from the device?
+    pub fn data_available(&mut self) -> Result<bool, Error<E>> {
+        if self.state.has_injected() {
+            return Ok(true);
+        }
+
+        self.irq.is_low().map_err(|_| Error::IOError)
+    }
+
+    /// Does the device have a message pending?
+    ///
+    /// Only the length of the pending message is read, so this is a cheap way
+    /// for a dispatcher to determine whether the device asserted an interrupt
+    /// line it shares with other devices, before reading the event in full.
+    /// An [injected](Self::inject) event is always pending.
+    pub fn has_pending(&mut self) -> Result<bool, Error<E>> {
+        if self.state.has_injected() {
+            return Ok(true);
+        }
+
+        Ok(self.read_message_length()? > 2)
+    }
+
+    /// Read an event from the device
+    ///
+    /// There are two types of events, [Event::Touch] and [Event::Button],
+    /// with touch events reported as [Event::PalmRejected] while a palm is
+    /// being rejected. Any event which has been [injected](Self::inject) is
+    /// returned ahead of those read from the device.
+    pub fn event(&mut self) -> Result<Event, Error<E>> {
+        if let Some(event) = self.state.take_injected(&mut self.orientation) {
+            return Ok(event);
+        }
+
+        // Messages which do not result in an event are skipped in favour of the
+        // next message queued on the device
+        for _ in 0..MAX_SKIPPED_MESSAGES {
+            if let Some(event) = self.read_event()? {
+                return Ok(event);
+            }
+        }
+
+        Err(Error::NoDataAvailable)
+    }
+
+    /// Wait for an event, polling the interrupt pin until data is available
+    ///
+    /// The idle hook, if one has been set, is invoked between each poll.
+    pub fn wait_for_event(&mut self) -> Result<Event, Error<E>> {
+        loop {
+            if self.data_available()? {
+                match self.event() {
+                    Err(Error::NoDataAvailable) => {}
+                    result => return result,
+                }
+            }
+
+            self.idle();
+        }
+    }
+
+    /// Wait for an event, sampling the device periodically rather than
+    /// polling the interrupt pin
+    ///
+    /// For use when no interrupt line is available. The device is sampled
+    /// every [Config::poll_interval_us] while it is in use, backing off to
+    /// [Config::idle_poll_interval_us] once no events have been read for
+    /// [Config::idle_after_us]. The idle hook (if one has been set) is invoked
+    /// between each sample.
+    pub fn poll_event<D>(&mut self, delay: &mut D) -> Result<Event, Error<E>>
+    where
+        D: DelayNs,
+    {
+        loop {
+            match self.event() {
+                Err(Error::NoDataAvailable) => {}
+                result => {
+                    self.state.record_sample(true, 0);
+                    return result;
+                }
+            }
+
+            self.idle();
+            let interval_us = self.state.poll_interval_us();
+            delay.delay_us(interval_us);
+            self.state.record_sample(false, interval_us);
+        }
+    }
+
+    /// Wait for an event, giving up after `timeout_us` microseconds
+    ///
+    /// The interrupt pin is polled at the interval specified by
+    /// [Config::poll_interval_us], with the idle hook (if one has been set)
+    /// invoked between each poll. Returns `Ok(None)` if no event was read
+    /// before the timeout elapsed.
+    pub fn event_with_timeout<D>(
+        &mut self,
+        delay: &mut D,
+        timeout_us: u32,
+    ) -> Result<Option<Event>, Error<E>>
+    where
+        D: DelayNs,
+    {
+        let mut waited_us = 0;
+        loop {
+            if self.data_available()? {
+                match self.event() {
+                    Ok(event) => return Ok(Some(event)),
+                    Err(Error::NoDataAvailable) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+
+            if waited_us >= timeout_us {
+                return Ok(None);
+            }
+
+            let interval_us = self
+                .state
+                .config
+                .poll_interval_us
+                .max(1)
+                .min(timeout_us - waited_us);
+            self.idle();
+            delay.delay_us(interval_us);
+            waited_us += interval_us;
+        }
+    }
+
+    /// Set a callback to be invoked between polls of the interrupt pin while
+    /// waiting for an event
+    ///
+    /// This can be used to, for example, enter a low-power state or feed a
+    /// watchdog rather than busy-waiting.
+    pub fn set_idle_hook(&mut self, hook: Option<fn()>) {
+        self.idle_hook = hook;
+    }
+
+    /// Discard every event currently queued on the device, along with any
+    /// [injected](Self::inject) event, returning the number of messages
+    /// discarded
+    ///
+    /// This is useful after resuming from sleep or a long pause in handling
+    /// events, when the queue may hold stale touches which would otherwise be
+    /// delivered as phantom input. Messages are discarded without being
+    /// parsed, so malformed messages do not interrupt the flush.
+    pub fn flush_pending(&mut self) -> Result<usize, Error<E>> {
+        self.state.flush();
+
+        let mut data = [0u8; MAX_MESSAGE_LEN];
+        for flushed in 0..MAX_FLUSHED_MESSAGES {
+            self.read_bytes(&mut data)?;
+            if u16::from_le_bytes([data[0], data[1]]) <= 2 {
+                return Ok(flushed);
+            }
+        }
+
+        Ok(MAX_FLUSHED_MESSAGES)
+    }
+
+    /// Iterate over the events currently queued on the device
+    ///
+    /// Events are read until the device reports that no more are available,
+    /// so this is typically used to handle everything pending once the
+    /// interrupt pin has been asserted. Iteration also ends after the first
+    /// error, which is yielded.
+    pub fn events(&mut self) -> Events<'_, I2C, IRQ, DELAY, RST, ORIENT> {
+        Events {
+            driver: self,
+            done: false,
+        }
+    }
+
+    /// Read every event currently queued on the device
+    ///
+    /// Events are read until the device reports that no more are available.
+    #[cfg(feature = "std")]
+    pub fn drain(&mut self) -> Result<Vec<Event>, Error<E>> {
+        self.events().collect()
+    }
+
+    /// The most recent message read from the device, exactly as it was read
+    ///
+    /// Passing this to a [TraceWriter](trace::TraceWriter) after each event is
+    /// read records a trace which can later be replayed. Injected events are
+    /// not included.
+    #[cfg(feature = "trace")]
+    pub fn last_frame(&self) -> &[u8] {
+        self.state.last_frame()
+    }
+
+    /// Inject a synthetic event, to be returned by the next read in place of
+    /// an event from the device
+    ///
+    /// Injected events are validated as though they had been read from the
+    /// device, allowing automated tests or remote control to drive any
+    /// processing of events without touching the panel. Returns the event
+    /// previously injected, if it has not yet been read.
+    pub fn inject(&mut self, event: Event) -> Option<Event> {
+        self.state.inject(event)
+    }
+
+    /// The current configuration of the driver
+    pub fn config(&self) -> &Config {
+        &self.state.config
+    }
+
+    /// Replace the configuration of the driver
+    ///
+    /// The new configuration takes effect from the next event read. As the
+    /// driver must be borrowed mutably to do so, the configuration can never
+    /// change part way through reading an event.
+    pub fn set_config(&mut self, config: Config) {
+        self.state.config = config;
+    }
+
+    /// Select the native coordinate range of the panel in use
+    pub fn set_panel(&mut self, panel: PanelProfile) {
+        self.state.config.panel = panel;
+    }
+
+    /// Select the transform applied to the coordinates of each touch record
+    pub fn set_transform(&mut self, transform: geom::Transform) {
+        self.state.config.transform = transform;
+    }
+
+    /// Select the mapping of the panel's coordinates onto those of the
+    /// display, if any
+    pub fn set_calibration(&mut self, calibration: Option<Calibration>) {
+        self.state.config.calibration = calibration;
+    }
+
+    /// Provide a clock used to measure the duration of bus transactions
+    ///
+    /// The clock must return the current time in microseconds; it is permitted
+    /// to wrap around.
+    #[cfg(feature = "instrumentation")]
+    pub fn set_clock(&mut self, clock: Option<fn() -> u32>) {
+        self.state.clock = clock;
+    }
+
+    /// Measured durations of the bus transactions performed by the driver
+    #[cfg(feature = "instrumentation")]
+    pub fn bus_timings(&self) -> &instrumentation::BusTimings {
+        &self.state.timings
+    }
+
+    /// Number of touch records which have been rejected for having coordinates
+    /// outside of the configured range, for appearing during the cool-down
+    /// period following a report of a large object, or while a palm was being
+    /// rejected
+    pub fn rejected_records(&self) -> u32 {
+        self.state.rejected_records()
+    }
+
+    /// Number of touch reports which have been dropped for being affected by
+    /// noise at least as severe as the configured
+    /// [threshold](crate::Config::noise_threshold)
+    #[cfg(feature = "report-processing")]
+    pub fn noisy_reports(&self) -> u32 {
+        self.state.noisy_reports()
+    }
+
+    /// Number of spontaneous resets of the device which have been detected
+    #[cfg(feature = "report-processing")]
+    pub fn resets(&self) -> u32 {
+        self.state.resets()
+    }
+
+    /// Number of touch reports which were missed, having been overwritten by
+    /// the next before they were read
+    ///
+    /// Missed reports are detected using the counter carried by each report,
+    /// which is only two bits wide, so a gap of four or more consecutive
+    /// reports is undercounted. An increasing count indicates that events are
+    /// not being read quickly enough.
+    #[cfg(feature = "report-processing")]
+    pub fn missed_reports(&self) -> u32 {
+        self.state.missed_reports()
+    }
+
+    // -----------------------------------------------------------------------
+    // PRIVATE
+
+    fn create(
+        i2c: I2C,
+        irq: IRQ,
+        reset: Option<RST>,
+        delay: DELAY,
+        config: Config,
+        orientation: ORIENT,
+    ) -> Self {
+        Self {
+            i2c,
+            irq,
+            reset,
+            orientation,
+            state: state::State::new(config),
+            delay,
+            #[cfg(feature = "sleep-on-drop")]
+            sleep: sleep_on_drop::<I2C, E>,
+            idle_hook: None,
+        }
+    }
+
+    fn connect(&mut self) -> Result<(), Error<E>> {
+        self.handshake()?;
+        self.state.initialized = true;
+
+        Ok(())
+    }
+
+    fn pulse_reset(&mut self) -> Result<(), Error<E>> {
+        if let Some(reset) = self.reset.as_mut() {
+            reset.set_low().map_err(|_| Error::IOError)?;
+            self.delay.delay_us(RESET_PULSE_US);
+            self.release_reset()?;
+        }
+
+        Ok(())
+    }
+
+    fn release_reset(&mut self) -> Result<(), Error<E>> {
+        if let Some(reset) = self.reset.as_mut() {
+            reset.set_high().map_err(|_| Error::IOError)?;
+            self.delay.delay_us(RESET_RECOVERY_US);
+        }
+        self.state.held_in_reset = false;
+
+        Ok(())
+    }
+
+    fn idle(&self) {
+        if let Some(hook) = self.idle_hook {
+            hook();
+        }
+    }
+
+    fn handshake(&mut self) -> Result<(), Error<E>> {
+        // I'm honestly not entirely sure what is going on here (would be *really* nice
+        // if I had a datasheet!).
+        //
+        // As far as I can tell, when no events are queued on device for reading it will
+        // always return an empty message with length 2, so we're just sort of
+        // making sure we can talk to the device.
+        //
+        // Each driver I referenced seems to perform this step:
+        //
+        // https://github.com/espressif/esp-box/blob/147cd8d/components/i2c_devices/touch_panel/tt21100.c#L56-L60
+        // https://github.com/SuGlider/Adafruit_ESP32S3_BOX/blob/a9884ac/src/ESP32_S3_Box_TouchScreen.cpp#L15-L20
+        // https://github.com/adafruit/Adafruit_CircuitPython_TT21100/blob/b3113a4/adafruit_tt21100.py#L59-L63
+        let mut message_length = 0;
+        for _ in 0..5 {
+            message_length = self.read_message_length()?;
+            if message_length == 2 {
+                break;
+            }
+        }
+
+        match message_length {
+            2 => Ok(()),
+            n => Err(Error::InvalidMessageLen(n)),
+        }
+    }
+
+    /// Read the next message queued on the device, returning the event it
+    /// carried, if any
+    fn read_event(&mut self) -> Result<Option<Event>, Error<E>> {
+        // The message is read in its entirety, along with its length, in a single
+        // transaction so that traffic to other devices on a shared bus cannot be
+        // interleaved between the two
+        let mut data = [0u8; MAX_MESSAGE_LEN];
+        #[cfg(feature = "instrumentation")]
+        let start = self.state.start_timing();
+        self.read_bytes(&mut data)?;
+        #[cfg(feature = "instrumentation")]
+        self.state
+            .record_timing(instrumentation::Transaction::PayloadRead, start);
+
+        let message_length = usize::from(u16::from_le_bytes([data[0], data[1]]));
+        let length = message_length.min(data.len());
+
+        match self
+            .state
+            .process(message_length, &data[0..][..length], &mut self.orientation)
+        {
+            Err(Error::DeviceReset) => self.recover(),
+            result => result,
+        }
+    }
+
+    fn recover<T>(&mut self) -> Result<T, Error<E>> {
+        self.reinit()?;
+
+        Err(Error::DeviceReset)
+    }
+
+    fn read_message_length(&mut self) -> Result<usize, Error<E>> {
+        let mut buffer = [0u8; 2];
+        #[cfg(feature = "instrumentation")]
+        let start = self.state.start_timing();
+        self.read_bytes(&mut buffer)?;
+        #[cfg(feature = "instrumentation")]
+        self.state
+            .record_timing(instrumentation::Transaction::LengthRead, start);
+
+        let message_length = u16::from_le_bytes(buffer);
+
+        Ok(message_length as usize)
+    }
+
+    fn wait_for_gap(&mut self) {
+        if let Some(gap_us) = self.state.transaction_gap_us() {
+            self.delay.delay_us(gap_us);
+        }
+    }
+
+    fn read_bytes(&mut self, buffer: &mut [u8]) -> Result<(), Error<E>> {
+        self.wait_for_gap();
+
+        self.i2c
+            .read(self.state.config.address, buffer)
+            .map_err(|e| Error::BusError(e))
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), Error<E>> {
+        self.wait_for_gap();
+
+        #[cfg(feature = "instrumentation")]
+        let start = self.state.start_timing();
+        self.i2c
+            .write(self.state.config.address, bytes)
+            .map_err(|e| Error::BusError(e))?;
+        #[cfg(feature = "instrumentation")]
+        self.state
+            .record_timing(instrumentation::Transaction::CommandWrite, start);
+
+        Ok(())
+    }
+}
+
+/// Iterator over the events queued on the device, returned by
+/// [TT21100::events]
+pub struct Events<'a, I2C, IRQ, DELAY, RST = NoReset, ORIENT = NoRotation> {
+    driver: &'a mut TT21100<I2C, IRQ, DELAY, RST, ORIENT>,
+    done: bool,
+}
+
+impl<I2C, IRQ, DELAY, RST, ORIENT, E> Iterator for Events<'_, I2C, IRQ, DELAY, RST, ORIENT>
+where
+    I2C: I2c<Error = E>,
+    IRQ: InputPin,
+    E: Debug,
+    DELAY: DelayNs,
+    RST: OutputPin,
+    ORIENT: OrientationProvider,
+{
+    type Item = Result<Event, Error<E>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.driver.event() {
+            Err(Error::NoDataAvailable) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+            event => Some(event),
+        }
+    }
+}
+
+#[cfg(feature = "sleep-on-drop")]
+impl<I2C, IRQ, DELAY, RST, ORIENT> Drop for TT21100<I2C, IRQ, DELAY, RST, ORIENT> {
+    fn drop(&mut self) {
+        if !self.state.asleep {
+            (self.sleep)(&mut self.i2c, self.state.config.address);
+        }
+    }
+}
+
+// Errors cannot be reported from `drop`, so are ignored; the device is left
+// awake if the command could not be written
+#[cfg(feature = "sleep-on-drop")]
+fn sleep_on_drop<I2C, E>(i2c: &mut I2C, address: u8)
+where
+    I2C: I2c<Error = E>,
+{
+    i2c.write(address, &SLEEP_COMMAND).ok();
+}
+
+/// Outcome of parsing a message read from the device
+pub(crate) enum Parsed {
+    /// The message carried an event
+    Event(Event),
+    /// The device had no message queued
+    Empty,
+    /// The message could not be parsed, and was skipped by
+    /// [ParseMode::Lenient] parsing
+    Skipped,
+}
+
+fn parse_message<E>(
+    message_length: usize,
+    message: &[u8],
+    parsing: ParseMode,
+) -> Result<Parsed, Error<E>>
+where
+    E: Debug,
+{
+    if message_length == 2 {
+        return Ok(Parsed::Empty);
+    }
+
+    // Messages are dispatched on their report ID, with their length only
+    // used to determine how much of the message is present
+    match (ReportId::of_message(message), parsing) {
+        (Some(ReportId::Touch), _) if is_touch_message_len(message_length) => {
+            touch_event(message).map(Parsed::Event)
+        }
+        (Some(ReportId::Button), _) if message_length == ButtonRecord::SIZE => {
+            button_event(message).map(Parsed::Event)
+        }
+        (Some(ReportId::Unknown(id)), ParseMode::Strict) => Err(Error::UnknownReport(id)),
+        (_, ParseMode::Strict) => Err(Error::InvalidMessageLen(message_length)),
+        // Parse as many complete touch records as are present, if any
+        (Some(ReportId::Touch), ParseMode::Lenient) if message_length > 7 => {
+            let records =
+                ((message.len() - TouchReport::SIZE) / TouchRecord::SIZE).min(MAX_TOUCHES);
+            touch_event(&message[0..][..TouchReport::SIZE + records * TouchRecord::SIZE])
+                .map(Parsed::Event)
+        }
+        // Tolerate trailing bytes following a button record
+        (Some(ReportId::Button), ParseMode::Lenient) if message.len() > ButtonRecord::SIZE => {
+            button_event(&message[0..][..ButtonRecord::SIZE]).map(Parsed::Event)
+        }
+        (_, ParseMode::Lenient) => Ok(Parsed::Skipped),
+    }
+}
+
+fn touch_event<E>(message: &[u8]) -> Result<Event, Error<E>>
+where
+    E: Debug,
+{
+    debug_assert!(is_touch_message_len(message.len()) && message.len() <= MAX_MESSAGE_LEN);
+
+    let report = message[0..][..TouchReport::SIZE].try_into()?;
+    let report = TouchReport::from_bytes(report);
+
+    let mut touches = Touches::new();
+    for record in message[TouchReport::SIZE..].chunks_exact(TouchRecord::SIZE) {
+        let record = record.try_into()?;
+        touches.push(TouchRecord::from_bytes(record)).ok();
+    }
+
+    Ok(Event::Touch { report, touches })
+}
+
+/// Is `length` the length of a touch message carrying a whole number of
+/// records?
+///
+/// Messages carrying more than [MAX_TOUCHES] records are accepted; only the
+/// first [MAX_TOUCHES] records are read from the device, and any others are
+/// discarded.
+fn is_touch_message_len(length: usize) -> bool {
+    length >= TouchReport::SIZE && (length - TouchReport::SIZE) % TouchRecord::SIZE == 0
+}
+
+fn button_event<E>(message: &[u8]) -> Result<Event, Error<E>>
+where
+    E: Debug,
+{
+    debug_assert_eq!(message.len(), 14);
+
+    let message = message.try_into()?;
+    let record = ButtonRecord::from_bytes(message);
+
+    Ok(Event::Button(record))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// Serialize a touch report followed by the given records, returning the
+    /// message along with its length
+    ///
+    /// The length of the message is taken from the records, whatever number of
+    /// records the report declares.
+    pub(crate) fn touch_message(
+        mut report: TouchReport,
+        records: &[TouchRecord],
+    ) -> ([u8; MAX_MESSAGE_LEN], usize) {
+        let length = TouchReport::SIZE + records.len() * TouchRecord::SIZE;
+        report.data_len = length as u16;
+
+        let mut message = [0; MAX_MESSAGE_LEN];
+        message[0..][..TouchReport::SIZE].copy_from_slice(&report.into_bytes());
+        for (chunk, record) in message[TouchReport::SIZE..]
+            .chunks_exact_mut(TouchRecord::SIZE)
+            .zip(records)
+        {
+            chunk.copy_from_slice(&record.into_bytes());
+        }
+
+        (message, length)
+    }
+
+    fn parse(message: &[u8], length: usize, parsing: ParseMode) -> Result<Parsed, Error<()>> {
+        parse_message(length, &message[0..][..length.min(message.len())], parsing)
+    }
+
+    #[test]
+    fn accepts_responses_with_valid_checksum() {
+        let response = mock::response(0x09, &[1, 2, 3]);
+        let mut driver = TT21100::new_without_irq(mock::Bus::new(&[&response]), Config::default());
+
+        match driver.write_command(0x09, &[]) {
+            Ok(response) => assert_eq!(response.data(), [1, 2, 3]),
+            _ => panic!("expected a response"),
+        }
+    }
+
+    #[test]
+    fn rejects_responses_with_invalid_checksum() {
+        let mut response = mock::response(0x09, &[1, 2, 3]);
+        response[5] ^= 0x01;
+        let mut driver = TT21100::new_without_irq(mock::Bus::new(&[&response]), Config::default());
+
+        let result = driver.write_command(0x09, &[]);
+
+        assert!(matches!(result, Err(Error::ChecksumMismatch)));
+    }
+
+    #[test]
+    fn skips_checksum_verification_when_disabled() {
+        let mut response = mock::response(0x09, &[1, 2, 3]);
+        response[5] ^= 0x01;
+        let config = Config {
+            verify_checksums: false,
+            ..Config::default()
+        };
+        let mut driver = TT21100::new_without_irq(mock::Bus::new(&[&response]), config);
+
+        assert!(driver.write_command(0x09, &[]).is_ok());
+    }
+
+    #[test]
+    fn rejects_commands_with_too_many_parameters() {
+        let mut driver = TT21100::new_without_irq(mock::Bus::default(), Config::default());
+
+        let result = driver.write_command(0x09, &[0; MAX_COMMAND_PARAMETERS + 1]);
+
+        assert!(matches!(result, Err(Error::CommandTooLong(17))));
+        assert!(driver.i2c.transactions.is_empty());
+    }
+
+    #[test]
+    fn reads_version_from_descriptor() {
+        let mut descriptor = [0; descriptor::DESCRIPTOR_LEN];
+        descriptor[0] = descriptor::DESCRIPTOR_LEN as u8;
+        descriptor[20..26].copy_from_slice(&[0xB4, 0x04, 0x10, 0x21, 0x02, 0x01]);
+        let mut driver =
+            TT21100::new_without_irq(mock::Bus::new(&[&descriptor]), Config::default());
+
+        match driver.version() {
+            Ok(version) => {
+                assert_eq!(version.vendor_id, 0x04B4);
+                assert_eq!(version.product_id, 0x2110);
+                assert_eq!(version.firmware, 0x0102);
+            }
+            _ => panic!("expected the version of the device"),
+        }
+        assert_eq!(
+            driver.i2c.transactions,
+            [[
+                mock::Op::Write(descriptor::DESCRIPTOR_REGISTER.to_le_bytes().to_vec()),
+                mock::Op::Read(descriptor::DESCRIPTOR_LEN),
+            ]]
+        );
+    }
+
+    #[test]
+    fn accesses_registers_of_device() {
+        let mut driver = TT21100::new_without_irq(mock::Bus::new(&[&[4, 0]]), Config::default());
+
+        let mut buffer = [0; 2];
+        assert!(driver.read_register(0x0102, &mut buffer).is_ok());
+        assert_eq!(buffer, [4, 0]);
+        assert!(driver.write_register(0x0102, &[5, 6]).is_ok());
+
+        assert_eq!(
+            driver.i2c.transactions,
+            [
+                [mock::Op::Write([0x02, 0x01].to_vec()), mock::Op::Read(2)],
+                [
+                    mock::Op::Write([0x02, 0x01].to_vec()),
+                    mock::Op::Write([5, 6].to_vec())
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn writes_commands_and_reads_responses() {
+        let response = mock::response(0x0A, &[7]);
+        let mut driver = TT21100::new_without_irq(mock::Bus::new(&[&response]), Config::default());
+
+        match driver.write_command(0x0A, &[1, 2]) {
+            Ok(response) => assert_eq!(response.data(), [7]),
+            _ => panic!("expected a response"),
+        }
+        let command = command::Command::new(0x0A, &[1, 2]).unwrap();
+        assert_eq!(
+            driver.i2c.transactions,
+            [
+                [mock::Op::Write(command.as_bytes().to_vec())],
+                [mock::Op::Read(MAX_MESSAGE_LEN)],
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_descriptors_of_unexpected_length() {
+        let mut driver = TT21100::new_without_irq(mock::Bus::new(&[&[2, 0]]), Config::default());
+
+        assert!(matches!(driver.version(), Err(Error::InvalidDescriptor)));
+    }
+
+    #[test]
+    fn follows_orientation_of_the_display() {
+        let (message, length) =
+            touch_message(TouchReport::new(0, 1), &[TouchRecord::new(1, 10, 20)]);
+        let config = Config {
+            panel: PanelProfile::new(100, 200),
+            ..Config::default()
+        };
+        let mut driver = TT21100::new_with_orientation(
+            mock::Bus::new(&[&message[0..][..length]]),
+            NoIrq,
+            NoDelay,
+            config,
+            || geom::Orientation::Rotate180,
+        );
+
+        match driver.event() {
+            Ok(event) => assert!(event.touches().eq([TouchRecord::new(1, 90, 180)])),
+            _ => panic!("expected a touch event"),
+        }
+    }
+
+    #[cfg(feature = "instrumentation")]
+    #[test]
+    fn times_commands_and_register_accesses() {
+        let mut driver = TT21100::new_without_irq(mock::Bus::default(), Config::default());
+        driver.set_clock(Some(|| 0));
+
+        driver.sleep().unwrap();
+        driver.read_register(0x0001, &mut [0; 2]).unwrap();
+        driver.write_register(0x0001, &[0; 2]).unwrap();
+
+        let timings = driver.bus_timings();
+        assert_eq!(timings.command_write.count, 1);
+        assert_eq!(timings.register_read.count, 1);
+        assert_eq!(timings.register_write.count, 1);
+    }
+
+    #[test]
+    fn parses_empty_message() {
+        let parsed = parse(&[2, 0], 2, ParseMode::Strict);
+
+        assert!(matches!(parsed, Ok(Parsed::Empty)));
+    }
+
+    #[test]
+    fn parses_touch_message() {
+        let records = [TouchRecord::new(1, 10, 20), TouchRecord::new(2, 30, 40)];
+        let (message, length) = touch_message(TouchReport::new(100, 2), &records);
+
+        match parse(&message, length, ParseMode::Strict) {
+            Ok(Parsed::Event(Event::Touch { report, touches })) => {
+                assert_eq!(report.time_stamp, 100);
+                assert_eq!(report.record_num, 2);
+                assert!(touches.into_iter().eq(records));
+            }
+            _ => panic!("expected a touch event"),
+        }
+    }
+
+    #[test]
+    fn truncates_messages_with_too_many_records() {
+        let records = [
+            TouchRecord::new(1, 10, 20),
+            TouchRecord::new(2, 30, 40),
+            TouchRecord::new(3, 50, 60),
+            TouchRecord::new(4, 70, 80),
+            TouchRecord::new(5, 90, 100),
+            TouchRecord::new(6, 110, 120),
+        ];
+        let (message, length) = touch_message(TouchReport::new(100, 6), &records);
+        assert_eq!(length, 67);
+
+        for parsing in [ParseMode::Strict, ParseMode::Lenient] {
+            match parse(&message, length, parsing) {
+                Ok(Parsed::Event(event)) => {
+                    assert!(event.touches().eq(records[..MAX_TOUCHES].iter().copied()))
+                }
+                _ => panic!("expected a touch event"),
+            }
+        }
+    }
+
+    #[test]
+    fn parses_button_message() {
+        let message = ButtonRecord::new(100, 0b0101).into_bytes();
+
+        match parse(&message, message.len(), ParseMode::Strict) {
+            Ok(Parsed::Event(Event::Button(record))) => assert_eq!(record.btn_val, 0b0101),
+            _ => panic!("expected a button event"),
+        }
+    }
+
+    #[test]
+    fn strict_parsing_rejects_unknown_reports() {
+        let (mut message, length) = touch_message(TouchReport::new(0, 0), &[]);
+        message[2] = 5;
+
+        let parsed = parse(&message, length, ParseMode::Strict);
+
+        assert!(matches!(parsed, Err(Error::UnknownReport(5))));
+    }
+
+    #[test]
+    fn strict_parsing_rejects_partial_records() {
+        let (message, _) = touch_message(TouchReport::new(0, 1), &[TouchRecord::new(1, 0, 0)]);
+
+        let parsed = parse(&message, 12, ParseMode::Strict);
+
+        assert!(matches!(parsed, Err(Error::InvalidMessageLen(12))));
+    }
+
+    #[test]
+    fn lenient_parsing_keeps_complete_records() {
+        let records = [TouchRecord::new(1, 10, 20), TouchRecord::new(2, 30, 40)];
+        let (message, _) = touch_message(TouchReport::new(0, 2), &records);
+
+        match parse(&message, 22, ParseMode::Lenient) {
+            Ok(Parsed::Event(event)) => assert!(event.touches().eq(records[..1].iter().copied())),
+            _ => panic!("expected a touch event"),
+        }
+    }
+
+    #[test]
+    fn lenient_parsing_skips_unknown_reports() {
+        let (mut message, length) = touch_message(TouchReport::new(0, 0), &[]);
+        message[2] = 5;
+
+        let parsed = parse(&message, length, ParseMode::Lenient);
+
+        assert!(matches!(parsed, Ok(Parsed::Skipped)));
+    }
+
+    #[test]
+    fn identifies_reports_of_messages() {
+        let (message, length) = touch_message(TouchReport::new(0, 0), &[]);
+
+        assert!(ReportId::of_message(&message[0..][..length]) == Some(ReportId::Touch));
+        assert!(ReportId::of_message(&[14, 0, 3]) == Some(ReportId::Button));
+        assert!(ReportId::of_message(&[2, 0]).is_none());
+        assert!(ReportId::from(9) == ReportId::Unknown(9));
+        assert_eq!(u8::from(ReportId::Button), ReportId::BUTTON);
+        assert_eq!(u8::from(ReportId::Unknown(9)), 9);
+    }
+
+    #[test]
+    fn lenient_parsing_tolerates_trailing_bytes_after_buttons() {
+        let mut message = [0; ButtonRecord::SIZE + 2];
+        message[0..][..ButtonRecord::SIZE]
+            .copy_from_slice(&ButtonRecord::new(100, 0b0010).into_bytes());
+
+        let strict = parse(&message, message.len(), ParseMode::Strict);
+        assert!(matches!(strict, Err(Error::InvalidMessageLen(16))));
+
+        match parse(&message, message.len(), ParseMode::Lenient) {
+            Ok(Parsed::Event(Event::Button(record))) => assert_eq!(record.btn_val, 0b0010),
+            _ => panic!("expected a button event"),
+        }
+    }
+
+    #[test]
+    fn iterates_over_touches_of_events() {
+        let records = [TouchRecord::new(1, 10, 20), TouchRecord::new(2, 30, 40)];
+
+        assert!(Event::touch(0, &records).touches().eq(records));
+        assert_eq!(Event::touch(0, &[]).touches().count(), 0);
+        assert_eq!(Event::Button(ButtonRecord::new(0, 1)).touches().count(), 0);
+        assert_eq!(
+            Event::PalmRejected(TouchReport::new(0, 2))
+                .touches()
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn from_message_reports_empty_messages_as_no_data() {
+        let event = Event::from_message::<()>(&[2, 0], ParseMode::Strict);
+
+        assert!(matches!(event, Err(Error::NoDataAvailable)));
+    }
+
+    #[test]
+    fn classifies_noise_levels() {
+        let effects = [0, 1, 2, 3, 4, 5, 7, 0b1000].map(NoiseEffect::from);
+
+        assert!(
+            effects
+                == [
+                    NoiseEffect::None,
+                    NoiseEffect::Low,
+                    NoiseEffect::Low,
+                    NoiseEffect::Moderate,
+                    NoiseEffect::Moderate,
+                    NoiseEffect::High,
+                    NoiseEffect::High,
+                    NoiseEffect::None,
+                ]
+        );
+    }
+
+    #[test]
+    fn recommends_filtering_for_noise() {
+        assert!(NoiseEffect::Moderate.is_reliable());
+        assert!(!NoiseEffect::High.is_reliable());
+        assert!(NoiseEffect::None.recommended_filtering() == FilterStrength::None);
+        assert!(NoiseEffect::Low.recommended_filtering() == FilterStrength::Light);
+        assert!(NoiseEffect::Moderate.recommended_filtering() == FilterStrength::Heavy);
+        assert!(NoiseEffect::High.recommended_filtering() == FilterStrength::Discard);
+    }
+
+    /// Create a driver whose device holds a single touch message carrying the
+    /// given records
+    fn touch_driver(records: &[TouchRecord], config: Config) -> TT21100<mock::Bus, NoIrq> {
+        let (message, length) = touch_message(TouchReport::new(0, records.len() as u8), records);
+
+        TT21100::new_without_irq(mock::Bus::new(&[&message[0..][..length]]), config)
+    }
+
+    #[test]
+    fn validates_against_selected_panel() {
+        let mut driver = touch_driver(&[TouchRecord::new(1, 400, 300)], Config::default());
+        driver.set_panel(PanelProfile::RES_320X240);
+
+        match driver.event() {
+            Ok(event) => assert!(event.touches().eq([TouchRecord::new(1, 319, 239)])),
+            _ => panic!("expected a touch event"),
+        }
+    }
+
+    #[test]
+    fn decodes_hovering_contacts() {
+        let touching = TouchRecord::new(1, 10, 20);
+        let hovering = touching.with_phase(Phase::Hover);
+
+        assert!(touching.phase() == Phase::Touch);
+        assert!(hovering.phase() == Phase::Hover);
+        assert_eq!(touching.into_bytes()[1], 0x81);
+        assert_eq!(hovering.into_bytes()[1], 0x01);
+        assert!(TouchRecord::from_bytes(hovering.into_bytes()).phase() == Phase::Hover);
+    }
+
+    #[test]
+    fn decodes_touch_phases() {
+        let phases = [0, 1, 2, 3].map(TouchPhase::from);
+
+        assert!(
+            phases
+                == [
+                    TouchPhase::None,
+                    TouchPhase::Down,
+                    TouchPhase::Move,
+                    TouchPhase::Up,
+                ]
+        );
+        assert!(phases.map(u8::from) == [0, 1, 2, 3]);
+        assert!(u8::from(TouchPhase::Unknown(7)) == 7);
+        assert!(TouchRecord::new(1, 0, 0).with_event_id(3).touch_phase() == TouchPhase::Up);
+    }
+
+    #[test]
+    fn decodes_touch_types() {
+        let types = [0, 1, 2, 9].map(TouchType::from);
+
+        assert!(
+            types
+                == [
+                    TouchType::Finger,
+                    TouchType::Stylus,
+                    TouchType::Glove,
+                    TouchType::Unknown(9),
+                ]
+        );
+        assert!(types.map(u8::from) == [0, 1, 2, 9]);
+        assert!(TouchRecord::new(1, 0, 0).with_touch_type(1).kind() == TouchType::Stylus);
+        assert!(TouchRecord::new(1, 0, 0).kind() == TouchType::Finger);
+    }
+
+    #[test]
+    fn returns_events_read_before_timeout() {
+        let mut driver = touch_driver(&[TouchRecord::new(1, 10, 20)], Config::default());
+        let mut delay = mock::Delay::default();
+
+        match driver.event_with_timeout(&mut delay, 1_000) {
+            Ok(Some(event)) => assert!(event.touches().eq([TouchRecord::new(1, 10, 20)])),
+            _ => panic!("expected a touch event"),
+        }
+        assert_eq!(delay.elapsed_ns, 0);
+    }
+
+    #[test]
+    fn reads_device_at_each_poll_without_interrupt_line() {
+        let (message, length) =
+            touch_message(TouchReport::new(0, 1), &[TouchRecord::new(1, 10, 20)]);
+        let bus = mock::Bus::new(&[&[2, 0], &[2, 0], &message[0..][..length]]);
+        let mut driver = TT21100::new_without_irq(bus, Config::default());
+        let mut delay = mock::Delay::default();
+
+        assert!(matches!(driver.data_available(), Ok(true)));
+        assert!(driver.i2c.transactions.is_empty());
+
+        assert!(matches!(
+            driver.event_with_timeout(&mut delay, 5_000),
+            Ok(Some(_))
+        ));
+        assert_eq!(driver.i2c.transactions.len(), 3);
+        assert_eq!(delay.elapsed_ns, 2_000_000);
+    }
+
+    #[test]
+    fn gives_up_once_timeout_elapses() {
+        let config = Config {
+            poll_interval_us: 300,
+            ..Config::default()
+        };
+        let mut driver = TT21100::new_without_irq(mock::Bus::default(), config);
+        let mut delay = mock::Delay::default();
+
+        assert!(matches!(
+            driver.event_with_timeout(&mut delay, 1_000),
+            Ok(None)
+        ));
+        assert_eq!(delay.elapsed_ns, 1_000_000);
+    }
+
+    #[test]
+    fn invokes_idle_hook_between_polls() {
+        static IDLED: AtomicUsize = AtomicUsize::new(0);
+        fn idle() {
+            IDLED.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let config = Config {
+            poll_interval_us: 250,
+            ..Config::default()
+        };
+        let mut driver = TT21100::new_without_irq(mock::Bus::default(), config);
+        driver.set_idle_hook(Some(idle));
+
+        assert!(matches!(
+            driver.event_with_timeout(&mut mock::Delay::default(), 1_000),
+            Ok(None)
+        ));
+        assert_eq!(IDLED.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn samples_device_at_poll_interval() {
+        let (message, length) =
+            touch_message(TouchReport::new(0, 1), &[TouchRecord::new(1, 10, 20)]);
+        let bus = mock::Bus::new(&[&[2, 0], &[2, 0], &message[0..][..length]]);
+        let mut driver = TT21100::new_without_irq(bus, Config::default());
+        let mut delay = mock::Delay::default();
+
+        match driver.poll_event(&mut delay) {
+            Ok(event) => assert!(event.touches().eq([TouchRecord::new(1, 10, 20)])),
+            _ => panic!("expected a touch event"),
+        }
+        assert_eq!(delay.elapsed_ns, 2_000_000);
+    }
+
+    #[test]
+    fn backs_off_polling_while_idle() {
+        let (message, length) =
+            touch_message(TouchReport::new(0, 1), &[TouchRecord::new(1, 10, 20)]);
+        let message = &message[0..][..length];
+        let empty: &[u8] = &[2, 0];
+        let bus = mock::Bus::new(&[empty, empty, empty, empty, message, empty, message]);
+        let config = Config {
+            poll_interval_us: 1_000,
+            idle_poll_interval_us: 50_000,
+            idle_after_us: 2_000,
+            ..Config::default()
+        };
+        let mut driver = TT21100::new_without_irq(bus, config);
+        let mut delay = mock::Delay::default();
+
+        assert!(driver.poll_event(&mut delay).is_ok());
+        assert_eq!(delay.elapsed_ns, 102_000_000);
+
+        // Reading an event resumes sampling at the regular interval
+        assert!(driver.poll_event(&mut delay).is_ok());
+        assert_eq!(delay.elapsed_ns, 103_000_000);
+    }
+
+    #[test]
+    fn returns_injected_events_ahead_of_device() {
+        let config = Config::for_panel(PanelProfile::RES_480X320);
+        let mut driver = touch_driver(&[TouchRecord::new(1, 10, 20)], config);
+        let injected = Event::touch(0, &[TouchRecord::new(2, 1_000, 30)]);
+
+        assert!(driver.inject(Event::touch(0, &[])).is_none());
+        assert!(driver.inject(injected).is_some());
+        assert!(matches!(driver.data_available(), Ok(true)));
+
+        // Injected events are validated as though read from the device
+        match driver.event() {
+            Ok(event) => assert!(event.touches().eq([TouchRecord::new(2, 479, 30)])),
+            _ => panic!("expected the injected event"),
+        }
+        assert!(driver.i2c.transactions.is_empty());
+
+        match driver.event() {
+            Ok(event) => assert!(event.touches().eq([TouchRecord::new(1, 10, 20)])),
+            _ => panic!("expected a touch event"),
+        }
+    }
+
+    #[test]
+    fn reads_messages_in_a_single_transaction() {
+        let mut driver = touch_driver(&[TouchRecord::new(1, 10, 20)], Config::default());
+
+        assert!(driver.event().is_ok());
+        assert_eq!(driver.i2c.transactions, [[mock::Op::Read(MAX_MESSAGE_LEN)]]);
+    }
+
+    #[test]
+    fn reads_length_without_writing() {
+        let mut driver = TT21100::new_without_irq(mock::Bus::default(), Config::default());
+
+        assert!(matches!(driver.has_pending(), Ok(false)));
+        assert_eq!(driver.i2c.transactions, [[mock::Op::Read(2)]]);
+    }
+
+    #[test]
+    fn reports_pending_messages_and_injected_events() {
+        let mut driver = touch_driver(&[TouchRecord::new(1, 10, 20)], Config::default());
+
+        assert!(matches!(driver.has_pending(), Ok(true)));
+        assert!(matches!(driver.has_pending(), Ok(false)));
+
+        driver.inject(Event::touch(0, &[TouchRecord::new(2, 10, 20)]));
+        assert!(matches!(driver.has_pending(), Ok(true)));
+        assert_eq!(driver.i2c.transactions.len(), 2);
+    }
+
+    #[test]
+    fn flushes_queued_and_injected_events() {
+        let (message, length) = touch_message(TouchReport::new(0, 0), &[]);
+        let malformed = [5, 0, 0xff, 0xff, 0xff];
+        let mut driver = TT21100::new_without_irq(
+            mock::Bus::new(&[&message[0..][..length], &malformed]),
+            Config::default(),
+        );
+        driver.inject(Event::touch(0, &[TouchRecord::new(1, 10, 20)]));
+
+        assert!(matches!(driver.flush_pending(), Ok(2)));
+        assert_eq!(driver.i2c.transactions.len(), 3);
+        assert!(matches!(driver.event(), Err(Error::NoDataAvailable)));
+    }
+
+    #[test]
+    fn verifies_first_message_without_initializing() {
+        let config = Config {
+            parsing: ParseMode::Lenient,
+            ..Config::default()
+        };
+        let (message, length) = touch_message(TouchReport::new(0, 0), &[]);
+        let malformed = [5, 0, ReportId::TOUCH, 0, 0];
+        let mut driver = TT21100::new_without_irq(
+            mock::Bus::new(&[&malformed, &message[0..][..length], &malformed]),
+            config,
+        );
+
+        assert!(matches!(driver.event(), Err(Error::InvalidMessageLen(5))));
+        assert!(!driver.is_initialized());
+        assert!(driver.event().is_ok());
+        assert!(driver.is_initialized());
+        assert!(matches!(driver.event(), Err(Error::NoDataAvailable)));
+    }
+
+    #[test]
+    fn performs_handshake_when_initialized() {
+        let mut driver = TT21100::new_without_irq(mock::Bus::default(), Config::default());
+        assert!(driver.i2c.transactions.is_empty());
+
+        assert!(driver.init().is_ok());
+        assert_eq!(driver.i2c.transactions, [[mock::Op::Read(2)]]);
+    }
+
+    #[test]
+    fn fails_to_initialize_unresponsive_device() {
+        let (message, length) = touch_message(TouchReport::new(0, 0), &[]);
+        let messages = [&message[0..][..length]; 5];
+        let mut driver = TT21100::new_without_irq(mock::Bus::new(&messages), Config::default());
+
+        assert!(matches!(driver.init(), Err(Error::InvalidMessageLen(7))));
+        assert_eq!(driver.i2c.transactions.len(), 5);
+    }
+
+    #[test]
+    fn reinitializes_discarding_injected_events() {
+        let mut driver = TT21100::new_without_irq(mock::Bus::default(), Config::default());
+        assert!(!driver.is_initialized());
+
+        assert!(driver.init().is_ok());
+        assert!(driver.is_initialized());
+
+        driver.inject(Event::touch(0, &[TouchRecord::new(1, 10, 20)]));
+        assert!(driver.reinit().is_ok());
+        assert!(driver.is_initialized());
+        assert!(matches!(driver.event(), Err(Error::NoDataAvailable)));
+    }
+
+    #[test]
+    fn sleeps_and_wakes_device() {
+        let mut driver = TT21100::new_without_irq(mock::Bus::default(), Config::default());
+
+        assert!(driver.sleep().is_ok());
+        assert!(driver.is_asleep());
+        assert!(driver.wake().is_ok());
+        assert!(!driver.is_asleep());
+        assert!(driver.is_initialized());
+
+        assert_eq!(
+            driver.i2c.transactions,
+            [
+                [mock::Op::Write(SLEEP_COMMAND.to_vec())],
+                [mock::Op::Write(WAKE_COMMAND.to_vec())],
+                [mock::Op::Read(MAX_MESSAGE_LEN)],
+                [mock::Op::Read(2)],
+            ]
+        );
+    }
+
+    #[cfg(feature = "sleep-on-drop")]
+    #[test]
+    fn sleeps_when_dropped_awake() {
+        static SLEPT: AtomicUsize = AtomicUsize::new(0);
+
+        fn sleep(_: &mut mock::Bus, _: u8) {
+            SLEPT.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let mut awake = TT21100::new_without_irq(mock::Bus::default(), Config::default());
+        awake.sleep = sleep;
+        drop(awake);
+        assert_eq!(SLEPT.load(Ordering::Relaxed), 1);
+
+        let mut asleep = TT21100::new_without_irq(mock::Bus::default(), Config::default());
+        asleep.sleep = sleep;
+        assert!(asleep.sleep().is_ok());
+        drop(asleep);
+        assert_eq!(SLEPT.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn addresses_configured_device() {
+        let mut driver = TT21100::new_with_address(mock::Bus::default(), NoIrq, 0x48);
+        assert_eq!(driver.config().address, 0x48);
+
+        assert!(driver.init().is_ok());
+        assert!(matches!(driver.event(), Err(Error::NoDataAvailable)));
+        assert_eq!(driver.i2c.addresses, [0x48, 0x48]);
+
+        let mut driver = TT21100::new_without_irq(mock::Bus::default(), Config::default());
+        assert!(driver.init().is_ok());
+        assert_eq!(driver.i2c.addresses, [DEFAULT_ADDRESS]);
+    }
+
+    #[test]
+    fn iterates_over_queued_events() {
+        let (first, first_len) =
+            touch_message(TouchReport::new(0, 1), &[TouchRecord::new(1, 10, 20)]);
+        let (second, second_len) = touch_message(TouchReport::new(10, 0), &[]);
+        let bus = mock::Bus::new(&[&first[0..][..first_len], &second[0..][..second_len]]);
+        let mut driver = TT21100::new_without_irq(bus, Config::default());
+
+        let mut events = driver.events();
+        assert!(matches!(events.next(), Some(Ok(event)) if event.touches().count() == 1));
+        assert!(matches!(events.next(), Some(Ok(event)) if event.touches().count() == 0));
+        assert!(events.next().is_none());
+        assert!(events.next().is_none());
+    }
+
+    #[test]
+    fn stops_iterating_after_errors() {
+        let mut driver = TT21100::new_without_irq(
+            mock::Bus::new(&[&[5, 0, ReportId::TOUCH, 0, 0], &[2, 0]]),
+            Config::default(),
+        );
+
+        let mut events = driver.events();
+        assert!(matches!(
+            events.next(),
+            Some(Err(Error::InvalidMessageLen(5)))
+        ));
+        assert!(events.next().is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn round_trips_events_through_serde() {
+        let events = [
+            Event::touch(
+                100,
+                &[TouchRecord::new(1, 10, 20), TouchRecord::new(2, 30, 40)],
+            ),
+            Event::Button(ButtonRecord::new(200, 0b0101)),
+            Event::PalmRejected(TouchReport::new(300, 1)),
+        ];
+
+        for event in events {
+            let json = serde_json::to_string(&event).unwrap();
+            assert!(serde_json::from_str::<Event>(&json).unwrap() == event);
+        }
+    }
+
+    fn reset_driver() -> TT21100<mock::Bus, NoIrq, mock::Delay, mock::ResetPin> {
+        TT21100::new_with_reset(
+            mock::Bus::default(),
+            NoIrq,
+            mock::ResetPin::default(),
+            mock::Delay::default(),
+            Config::default(),
+        )
+    }
+
+    #[test]
+    fn pulses_reset_pin_when_initialized() {
+        let mut driver = reset_driver();
+
+        assert!(driver.init().is_ok());
+        assert_eq!(driver.reset.as_ref().unwrap().levels, [false, true]);
+        assert_eq!(
+            driver.delay.elapsed_ns,
+            u64::from(RESET_PULSE_US + RESET_RECOVERY_US) * 1_000
+        );
+        assert!(driver.is_initialized());
+    }
+
+    #[test]
+    fn hard_resets_and_wakes_device() {
+        let mut driver = reset_driver();
+        assert!(driver.sleep().is_ok());
+
+        assert!(driver.hard_reset().is_ok());
+        assert_eq!(driver.reset.as_ref().unwrap().levels, [false, true]);
+        assert!(!driver.is_asleep());
+        assert!(driver.is_initialized());
+    }
+
+    #[test]
+    fn deep_sleeps_like_sleep_without_reset_pin() {
+        let mut driver = TT21100::new_without_irq(mock::Bus::default(), Config::default());
+
+        assert!(driver.deep_sleep().is_ok());
+        assert!(driver.is_asleep());
+        assert!(!driver.state.held_in_reset);
+        assert_eq!(
+            driver.i2c.transactions,
+            [[mock::Op::Write(SLEEP_COMMAND.to_vec())]]
+        );
+
+        assert!(driver.wake().is_ok());
+        assert_eq!(
+            driver.i2c.transactions[1],
+            [mock::Op::Write(WAKE_COMMAND.to_vec())]
+        );
+    }
+
+    #[test]
+    fn holds_device_in_reset_during_deep_sleep() {
+        let mut driver = reset_driver();
+
+        assert!(driver.deep_sleep().is_ok());
+        assert!(driver.is_asleep());
+        assert_eq!(driver.reset.as_ref().unwrap().levels, [false]);
+        assert_eq!(
+            driver.i2c.transactions,
+            [[mock::Op::Write(SLEEP_COMMAND.to_vec())]]
+        );
+
+        assert!(driver.wake().is_ok());
+        assert!(!driver.is_asleep());
+        assert!(!driver.state.held_in_reset);
+        assert_eq!(driver.reset.as_ref().unwrap().levels, [false, true]);
+        assert_eq!(
+            driver.delay.elapsed_ns,
+            u64::from(RESET_RECOVERY_US) * 1_000
+        );
+        assert!(!driver
+            .i2c
+            .transactions
+            .iter()
+            .any(|ops| ops[..] == [mock::Op::Write(WAKE_COMMAND.to_vec())]));
+    }
+
+    #[test]
+    fn arms_wake_gesture_before_sleeping() {
+        let (message, length) =
+            touch_message(TouchReport::new(0, 1), &[TouchRecord::new(1, 10, 20)]);
+        let response = mock::response(command::ENTER_EASY_WAKE, &[]);
+        let bus = mock::Bus::new(&[&message[0..][..length], &response]);
+        let mut driver =
+            TT21100::new_with_delay(bus, NoIrq, mock::Delay::default(), Config::default());
+
+        assert!(driver.sleep_until(WakeGesture::DoubleTap).is_ok());
+        assert!(driver.is_asleep());
+
+        let command = command::Command::new(command::ENTER_EASY_WAKE, &[0x01]).unwrap();
+        assert_eq!(
+            driver.i2c.transactions[0],
+            [mock::Op::Write(command.as_bytes().to_vec())]
+        );
+        // The touch event read before the acknowledgement is discarded
+        assert_eq!(driver.i2c.transactions.len(), 3);
+        assert_eq!(
+            driver.delay.elapsed_ns,
+            u64::from(RESPONSE_INTERVAL_US) * 1_000
+        );
+        assert!(matches!(driver.event(), Err(Error::NoDataAvailable)));
+    }
+
+    #[test]
+    fn stays_awake_when_wake_gesture_is_not_acknowledged() {
+        let mut driver = TT21100::new_with_delay(
+            mock::Bus::default(),
+            NoIrq,
+            mock::Delay::default(),
+            Config::default(),
+        );
+
+        let result = driver.sleep_until(WakeGesture::Touch);
+
+        assert!(matches!(result, Err(Error::NoResponse)));
+        assert!(!driver.is_asleep());
+        assert_eq!(driver.i2c.transactions.len(), 1 + RESPONSE_ATTEMPTS);
+        assert_eq!(
+            driver.delay.elapsed_ns,
+            (RESPONSE_ATTEMPTS as u64) * u64::from(RESPONSE_INTERVAL_US) * 1_000
+        );
+    }
+
+    #[cfg(feature = "report-processing")]
+    #[test]
+    fn counts_reports_missed_between_reads() {
+        let records = [TouchRecord::new(1, 10, 20)];
+        let report = |report_counter| TouchReport {
+            report_counter,
+            ..TouchReport::new(0, 1)
+        };
+        let (first, length) = touch_message(report(0), &records);
+        let (second, _) = touch_message(report(1), &records);
+        let (third, _) = touch_message(report(3), &records);
+        let bus = mock::Bus::new(&[
+            &first[0..][..length],
+            &second[0..][..length],
+            &third[0..][..length],
+        ]);
+        let mut driver = TT21100::new_without_irq(bus, Config::default());
+
+        for _ in 0..3 {
+            assert!(driver.event().is_ok());
+        }
+        assert_eq!(driver.missed_reports(), 1);
+    }
+
+    #[cfg(feature = "report-processing")]
+    #[test]
+    fn reports_rejected_palms_in_place_of_touches() {
+        let palm = TouchReport {
+            large_object: 1,
+            ..TouchReport::new(0, 1)
+        };
+        let (message, length) = touch_message(palm, &[TouchRecord::new(1, 10, 20)]);
+        let config = Config {
+            reject_palms: true,
+            ..Config::default()
+        };
+        let mut driver =
+            TT21100::new_without_irq(mock::Bus::new(&[&message[0..][..length]]), config);
+
+        match driver.event() {
+            Ok(event @ Event::PalmRejected(_)) => assert_eq!(event.touches().count(), 0),
+            _ => panic!("expected a rejected palm"),
+        }
+        assert_eq!(driver.rejected_records(), 1);
+    }
+
+    #[cfg(feature = "report-processing")]
+    #[test]
+    fn skips_noisy_reports_in_favour_of_next() {
+        let noisy = TouchReport {
+            noise_effect: 3,
+            ..TouchReport::new(0, 1)
+        };
+        let (first, length) = touch_message(noisy, &[TouchRecord::new(1, 10, 20)]);
+        let (second, _) = touch_message(TouchReport::new(0, 1), &[TouchRecord::new(1, 30, 40)]);
+        let config = Config {
+            noise_threshold: Some(NoiseEffect::Moderate),
+            ..Config::default()
+        };
+        let bus = mock::Bus::new(&[&first[0..][..length], &second[0..][..length]]);
+        let mut driver = TT21100::new_without_irq(bus, config);
+
+        match driver.event() {
+            Ok(event) => assert!(event.touches().eq([TouchRecord::new(1, 30, 40)])),
+            _ => panic!("expected a touch event"),
+        }
+        assert_eq!(driver.noisy_reports(), 1);
+    }
+
+    #[test]
+    fn waits_between_transactions() {
+        let config = Config {
+            transaction_gap_us: 500,
+            ..Config::default()
+        };
+        let mut driver =
+            TT21100::new_with_delay(mock::Bus::default(), NoIrq, mock::Delay::default(), config);
+
+        assert!(matches!(driver.has_pending(), Ok(false)));
+        assert_eq!(driver.delay.elapsed_ns, 0);
+        assert!(matches!(driver.has_pending(), Ok(false)));
+        assert!(matches!(driver.event(), Err(Error::NoDataAvailable)));
+        assert_eq!(driver.delay.elapsed_ns, 1_000_000);
+    }
+
+    #[test]
+    fn builds_reports_and_records() {
+        let report = TouchReport::new(100, 2);
+        assert_eq!(report.data_len, 27);
+        assert_eq!(report.report_id, ReportId::TOUCH);
+        assert_eq!(report.record_num, 2);
+
+        let record = TouchRecord::new(3, 10, 20)
+            .with_touch_type(1)
+            .with_phase(Phase::Hover)
+            .with_event_id(2)
+            .with_pressure(40)
+            .with_major_axis_length(12)
+            .with_orientation(5);
+        assert!(record.kind() == TouchType::Stylus);
+        assert!(record.phase() == Phase::Hover);
+        assert!(record.touch_phase() == TouchPhase::Move);
+        assert!(record.position() == geom::Point::new(10, 20));
+        assert_eq!(
+            (
+                record.pressure,
+                record.major_axis_length,
+                record.orientation
+            ),
+            (40, 12, 5)
+        );
+
+        let button = ButtonRecord::new(100, 0b0011);
+        assert_eq!((button.length, button.report_id), (14, ReportId::BUTTON));
+        assert!(ButtonRecord::default() == ButtonRecord::new(0, 0));
+    }
+
+    #[test]
+    fn round_trips_reports_and_records_through_bytes() {
+        let report = [27, 0, 1, 0x10, 0x27, 0x02, 0x00];
+        assert!(TouchReport::from_bytes(report) == TouchReport::new(10_000, 2));
+        assert_eq!(TouchReport::new(10_000, 2).into_bytes(), report);
+
+        let bytes = [0x00, 0xC0, 0x40, 0x01, 0xF0, 0x00, 0x20, 0x08, 0x00, 0x00];
+        let record = TouchRecord::new(0, 320, 240)
+            .with_event_id(2)
+            .with_pressure(32)
+            .with_major_axis_length(8);
+        assert!(TouchRecord::from_bytes(bytes) == record);
+        assert_eq!(record.into_bytes(), bytes);
+
+        let button = ButtonRecord::new(10_000, 1);
+        assert!(ButtonRecord::from_bytes(button.into_bytes()) == button);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn drains_queued_events() {
+        let first = touch_message(TouchReport::new(0, 1), &[TouchRecord::new(1, 10, 20)]);
+        let second = touch_message(TouchReport::new(1, 1), &[TouchRecord::new(1, 15, 20)]);
+        let bus = mock::Bus::new(&[&first.0[0..][..first.1], &second.0[0..][..second.1]]);
+        let mut driver = TT21100::new_without_irq(bus, Config::default());
+
+        let events = driver.drain().unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(events[1].touches().eq([TouchRecord::new(1, 15, 20)]));
+        assert!(driver.drain().unwrap().is_empty());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn describes_errors() {
+        let error: Box<dyn std::error::Error> = Box::new(Error::<()>::UnknownReport(9));
+
+        assert_eq!(error.to_string(), "unknown report ID: 9");
+        assert_eq!(Error::BusError("nack").to_string(), "bus error: \"nack\"");
+    }
+
+    #[test]
+    fn applies_new_configuration_from_next_event() {
+        let record = TouchRecord::new(1, 400, 300);
+        let (message, length) = touch_message(TouchReport::new(0, 1), &[record]);
+        let bus = mock::Bus::new(&[&message[0..][..length], &message[0..][..length]]);
+        let mut driver = TT21100::new_without_irq(bus, Config::default());
+
+        match driver.event() {
+            Ok(event) => assert!(event.touches().eq([record])),
+            _ => panic!("expected a touch event"),
+        }
+
+        driver.set_config(Config::for_panel(PanelProfile::RES_320X240));
+        assert!(driver.config().panel == PanelProfile::RES_320X240);
+        match driver.event() {
+            Ok(event) => assert!(event.touches().eq([TouchRecord::new(1, 319, 239)])),
+            _ => panic!("expected a touch event"),
+        }
+    }
 }
@@ -0,0 +1,71 @@
+//! Integer math helpers
+
+/// Integer square root, rounded down
+pub(crate) fn isqrt(value: u32) -> u32 {
+    if value < 2 {
+        return value;
+    }
+
+    // Newton's method, starting from an estimate which is always too large
+    let mut x = 1 << ((32 - value.leading_zeros() + 1) / 2);
+    loop {
+        let y = (x + value / x) / 2;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+/// Angle of the vector `(dx, dy)` from the positive X axis, in hundredths of a
+/// degree, in the range `-18000..=18000`
+///
+/// Accurate to within roughly a quarter of a degree.
+pub(crate) fn atan2_cdeg(dy: i32, dx: i32) -> i32 {
+    if dx == 0 && dy == 0 {
+        return 0;
+    }
+
+    let (ax, ay) = (i64::from(dx).abs(), i64::from(dy).abs());
+
+    // atan(r) ≈ (π/4)·r + 0.273·r·(1 - r), for 0 <= r <= 1 (r in Q16)
+    let atan = |r: i64| (4_500 * r + ((1_564 * r * ((1 << 16) - r)) >> 16)) >> 16;
+    let octant = if ax >= ay {
+        atan((ay << 16) / ax)
+    } else {
+        9_000 - atan((ax << 16) / ay)
+    };
+
+    let angle = match (dx >= 0, dy >= 0) {
+        (true, true) => octant,
+        (false, true) => 18_000 - octant,
+        (false, false) => octant - 18_000,
+        (true, false) => -octant,
+    };
+
+    angle as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_square_roots_down() {
+        let roots = [0, 1, 2, 3, 4, 99, 100, u32::MAX].map(isqrt);
+
+        assert_eq!(roots, [0, 1, 1, 1, 2, 9, 10, 65_535]);
+    }
+
+    #[test]
+    fn approximates_angles() {
+        let angles = [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0)]
+            .map(|(dy, dx)| atan2_cdeg(dy, dx));
+        let expected = [0, 4_500, 9_000, 13_500, 18_000, -13_500, -9_000];
+
+        for (angle, expected) in angles.iter().zip(expected) {
+            assert!((angle - expected).abs() <= 25, "{angle} != {expected}");
+        }
+        assert_eq!(atan2_cdeg(0, 0), 0);
+    }
+}
@@ -0,0 +1,249 @@
+//! Test doubles standing in for the peripherals used by the driver
+
+extern crate std;
+
+use core::convert::Infallible;
+use std::{collections::VecDeque, vec, vec::Vec};
+
+use embedded_hal::{
+    delay::DelayNs,
+    i2c::{ErrorType, I2c, Operation},
+};
+
+use crate::command::crc16;
+
+/// An operation performed on the bus
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Op {
+    /// Bytes written to the device
+    Write(Vec<u8>),
+    /// Number of bytes read from the device
+    Read(usize),
+}
+
+/// A bus to which a device holding a queue of messages is attached
+///
+/// Each read returns as much of the oldest message as fits in the buffer,
+/// removing it from the queue, or an empty message once the queue is empty.
+#[derive(Debug, Default)]
+pub(crate) struct Bus {
+    /// Messages waiting to be read, oldest first
+    pub(crate) messages: VecDeque<Vec<u8>>,
+    /// Operations making up each transaction performed, oldest first
+    pub(crate) transactions: Vec<Vec<Op>>,
+    /// Address targeted by each transaction performed, oldest first
+    pub(crate) addresses: Vec<u8>,
+}
+
+impl Bus {
+    /// Create a bus whose device holds the given messages
+    pub(crate) fn new(messages: &[&[u8]]) -> Self {
+        Self {
+            messages: messages.iter().map(|message| message.to_vec()).collect(),
+            transactions: Vec::new(),
+            addresses: Vec::new(),
+        }
+    }
+}
+
+impl ErrorType for Bus {
+    type Error = Infallible;
+}
+
+impl I2c for Bus {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let mut transaction = Vec::new();
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => transaction.push(Op::Write(bytes.to_vec())),
+                Operation::Read(buffer) => {
+                    let message = self.messages.pop_front().unwrap_or_else(|| vec![2, 0]);
+                    let len = buffer.len().min(message.len());
+                    buffer.fill(0);
+                    buffer[0..][..len].copy_from_slice(&message[0..][..len]);
+                    transaction.push(Op::Read(buffer.len()));
+                }
+            }
+        }
+        self.transactions.push(transaction);
+        self.addresses.push(address);
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::i2c::I2c for Bus {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        I2c::transaction(self, address, operations)
+    }
+}
+
+/// Serialize the response to the command with the given code, carrying the
+/// given data
+pub(crate) fn response(code: u8, data: &[u8]) -> Vec<u8> {
+    let len = 7 + data.len();
+
+    let mut response = vec![0; len];
+    response[0..2].copy_from_slice(&(len as u16).to_le_bytes());
+    response[2] = 0x1F;
+    response[4] = code;
+    response[5..][..data.len()].copy_from_slice(data);
+
+    let crc = crc16(&response[0..][..len - 2]);
+    response[len - 2..].copy_from_slice(&crc.to_be_bytes());
+
+    response
+}
+
+/// A delay which records how long it was asked to wait, without waiting
+#[derive(Debug, Default)]
+pub(crate) struct Delay {
+    /// Total time waited, in nanoseconds
+    pub(crate) elapsed_ns: u64,
+}
+
+impl DelayNs for Delay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.elapsed_ns += u64::from(ns);
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::delay::DelayNs for Delay {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.elapsed_ns += u64::from(ns);
+    }
+}
+
+/// A reset pin which records each level it is driven to
+#[derive(Debug, Default)]
+pub(crate) struct ResetPin {
+    /// Levels the pin was driven to, oldest first; `true` when driven high
+    pub(crate) levels: Vec<bool>,
+}
+
+impl embedded_hal::digital::ErrorType for ResetPin {
+    type Error = Infallible;
+}
+
+impl embedded_hal::digital::OutputPin for ResetPin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.levels.push(false);
+
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.levels.push(true);
+
+        Ok(())
+    }
+}
+
+/// A wait performed on a [Pin]
+#[cfg(feature = "async")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PinWait {
+    High,
+    Low,
+    RisingEdge,
+    FallingEdge,
+    AnyEdge,
+}
+
+/// An interrupt pin whose waits complete at once, leaving it at the level
+/// waited for
+#[cfg(feature = "async")]
+#[derive(Debug, Default)]
+pub(crate) struct Pin {
+    /// Is the pin currently held low?
+    pub(crate) low: bool,
+    /// Waits performed on the pin, oldest first
+    pub(crate) waits: Vec<PinWait>,
+}
+
+#[cfg(feature = "async")]
+impl Pin {
+    fn wait(&mut self, wait: PinWait, low: bool) -> Result<(), Infallible> {
+        self.waits.push(wait);
+        self.low = low;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal::digital::ErrorType for Pin {
+    type Error = Infallible;
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal::digital::InputPin for Pin {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(!self.low)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.low)
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::digital::Wait for Pin {
+    async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+        self.wait(PinWait::High, false)
+    }
+
+    async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+        self.wait(PinWait::Low, true)
+    }
+
+    async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait(PinWait::RisingEdge, false)
+    }
+
+    async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+        self.wait(PinWait::FallingEdge, true)
+    }
+
+    async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+        let low = !self.low;
+        self.wait(PinWait::AnyEdge, low)
+    }
+}
+
+/// Poll a future which is expected to complete without ever waiting
+#[cfg(feature = "async")]
+pub(crate) fn block_on<F>(future: F) -> F::Output
+where
+    F: core::future::Future,
+{
+    use core::{
+        pin::pin,
+        ptr,
+        task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+    };
+
+    const VTABLE: RawWakerVTable = RawWakerVTable::new(
+        |_| RawWaker::new(ptr::null(), &VTABLE),
+        |_| {},
+        |_| {},
+        |_| {},
+    );
+
+    // SAFETY: the waker does nothing, so its data pointer is never used
+    let waker = unsafe { Waker::from_raw(RawWaker::new(ptr::null(), &VTABLE)) };
+    match pin!(future).poll(&mut Context::from_waker(&waker)) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("future did not complete"),
+    }
+}
@@ -0,0 +1,295 @@
+//! Recognition of unlock patterns drawn across a grid of nodes
+
+use crate::{
+    geom::{Point, Rect},
+    tracker::TouchEvent,
+};
+
+/// Configuration for a [PatternRecognizer]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatternConfig {
+    /// Region of the panel covered by the grid
+    ///
+    /// The region is divided into equally sized cells, with a node at the
+    /// center of each.
+    pub area: Rect,
+    /// Number of columns of nodes
+    pub columns: u8,
+    /// Number of rows of nodes
+    pub rows: u8,
+    /// Greatest distance from a node at which a contact visits it
+    pub node_radius: u16,
+}
+
+impl PatternConfig {
+    /// Create a new configuration for a 3×3 grid covering the given region,
+    /// with nodes of a radius proportional to the size of each cell
+    pub fn new(area: Rect) -> Self {
+        let cell = (area.width / 3).min(area.height / 3);
+
+        Self {
+            area,
+            columns: 3,
+            rows: 3,
+            node_radius: cell / 3,
+        }
+    }
+
+    /// Position of the node with the given index, counted row by row from the
+    /// top-left of the grid
+    pub fn node_position(&self, index: u8) -> Option<Point> {
+        let columns = u32::from(self.columns);
+        let rows = u32::from(self.rows);
+        let index = u32::from(index);
+        if columns == 0 || index >= columns * rows {
+            return None;
+        }
+
+        let (column, row) = (index % columns, index / columns);
+        let width = u32::from(self.area.width);
+        let height = u32::from(self.area.height);
+        let x = u32::from(self.area.origin.x) + (2 * column + 1) * width / (2 * columns);
+        let y = u32::from(self.area.origin.y) + (2 * row + 1) * height / (2 * rows);
+
+        Some(Point::new(
+            x.min(u32::from(u16::MAX)) as u16,
+            y.min(u32::from(u16::MAX)) as u16,
+        ))
+    }
+
+    /// Index of the node visited by a contact at the given position, if any
+    ///
+    /// Only the first 256 nodes of the grid can be visited.
+    pub fn node_at(&self, position: Point) -> Option<u8> {
+        let radius = u32::from(self.node_radius);
+        let nodes = u16::from(self.columns) * u16::from(self.rows);
+
+        (0..=u8::MAX).take(usize::from(nodes)).find(|&index| {
+            self.node_position(index).map_or(false, |node| {
+                node.distance_squared(position) <= radius * radius
+            })
+        })
+    }
+}
+
+/// A pattern drawn across the grid
+///
+/// Holds the indices of the nodes visited, in the order they were visited,
+/// up to a capacity of `N` nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pattern<const N: usize> {
+    nodes: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Pattern<N> {
+    fn new() -> Self {
+        Self {
+            nodes: [0; N],
+            len: 0,
+        }
+    }
+
+    /// The indices of the nodes visited, in the order they were visited
+    pub fn nodes(&self) -> &[u8] {
+        &self.nodes[0..][..self.len]
+    }
+
+    /// Number of nodes visited
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Have no nodes been visited?
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn contains(&self, node: u8) -> bool {
+        self.nodes().contains(&node)
+    }
+
+    fn push(&mut self, node: u8) {
+        if let Some(slot) = self.nodes.get_mut(self.len) {
+            *slot = node;
+            self.len += 1;
+        }
+    }
+}
+
+/// Recognizes patterns drawn by dragging a contact across a grid of nodes,
+/// as on the lock screen of a phone
+///
+/// Each node may be visited at most once per pattern, and the visited
+/// sequence is reported when the contact is lifted. Only the first contact
+/// placed on the panel draws a pattern; any further contacts are ignored.
+#[derive(Debug, Clone)]
+pub struct PatternRecognizer<const N: usize> {
+    /// Recognizer configuration
+    config: PatternConfig,
+    /// ID of the contact drawing the pattern, along with the pattern so far
+    drawing: Option<(u8, Pattern<N>)>,
+}
+
+impl<const N: usize> PatternRecognizer<N> {
+    /// Create a new recognizer using the provided configuration
+    pub fn new(config: PatternConfig) -> Self {
+        Self {
+            config,
+            drawing: None,
+        }
+    }
+
+    /// Abandon any pattern in progress
+    pub fn reset(&mut self) {
+        self.drawing = None;
+    }
+
+    /// The configuration of the recognizer
+    pub fn config(&self) -> &PatternConfig {
+        &self.config
+    }
+
+    /// Replace the configuration of the recognizer, abandoning any pattern in
+    /// progress
+    pub fn set_config(&mut self, config: PatternConfig) {
+        self.config = config;
+        self.drawing = None;
+    }
+
+    /// The pattern drawn so far, for drawing feedback while a contact is down
+    pub fn in_progress(&self) -> Option<&Pattern<N>> {
+        self.drawing.as_ref().map(|(_, pattern)| pattern)
+    }
+
+    /// Update the recognizer with an event emitted by a
+    /// [Tracker](crate::tracker::Tracker), returning the pattern which was
+    /// drawn once its contact is lifted
+    ///
+    /// Contacts lifted without having visited any node produce no pattern.
+    pub fn update(&mut self, event: &TouchEvent) -> Option<Pattern<N>> {
+        match (*event, self.drawing.as_mut()) {
+            (TouchEvent::Down(record), None) => {
+                let mut pattern = Pattern::new();
+                if let Some(node) = self.config.node_at(Point::from(&record)) {
+                    pattern.push(node);
+                }
+                self.drawing = Some((record.touch_id, pattern));
+
+                None
+            }
+            (TouchEvent::Move(record), Some((touch_id, pattern)))
+                if record.touch_id == *touch_id =>
+            {
+                if let Some(node) = self.config.node_at(Point::from(&record)) {
+                    if !pattern.contains(node) {
+                        pattern.push(node);
+                    }
+                }
+
+                None
+            }
+            (TouchEvent::Up(record, _), Some((touch_id, _))) if record.touch_id == *touch_id => {
+                let (_, pattern) = self.drawing.take()?;
+
+                if pattern.is_empty() {
+                    None
+                } else {
+                    Some(pattern)
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tracker::ContactStats, TouchRecord};
+
+    fn config() -> PatternConfig {
+        PatternConfig::new(Rect::new(Point::new(0, 0), 300, 300))
+    }
+
+    fn down(id: u8, x: u16, y: u16) -> TouchEvent {
+        TouchEvent::Down(TouchRecord::new(id, x, y))
+    }
+
+    fn moved(id: u8, x: u16, y: u16) -> TouchEvent {
+        TouchEvent::Move(TouchRecord::new(id, x, y))
+    }
+
+    fn up(id: u8, x: u16, y: u16) -> TouchEvent {
+        TouchEvent::Up(TouchRecord::new(id, x, y), ContactStats::default())
+    }
+
+    #[test]
+    fn lays_out_nodes_across_grid() {
+        let config = config();
+
+        assert_eq!(config.node_radius, 33);
+        assert_eq!(config.node_position(0), Some(Point::new(50, 50)));
+        assert_eq!(config.node_position(5), Some(Point::new(250, 150)));
+        assert_eq!(config.node_position(9), None);
+
+        assert_eq!(config.node_at(Point::new(150, 170)), Some(4));
+        assert_eq!(config.node_at(Point::new(100, 100)), None);
+    }
+
+    #[test]
+    fn reports_nodes_visited_once_lifted() {
+        let mut recognizer = PatternRecognizer::<9>::new(config());
+
+        assert_eq!(recognizer.update(&down(1, 50, 50)), None);
+        for (x, y) in [(100, 50), (150, 50), (150, 150), (60, 45), (250, 250)] {
+            assert_eq!(recognizer.update(&moved(1, x, y)), None);
+        }
+        assert_eq!(
+            recognizer.in_progress().map(Pattern::nodes),
+            Some(&[0, 1, 4, 8][..])
+        );
+
+        let pattern = recognizer.update(&up(1, 250, 250));
+        assert_eq!(
+            pattern.as_ref().map(Pattern::nodes),
+            Some(&[0, 1, 4, 8][..])
+        );
+        assert!(recognizer.in_progress().is_none());
+    }
+
+    #[test]
+    fn ignores_further_contacts() {
+        let mut recognizer = PatternRecognizer::<9>::new(config());
+
+        recognizer.update(&down(1, 50, 50));
+        recognizer.update(&down(2, 150, 150));
+        recognizer.update(&moved(2, 250, 150));
+        assert_eq!(recognizer.update(&up(2, 250, 150)), None);
+
+        let pattern = recognizer.update(&up(1, 50, 50));
+        assert_eq!(pattern.as_ref().map(Pattern::nodes), Some(&[0][..]));
+    }
+
+    #[test]
+    fn produces_no_pattern_without_visiting_nodes() {
+        let mut recognizer = PatternRecognizer::<9>::new(config());
+
+        recognizer.update(&down(1, 100, 100));
+        recognizer.update(&moved(1, 200, 100));
+
+        assert_eq!(recognizer.update(&up(1, 200, 100)), None);
+    }
+
+    #[test]
+    fn truncates_patterns_at_capacity() {
+        let mut recognizer = PatternRecognizer::<2>::new(config());
+
+        recognizer.update(&down(1, 50, 50));
+        recognizer.update(&moved(1, 150, 50));
+        recognizer.update(&moved(1, 250, 50));
+
+        let pattern = recognizer.update(&up(1, 250, 50));
+        assert_eq!(pattern.as_ref().map(Pattern::len), Some(2));
+    }
+}
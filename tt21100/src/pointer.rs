@@ -0,0 +1,809 @@
+//! Emulation of pointing devices
+
+#[cfg(feature = "gesture")]
+use crate::gesture::{Gesture, TapConfig, TapRecognizer};
+use crate::{
+    geom::Point,
+    time::Instant,
+    tracker::TouchEvent,
+    Event,
+    TouchRecord,
+    Touches,
+    MAX_TOUCHES,
+};
+
+/// Configuration for a [PressureClick]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PressureClickConfig {
+    /// Pressure at or above which a contact clicks down
+    pub press_threshold: u8,
+    /// Pressure at or below which a clicked contact clicks up
+    ///
+    /// Should be lower than `press_threshold`, providing hysteresis so that a
+    /// contact held near the threshold does not repeatedly click.
+    pub release_threshold: u8,
+}
+
+impl Default for PressureClickConfig {
+    fn default() -> Self {
+        Self {
+            press_threshold: 160,
+            release_threshold: 96,
+        }
+    }
+}
+
+/// A change in the state of an emulated button
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Click {
+    /// The button was pressed
+    Down {
+        /// ID of the contact pressing the button
+        touch_id: u8,
+        /// Position of the contact
+        position: Point,
+    },
+    /// The button was released
+    Up {
+        /// ID of the contact which was pressing the button
+        touch_id: u8,
+        /// Last reported position of the contact
+        position: Point,
+    },
+}
+
+/// Iterator over the [Click]s resulting from a single update of a
+/// [PressureClick]
+#[derive(Debug, Clone)]
+pub struct Clicks {
+    clicks: [Option<Click>; 2 * MAX_TOUCHES],
+    len: usize,
+    index: usize,
+}
+
+impl Clicks {
+    fn new() -> Self {
+        Self {
+            clicks: [None; 2 * MAX_TOUCHES],
+            len: 0,
+            index: 0,
+        }
+    }
+
+    fn push(&mut self, click: Click) {
+        if let Some(slot) = self.clicks.get_mut(self.len) {
+            *slot = Some(click);
+            self.len += 1;
+        }
+    }
+}
+
+impl Iterator for Clicks {
+    type Item = Click;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let click = self.clicks.get(self.index).copied().flatten();
+        if click.is_some() {
+            self.index += 1;
+        }
+
+        click
+    }
+}
+
+/// Emulates a button for each contact, pressed while the pressure of the
+/// contact is high
+#[derive(Debug, Clone)]
+pub struct PressureClick {
+    /// Click configuration
+    config: PressureClickConfig,
+    /// ID and last position of each contact which has clicked down
+    pressed: [Option<(u8, Point)>; MAX_TOUCHES],
+}
+
+impl PressureClick {
+    /// Create a new click emulator using the provided configuration
+    pub fn new(config: PressureClickConfig) -> Self {
+        Self {
+            config,
+            pressed: Default::default(),
+        }
+    }
+
+    /// Forget all pressed contacts, without emitting any clicks
+    pub fn reset(&mut self) {
+        self.pressed = Default::default();
+    }
+
+    /// The configuration of the click emulator
+    pub fn config(&self) -> &PressureClickConfig {
+        &self.config
+    }
+
+    /// Replace the configuration of the click emulator, taking effect from
+    /// the next update
+    pub fn set_config(&mut self, config: PressureClickConfig) {
+        self.config = config;
+    }
+
+    /// Update the click emulator with an event read from the device,
+    /// returning the resulting clicks
+    ///
+    /// Contacts which are not present in a touch event are considered to have
+    /// been lifted, releasing their button if it was pressed.
+    pub fn update(&mut self, event: &Event) -> Clicks {
+        let mut clicks = Clicks::new();
+
+        let records = match event {
+            Event::Touch { touches, .. } => *touches,
+            Event::PalmRejected(_) => Touches::new(),
+            Event::Button(_) => return clicks,
+        };
+
+        for slot in self.pressed.iter_mut() {
+            if let Some((touch_id, position)) = *slot {
+                if !records.iter().any(|record| record.touch_id == touch_id) {
+                    clicks.push(Click::Up { touch_id, position });
+                    *slot = None;
+                }
+            }
+        }
+
+        for record in records.iter() {
+            self.update_record(record, &mut clicks);
+        }
+
+        clicks
+    }
+
+    fn update_record(&mut self, record: &TouchRecord, clicks: &mut Clicks) {
+        let touch_id = record.touch_id;
+        let position = Point::from(record);
+
+        let pressed = self
+            .pressed
+            .iter_mut()
+            .flatten()
+            .find(|(id, _)| *id == touch_id);
+        match pressed {
+            Some(pressed) => {
+                pressed.1 = position;
+                if record.pressure <= self.config.release_threshold {
+                    clicks.push(Click::Up { touch_id, position });
+                    self.release(touch_id);
+                }
+            }
+            None if record.pressure >= self.config.press_threshold => {
+                if let Some(slot) = self.pressed.iter_mut().find(|slot| slot.is_none()) {
+                    *slot = Some((touch_id, position));
+                    clicks.push(Click::Down { touch_id, position });
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn release(&mut self, touch_id: u8) {
+        for slot in self.pressed.iter_mut() {
+            if slot.map_or(false, |(id, _)| id == touch_id) {
+                *slot = None;
+            }
+        }
+    }
+}
+
+/// Acceleration curve of a [RelativePointer]
+///
+/// Gains are expressed in hundredths, so a gain of `100` moves the pointer by
+/// one unit for each unit moved on the panel. Below `threshold` the movement is
+/// scaled by `base_gain`; above it the gain increases linearly with speed, up
+/// to `max_gain`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Acceleration {
+    /// Gain applied to slow movements, in hundredths
+    pub base_gain: u32,
+    /// Speed above which movement is accelerated, in units per second
+    pub threshold: u32,
+    /// Increase in gain for each 1000 units per second above `threshold`, in
+    /// hundredths
+    pub factor: u32,
+    /// Greatest gain, in hundredths
+    pub max_gain: u32,
+}
+
+impl Acceleration {
+    /// Move the pointer exactly as the contact moves
+    pub const NONE: Self = Self {
+        base_gain: 100,
+        threshold: 0,
+        factor: 0,
+        max_gain: 100,
+    };
+
+    /// Gain applied to a movement at the given speed, in hundredths
+    pub fn gain(&self, speed: u32) -> u32 {
+        let excess = speed.saturating_sub(self.threshold);
+        let gain = self
+            .base_gain
+            .saturating_add(self.factor.saturating_mul(excess) / 1000);
+
+        gain.min(self.max_gain.max(self.base_gain))
+    }
+}
+
+impl Default for Acceleration {
+    fn default() -> Self {
+        Self {
+            base_gain: 100,
+            threshold: 200,
+            factor: 100,
+            max_gain: 400,
+        }
+    }
+}
+
+/// Configuration for a [RelativePointer]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RelativePointerConfig {
+    /// Acceleration curve applied to movements
+    pub acceleration: Acceleration,
+    /// Taps which click the button, or `None` to disable tap-to-click; only
+    /// available when the `gesture` feature is enabled
+    #[cfg(feature = "gesture")]
+    pub tap_to_click: Option<TapConfig>,
+}
+
+#[cfg_attr(not(feature = "gesture"), allow(clippy::derivable_impls))]
+impl Default for RelativePointerConfig {
+    fn default() -> Self {
+        Self {
+            acceleration: Acceleration::default(),
+            #[cfg(feature = "gesture")]
+            tap_to_click: Some(TapConfig::default()),
+        }
+    }
+}
+
+/// Output of a [RelativePointer]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerEvent {
+    /// The pointer moved by the given distance along each axis
+    Motion {
+        /// Distance moved along the X axis
+        dx: i32,
+        /// Distance moved along the Y axis
+        dy: i32,
+    },
+    /// The button was pressed and released by a tap
+    #[cfg(feature = "gesture")]
+    Click {
+        /// Number of taps in quick succession, as for [Gesture::Tap]
+        count: u8,
+    },
+}
+
+/// Contact currently driving a [RelativePointer]
+#[derive(Debug, Clone, Copy)]
+struct Driver {
+    touch_id: u8,
+    position: Point,
+    time: Instant,
+}
+
+/// Converts the movement of a contact into relative pointer motion, for
+/// trackpad-style use of a panel which does not overlay the display
+///
+/// Only the first contact placed on the panel moves the pointer; further
+/// contacts are ignored until it is lifted.
+#[derive(Debug, Clone)]
+pub struct RelativePointer {
+    /// Pointer configuration
+    config: RelativePointerConfig,
+    /// Recognizer for taps which click the button
+    #[cfg(feature = "gesture")]
+    taps: TapRecognizer,
+    /// Contact moving the pointer, if any
+    driver: Option<Driver>,
+    /// Movement not yet emitted along each axis, in hundredths of a unit
+    residual: (i32, i32),
+}
+
+impl RelativePointer {
+    /// Create a new relative pointer using the provided configuration
+    pub fn new(config: RelativePointerConfig) -> Self {
+        Self {
+            config,
+            #[cfg(feature = "gesture")]
+            taps: TapRecognizer::new(config.tap_to_click.unwrap_or_default()),
+            driver: None,
+            residual: (0, 0),
+        }
+    }
+
+    /// Abandon any movement or tap in progress
+    pub fn reset(&mut self) {
+        #[cfg(feature = "gesture")]
+        self.taps.reset();
+        self.driver = None;
+        self.residual = (0, 0);
+    }
+
+    /// The configuration of the pointer
+    pub fn config(&self) -> &RelativePointerConfig {
+        &self.config
+    }
+
+    /// Replace the configuration of the pointer, taking effect from the next
+    /// update
+    pub fn set_config(&mut self, config: RelativePointerConfig) {
+        self.config = config;
+        #[cfg(feature = "gesture")]
+        self.taps
+            .set_config(config.tap_to_click.unwrap_or_default());
+    }
+
+    /// Update the pointer with an event emitted by a
+    /// [Tracker](crate::tracker::Tracker), returning the resulting pointer
+    /// event, if any
+    pub fn update(&mut self, event: &TouchEvent, now: Instant) -> Option<PointerEvent> {
+        #[cfg(feature = "gesture")]
+        let tap = self.taps.update(event, now);
+
+        match *event {
+            TouchEvent::Down(record) => {
+                if self.driver.is_none() {
+                    self.driver = Some(Driver {
+                        touch_id: record.touch_id,
+                        position: Point::from(&record),
+                        time: now,
+                    });
+                    self.residual = (0, 0);
+                }
+
+                None
+            }
+            TouchEvent::Move(record) => self.motion(&record, now),
+            TouchEvent::Up(record, _) => {
+                if self.driver.map_or(false, |d| d.touch_id == record.touch_id) {
+                    self.driver = None;
+                }
+
+                #[cfg(feature = "gesture")]
+                if let Some(Gesture::Tap { count, .. }) = tap {
+                    if self.config.tap_to_click.is_some() {
+                        return Some(PointerEvent::Click { count });
+                    }
+                }
+
+                None
+            }
+        }
+    }
+
+    fn motion(&mut self, record: &TouchRecord, now: Instant) -> Option<PointerEvent> {
+        let driver = self.driver.as_mut()?;
+        if driver.touch_id != record.touch_id {
+            return None;
+        }
+
+        let position = Point::from(record);
+        let dx = i32::from(position.x) - i32::from(driver.position.x);
+        let dy = i32::from(position.y) - i32::from(driver.position.y);
+        let elapsed = now.millis_since(driver.time).max(1);
+        let speed = driver.position.distance(position).saturating_mul(1000) / elapsed;
+        driver.position = position;
+        driver.time = now;
+
+        let gain = self.config.acceleration.gain(speed).min(i32::MAX as u32) as i32;
+
+        let x = self.residual.0.saturating_add(dx.saturating_mul(gain));
+        let y = self.residual.1.saturating_add(dy.saturating_mul(gain));
+        self.residual = (x % 100, y % 100);
+
+        let (dx, dy) = (x / 100, y / 100);
+        if dx == 0 && dy == 0 {
+            None
+        } else {
+            Some(PointerEvent::Motion { dx, dy })
+        }
+    }
+}
+
+/// Configuration for a [DwellClick]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DwellConfig {
+    /// Greatest distance a contact may move while dwelling
+    pub radius: u16,
+    /// Time a contact must dwell in place to click, in milliseconds
+    pub dwell_ms: u32,
+}
+
+impl Default for DwellConfig {
+    fn default() -> Self {
+        Self {
+            radius: 10,
+            dwell_ms: 1_000,
+        }
+    }
+}
+
+/// Output of a [DwellClick]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DwellEvent {
+    /// A contact has dwelt in place for part of the dwell time
+    Progress {
+        /// Position at which the contact is dwelling
+        position: Point,
+        /// Proportion of the dwell time elapsed, in percent
+        percent: u8,
+    },
+    /// A contact dwelt in place for the full dwell time
+    Click {
+        /// Position at which the contact dwelt
+        position: Point,
+    },
+    /// A contact moved away or was lifted before the dwell time elapsed
+    Cancel {
+        /// Position at which the contact was dwelling
+        position: Point,
+    },
+}
+
+/// State of a contact dwelling in place
+#[derive(Debug, Clone, Copy)]
+struct Dwell {
+    touch_id: u8,
+    /// Position at which the contact began dwelling
+    anchor: Point,
+    /// Time at which the contact began dwelling
+    since: Instant,
+    /// Most recently reported progress, or `None` once the contact has clicked
+    progress: Option<u8>,
+}
+
+/// Synthesizes a click when a contact is held in place for a period of time,
+/// for users who are unable to tap reliably
+///
+/// While a contact dwells, [DwellClick::poll] reports its progress so that a
+/// dwell indicator can be drawn. Once a contact has clicked, it must move
+/// away or be lifted before it can click again. Only the first contact placed
+/// on the panel is considered.
+#[derive(Debug, Clone)]
+pub struct DwellClick {
+    /// Dwell configuration
+    config: DwellConfig,
+    /// Contact currently dwelling, if any
+    dwell: Option<Dwell>,
+}
+
+impl DwellClick {
+    /// Create a new dwell clicker using the provided configuration
+    pub fn new(config: DwellConfig) -> Self {
+        Self {
+            config,
+            dwell: None,
+        }
+    }
+
+    /// Abandon any dwell in progress
+    pub fn reset(&mut self) {
+        self.dwell = None;
+    }
+
+    /// The configuration of the dwell clicker
+    pub fn config(&self) -> &DwellConfig {
+        &self.config
+    }
+
+    /// Replace the configuration of the dwell clicker, taking effect from the
+    /// next update
+    pub fn set_config(&mut self, config: DwellConfig) {
+        self.config = config;
+    }
+
+    /// Update the dwell clicker with an event emitted by a
+    /// [Tracker](crate::tracker::Tracker), returning [DwellEvent::Cancel] if
+    /// it interrupts a dwell in progress
+    pub fn update(&mut self, event: &TouchEvent, now: Instant) -> Option<DwellEvent> {
+        match (*event, self.dwell.as_mut()) {
+            (TouchEvent::Down(record), None) => {
+                self.dwell = Some(Dwell {
+                    touch_id: record.touch_id,
+                    anchor: Point::from(&record),
+                    since: now,
+                    progress: Some(0),
+                });
+
+                None
+            }
+            (TouchEvent::Move(record), Some(dwell)) if record.touch_id == dwell.touch_id => {
+                let position = Point::from(&record);
+                let radius = u32::from(self.config.radius);
+                if dwell.anchor.distance_squared(position) <= radius * radius {
+                    return None;
+                }
+
+                let anchor = dwell.anchor;
+                let cancelled = dwell.progress.is_some();
+                dwell.anchor = position;
+                dwell.since = now;
+                dwell.progress = Some(0);
+
+                if cancelled {
+                    Some(DwellEvent::Cancel { position: anchor })
+                } else {
+                    None
+                }
+            }
+            (TouchEvent::Up(record, _), Some(dwell)) if record.touch_id == dwell.touch_id => {
+                let dwell = *dwell;
+                self.dwell = None;
+
+                dwell.progress.map(|_| DwellEvent::Cancel {
+                    position: dwell.anchor,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Advance any dwell in progress, returning its progress when it changes
+    /// and [DwellEvent::Click] once the dwell time has elapsed
+    ///
+    /// This should be called periodically while a contact is down, as a
+    /// contact held still produces no events.
+    pub fn poll(&mut self, now: Instant) -> Option<DwellEvent> {
+        let dwell_ms = self.config.dwell_ms.max(1);
+        let dwell = self.dwell.as_mut()?;
+        let progress = dwell.progress?;
+
+        let elapsed = now.millis_since(dwell.since);
+        if elapsed >= dwell_ms {
+            dwell.progress = None;
+            return Some(DwellEvent::Click {
+                position: dwell.anchor,
+            });
+        }
+
+        let percent = (u64::from(elapsed) * 100 / u64::from(dwell_ms)) as u8;
+        if percent == progress {
+            return None;
+        }
+        dwell.progress = Some(percent);
+
+        Some(DwellEvent::Progress {
+            position: dwell.anchor,
+            percent,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pressed(touch_id: u8, x: u16, pressure: u8) -> TouchRecord {
+        TouchRecord::new(touch_id, x, 100).with_pressure(pressure)
+    }
+
+    #[test]
+    fn clicks_with_hysteresis() {
+        let mut clicks = PressureClick::new(PressureClickConfig::default());
+        let mut update = |records: &[TouchRecord]| clicks.update(&Event::touch(0, records));
+
+        assert_eq!(update(&[pressed(1, 10, 150)]).count(), 0);
+        assert!(update(&[pressed(1, 10, 160)]).eq([Click::Down {
+            touch_id: 1,
+            position: Point::new(10, 100),
+        }]));
+        assert_eq!(update(&[pressed(1, 12, 100)]).count(), 0);
+        assert!(update(&[pressed(1, 14, 96)]).eq([Click::Up {
+            touch_id: 1,
+            position: Point::new(14, 100),
+        }]));
+        assert_eq!(update(&[pressed(1, 14, 150)]).count(), 0);
+    }
+
+    #[test]
+    fn releases_lifted_contacts() {
+        let mut clicks = PressureClick::new(PressureClickConfig::default());
+
+        clicks.update(&Event::touch(
+            0,
+            &[pressed(1, 10, 200), pressed(2, 50, 200)],
+        ));
+        let released = clicks.update(&Event::touch(0, &[pressed(2, 50, 200)]));
+
+        assert!(released.eq([Click::Up {
+            touch_id: 1,
+            position: Point::new(10, 100),
+        }]));
+    }
+
+    fn update(
+        pointer: &mut RelativePointer,
+        event: TouchEvent,
+        millis: u32,
+    ) -> Option<PointerEvent> {
+        pointer.update(&event, Instant::from_millis(millis))
+    }
+
+    #[test]
+    fn accelerates_fast_movements() {
+        let acceleration = Acceleration::default();
+
+        assert_eq!(acceleration.gain(0), 100);
+        assert_eq!(acceleration.gain(200), 100);
+        assert_eq!(acceleration.gain(1_200), 200);
+        assert_eq!(acceleration.gain(100_000), 400);
+        assert_eq!(Acceleration::NONE.gain(100_000), 100);
+    }
+
+    #[test]
+    #[cfg_attr(not(feature = "gesture"), allow(clippy::needless_update))]
+    fn moves_pointer_relative_to_contact() {
+        let mut pointer = RelativePointer::new(RelativePointerConfig {
+            acceleration: Acceleration {
+                base_gain: 50,
+                ..Acceleration::NONE
+            },
+            ..RelativePointerConfig::default()
+        });
+
+        update(
+            &mut pointer,
+            TouchEvent::Down(TouchRecord::new(1, 100, 100)),
+            0,
+        );
+        assert_eq!(
+            update(
+                &mut pointer,
+                TouchEvent::Move(TouchRecord::new(1, 103, 96)),
+                10
+            ),
+            Some(PointerEvent::Motion { dx: 1, dy: -2 })
+        );
+        // The remaining half unit along the X axis is carried over
+        assert_eq!(
+            update(
+                &mut pointer,
+                TouchEvent::Move(TouchRecord::new(1, 104, 96)),
+                20
+            ),
+            Some(PointerEvent::Motion { dx: 1, dy: 0 })
+        );
+
+        // Further contacts do not move the pointer
+        update(
+            &mut pointer,
+            TouchEvent::Down(TouchRecord::new(2, 50, 50)),
+            30,
+        );
+        assert_eq!(
+            update(
+                &mut pointer,
+                TouchEvent::Move(TouchRecord::new(2, 60, 50)),
+                40
+            ),
+            None
+        );
+    }
+
+    #[cfg(feature = "gesture")]
+    #[test]
+    fn clicks_on_tap() {
+        let mut pointer = RelativePointer::new(RelativePointerConfig::default());
+        let record = TouchRecord::new(1, 100, 100);
+        let stats = crate::tracker::ContactStats {
+            travel: 0,
+            duration_ms: 50,
+        };
+
+        update(&mut pointer, TouchEvent::Down(record), 0);
+        assert_eq!(
+            update(&mut pointer, TouchEvent::Up(record, stats), 50),
+            Some(PointerEvent::Click { count: 1 })
+        );
+
+        pointer.set_config(RelativePointerConfig {
+            tap_to_click: None,
+            ..RelativePointerConfig::default()
+        });
+        update(&mut pointer, TouchEvent::Down(record), 500);
+        assert_eq!(
+            update(&mut pointer, TouchEvent::Up(record, stats), 550),
+            None
+        );
+    }
+
+    #[test]
+    fn clicks_once_dwell_time_elapses() {
+        let mut dwell = DwellClick::new(DwellConfig::default());
+        let position = Point::new(100, 100);
+
+        assert_eq!(dwell.poll(Instant::from_millis(0)), None);
+        dwell.update(
+            &TouchEvent::Down(TouchRecord::new(1, 100, 100)),
+            Instant::from_millis(0),
+        );
+        assert_eq!(dwell.poll(Instant::from_millis(5)), None);
+        assert_eq!(
+            dwell.poll(Instant::from_millis(250)),
+            Some(DwellEvent::Progress {
+                position,
+                percent: 25,
+            })
+        );
+        assert_eq!(
+            dwell.update(
+                &TouchEvent::Move(TouchRecord::new(1, 106, 108)),
+                Instant::from_millis(500)
+            ),
+            None
+        );
+        assert_eq!(
+            dwell.poll(Instant::from_millis(1_000)),
+            Some(DwellEvent::Click { position })
+        );
+        assert_eq!(dwell.poll(Instant::from_millis(2_000)), None);
+
+        // Having clicked, the contact is lifted without cancelling
+        assert_eq!(
+            dwell.update(
+                &TouchEvent::Up(TouchRecord::new(1, 106, 108), Default::default()),
+                Instant::from_millis(2_000)
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn cancels_dwell_when_contact_moves_away() {
+        let mut dwell = DwellClick::new(DwellConfig::default());
+
+        dwell.update(
+            &TouchEvent::Down(TouchRecord::new(1, 100, 100)),
+            Instant::from_millis(0),
+        );
+        assert_eq!(
+            dwell.update(
+                &TouchEvent::Move(TouchRecord::new(1, 120, 100)),
+                Instant::from_millis(500)
+            ),
+            Some(DwellEvent::Cancel {
+                position: Point::new(100, 100),
+            })
+        );
+
+        // The dwell restarts from where the contact moved to
+        assert_eq!(
+            dwell.poll(Instant::from_millis(1_500)),
+            Some(DwellEvent::Click {
+                position: Point::new(120, 100),
+            })
+        );
+    }
+
+    #[test]
+    fn cancels_dwell_when_contact_is_lifted() {
+        let mut dwell = DwellClick::new(DwellConfig::default());
+        let record = TouchRecord::new(1, 100, 100);
+
+        dwell.update(&TouchEvent::Down(record), Instant::from_millis(0));
+        dwell.update(
+            &TouchEvent::Down(TouchRecord::new(2, 50, 50)),
+            Instant::from_millis(100),
+        );
+
+        assert_eq!(
+            dwell.update(
+                &TouchEvent::Up(record, Default::default()),
+                Instant::from_millis(500)
+            ),
+            Some(DwellEvent::Cancel {
+                position: Point::new(100, 100),
+            })
+        );
+        assert_eq!(dwell.poll(Instant::from_millis(1_000)), None);
+    }
+}
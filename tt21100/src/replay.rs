@@ -0,0 +1,264 @@
+//! Replay of recorded traces in place of a physical device
+
+use core::{cell::Cell, convert::Infallible};
+
+use embedded_hal::{
+    digital::{self, InputPin},
+    i2c::{self, I2c, Operation},
+};
+
+#[cfg(feature = "async")]
+use crate::asynch::source::EventSource;
+use crate::trace::{TraceError, TraceReader};
+
+/// Message indicating that no data is available
+const NO_DATA: [u8; 2] = [2, 0];
+
+/// How much of a trace has been released for reading
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+enum Release {
+    /// Frames captured up to and including the given time
+    Until(u32),
+    /// Every frame
+    All,
+}
+
+/// An I²C device which replays the frames of a recorded trace
+///
+/// No frames are available until they have been released using
+/// [ReplayDevice::advance_to] or [ReplayDevice::release_all], so that the
+/// driver may be initialized as it would be with a physical device. Frames in
+/// the trace which carry no data are skipped, and once every released frame
+/// has been read the device reports that no data is available. Malformed
+/// traces end at the first invalid frame.
+///
+/// The bus traits are also implemented for shared references to the device,
+/// allowing frames to be released while the driver holds a reference to it.
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct ReplayDevice<'a> {
+    frames: Cell<TraceReader<'a>>,
+    next: Cell<Option<(u32, &'a [u8])>>,
+    release: Cell<Option<Release>>,
+}
+
+impl<'a> ReplayDevice<'a> {
+    /// Create a new device replaying the given trace
+    pub fn new(trace: &'a [u8]) -> Result<Self, TraceError> {
+        let me = Self {
+            frames: Cell::new(TraceReader::new(trace)?),
+            next: Cell::new(None),
+            release: Cell::new(None),
+        };
+        me.load_next();
+
+        Ok(me)
+    }
+
+    /// Release every frame captured up to and including `timestamp_ms`
+    pub fn advance_to(&self, timestamp_ms: u32) {
+        if self.release.get() != Some(Release::All) {
+            self.release.set(Some(Release::Until(timestamp_ms)));
+        }
+    }
+
+    /// Release every frame in the trace
+    pub fn release_all(&self) {
+        self.release.set(Some(Release::All));
+    }
+
+    /// Has every frame in the trace been read?
+    pub fn is_finished(&self) -> bool {
+        self.next.get().is_none()
+    }
+
+    /// The next released frame, if any
+    fn available(&self) -> Option<&'a [u8]> {
+        let (timestamp_ms, message) = self.next.get()?;
+        match self.release.get()? {
+            Release::Until(until) if timestamp_ms > until => None,
+            _ => Some(message),
+        }
+    }
+
+    fn transfer(&self, operations: &mut [Operation<'_>]) {
+        for operation in operations {
+            if let Operation::Read(buffer) = operation {
+                let message = self.available().unwrap_or(&NO_DATA);
+
+                buffer.fill(0);
+                let len = buffer.len().min(message.len());
+                buffer[0..][..len].copy_from_slice(&message[0..][..len]);
+
+                // The length of a message is read ahead of the message itself,
+                // so only consider the message to have been read once more than
+                // its length has
+                if buffer.len() > NO_DATA.len() && self.available().is_some() {
+                    self.load_next();
+                }
+            }
+        }
+    }
+
+    fn load_next(&self) {
+        let mut frames = self.frames.get();
+        let next = frames
+            .by_ref()
+            .map_while(Result::ok)
+            .find(|frame| frame.message.len() > NO_DATA.len())
+            .map(|frame| (frame.timestamp_ms, frame.message));
+
+        self.frames.set(frames);
+        self.next.set(next);
+    }
+}
+
+impl i2c::ErrorType for &ReplayDevice<'_> {
+    type Error = Infallible;
+}
+
+impl I2c for &ReplayDevice<'_> {
+    fn transaction(
+        &mut self,
+        _address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.transfer(operations);
+
+        Ok(())
+    }
+}
+
+impl i2c::ErrorType for ReplayDevice<'_> {
+    type Error = Infallible;
+}
+
+impl I2c for ReplayDevice<'_> {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        (&*self).transaction(address, operations)
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::i2c::I2c for &ReplayDevice<'_> {
+    async fn transaction(
+        &mut self,
+        _address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.transfer(operations);
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::i2c::I2c for ReplayDevice<'_> {
+    async fn transaction(
+        &mut self,
+        _address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.transfer(operations);
+
+        Ok(())
+    }
+}
+
+/// An interrupt pin which is always asserted, for use alongside a
+/// [ReplayDevice]
+///
+/// With the `async` feature enabled, this is also an event source which is
+/// always ready.
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct Asserted;
+
+impl digital::ErrorType for Asserted {
+    type Error = Infallible;
+}
+
+impl InputPin for Asserted {
+    fn is_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(false)
+    }
+
+    fn is_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+}
+
+#[cfg(feature = "async")]
+impl EventSource for Asserted {
+    type Error = Infallible;
+
+    async fn wait(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        tests::touch_message,
+        trace::TraceWriter,
+        Config,
+        Error,
+        TouchRecord,
+        TouchReport,
+        TT21100,
+    };
+
+    /// Record a trace of a touch at 0ms, an empty frame at 5ms and another
+    /// touch at 10ms
+    fn record(buffer: &mut [u8]) -> &[u8] {
+        let first = touch_message(TouchReport::new(0, 1), &[TouchRecord::new(1, 10, 20)]);
+        let second = touch_message(TouchReport::new(0, 1), &[TouchRecord::new(1, 20, 20)]);
+
+        let mut writer = TraceWriter::new(buffer).unwrap();
+        assert!(writer.record(0, &first.0[0..][..first.1]).is_ok());
+        assert!(writer.record(5, &NO_DATA).is_ok());
+        assert!(writer.record(10, &second.0[0..][..second.1]).is_ok());
+        let len = writer.len();
+
+        &buffer[0..][..len]
+    }
+
+    #[test]
+    fn replays_released_frames() {
+        let mut buffer = [0; 64];
+        let device = ReplayDevice::new(record(&mut buffer)).unwrap();
+        let mut driver = TT21100::new_without_irq(&device, Config::default());
+
+        assert!(matches!(driver.event(), Err(Error::NoDataAvailable)));
+
+        device.advance_to(5);
+        match driver.event() {
+            Ok(event) => assert!(event.touches().eq([TouchRecord::new(1, 10, 20)])),
+            _ => panic!("expected the first touch"),
+        }
+        assert!(matches!(driver.event(), Err(Error::NoDataAvailable)));
+        assert!(!device.is_finished());
+
+        device.release_all();
+        match driver.event() {
+            Ok(event) => assert!(event.touches().eq([TouchRecord::new(1, 20, 20)])),
+            _ => panic!("expected the second touch"),
+        }
+        assert!(device.is_finished());
+        assert!(matches!(driver.event(), Err(Error::NoDataAvailable)));
+    }
+
+    #[test]
+    fn rejects_invalid_traces() {
+        assert!(matches!(
+            ReplayDevice::new(b"TT21\x09"),
+            Err(TraceError::UnsupportedVersion(9))
+        ));
+    }
+}
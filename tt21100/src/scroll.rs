@@ -0,0 +1,618 @@
+//! Conversion of drags into scroll deltas
+//!
+//! When a contact is lifted while moving quickly enough, the scroll continues
+//! as a fling, decelerating at a constant rate until it comes to rest or a new
+//! contact is placed on the panel.
+//!
+//! Drags along an edge of the panel may instead be converted into discrete
+//! wheel ticks using a [Wheel], in place of a rotary encoder.
+
+use crate::{
+    geom::{Point, Rect},
+    math::isqrt,
+    time::Instant,
+    tracker::{History, Sample, TouchEvent},
+};
+
+/// Number of recent positions used to estimate the velocity of a drag
+const VELOCITY_SAMPLES: usize = 4;
+
+/// Time after its last movement within which a contact must be lifted for a
+/// fling to begin, in milliseconds
+const FLING_WINDOW_MS: u32 = 100;
+
+/// Greatest speed of a fling along either axis, in units per second
+const MAX_FLING_SPEED: i32 = 20_000;
+
+/// Restriction of scrolling to a single axis
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AxisLock {
+    /// Scroll freely along both axes
+    Free,
+    /// Lock scrolling to whichever axis the drag initially moves furthest
+    /// along
+    Auto,
+    /// Only scroll horizontally
+    Horizontal,
+    /// Only scroll vertically
+    Vertical,
+}
+
+/// Configuration for a [Scroller]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScrollConfig {
+    /// Distance a contact must move from where it was placed before
+    /// scrolling begins
+    pub slop: u16,
+    /// Restriction of scrolling to a single axis
+    pub axis_lock: AxisLock,
+    /// Speed above which lifting a contact begins a fling, in units per
+    /// second
+    ///
+    /// Set to `u32::MAX` to disable flinging.
+    pub fling_threshold: u32,
+    /// Rate at which a fling decelerates, in units per second squared
+    pub fling_deceleration: u32,
+}
+
+impl Default for ScrollConfig {
+    fn default() -> Self {
+        Self {
+            slop: 8,
+            axis_lock: AxisLock::Auto,
+            fling_threshold: 300,
+            fling_deceleration: 1_500,
+        }
+    }
+}
+
+/// A change in scroll offset
+///
+/// Deltas are positive when the contact moves towards lower coordinates, so
+/// may be added directly to the scroll offset of a view whose content should
+/// follow the contact.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Scroll {
+    /// Change in scroll offset along the X axis
+    pub dx: i32,
+    /// Change in scroll offset along the Y axis
+    pub dy: i32,
+}
+
+/// State of the contact driving a scroll
+#[derive(Debug, Clone, Copy)]
+struct Drag {
+    /// ID of the contact
+    touch_id: u8,
+    /// Position at which the contact was placed
+    origin: Point,
+    /// Position at which the most recent delta was emitted
+    last: Point,
+    /// Has the contact moved beyond the slop distance?
+    scrolling: bool,
+    /// Axis to which scrolling has been locked
+    axis: Option<AxisLock>,
+    /// Recently reported positions of the contact
+    history: History<VELOCITY_SAMPLES>,
+}
+
+/// State of a scroll continuing after its contact was lifted
+#[derive(Debug, Clone, Copy)]
+struct Fling {
+    /// Scroll velocity along each axis, in units per second
+    velocity: (i32, i32),
+    /// Distance travelled but not yet emitted, in thousandths of a unit
+    residual: (i64, i64),
+    /// Time at which the fling was last advanced
+    updated_at: Instant,
+}
+
+/// Converts drags into accumulated scroll deltas
+///
+/// Only the first contact placed on the panel drives scrolling; any further
+/// contacts are ignored until it is lifted.
+///
+/// While a fling is in progress [Scroller::poll] should be called
+/// periodically, for example once per frame, to obtain its scroll deltas.
+#[derive(Debug, Clone)]
+pub struct Scroller {
+    /// Scroller configuration
+    config: ScrollConfig,
+    /// Contact currently driving the scroll, if any
+    drag: Option<Drag>,
+    /// Fling in progress, if any
+    fling: Option<Fling>,
+}
+
+impl Scroller {
+    /// Create a new scroller using the provided configuration
+    pub fn new(config: ScrollConfig) -> Self {
+        Self {
+            config,
+            drag: None,
+            fling: None,
+        }
+    }
+
+    /// Abandon any scroll or fling in progress
+    pub fn reset(&mut self) {
+        self.drag = None;
+        self.fling = None;
+    }
+
+    /// The configuration of the scroller
+    pub fn config(&self) -> &ScrollConfig {
+        &self.config
+    }
+
+    /// Replace the configuration of the scroller, taking effect from the next
+    /// update
+    pub fn set_config(&mut self, config: ScrollConfig) {
+        self.config = config;
+    }
+
+    /// Is a fling currently in progress?
+    pub fn is_flinging(&self) -> bool {
+        self.fling.is_some()
+    }
+
+    /// Is a contact currently scrolling?
+    pub fn is_scrolling(&self) -> bool {
+        self.drag.map_or(false, |drag| drag.scrolling)
+    }
+
+    /// Update the scroller with an event emitted by a
+    /// [Tracker](crate::tracker::Tracker), returning the resulting change in
+    /// scroll offset, if any
+    ///
+    /// Placing a new contact on the panel stops any fling in progress.
+    pub fn update(&mut self, event: &TouchEvent, now: Instant) -> Option<Scroll> {
+        match (*event, self.drag.as_mut()) {
+            (TouchEvent::Down(record), None) => {
+                let position = Point::from(&record);
+                let mut history = History::new();
+                history.push(Sample {
+                    position,
+                    time: now,
+                });
+
+                self.fling = None;
+                self.drag = Some(Drag {
+                    touch_id: record.touch_id,
+                    origin: position,
+                    last: position,
+                    scrolling: false,
+                    axis: None,
+                    history,
+                });
+
+                None
+            }
+            (TouchEvent::Move(record), Some(drag)) if record.touch_id == drag.touch_id => {
+                let position = Point::from(&record);
+                drag.history.push(Sample {
+                    position,
+                    time: now,
+                });
+
+                if !drag.scrolling {
+                    let slop = u32::from(self.config.slop);
+                    if drag.origin.distance_squared(position) < slop * slop {
+                        return None;
+                    }
+
+                    drag.scrolling = true;
+                    drag.axis = Some(lock_axis(self.config.axis_lock, drag.origin, position));
+                }
+
+                let mut scroll = Scroll {
+                    dx: i32::from(drag.last.x) - i32::from(position.x),
+                    dy: i32::from(drag.last.y) - i32::from(position.y),
+                };
+                match drag.axis {
+                    Some(AxisLock::Horizontal) => scroll.dy = 0,
+                    Some(AxisLock::Vertical) => scroll.dx = 0,
+                    _ => {}
+                }
+                drag.last = position;
+
+                if scroll == Scroll::default() {
+                    None
+                } else {
+                    Some(scroll)
+                }
+            }
+            (TouchEvent::Up(record, _), Some(drag)) if record.touch_id == drag.touch_id => {
+                let drag = *drag;
+                self.drag = None;
+                self.fling = self.begin_fling(&drag, now);
+
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Advance any fling in progress, returning the resulting change in scroll
+    /// offset, if any
+    pub fn poll(&mut self, now: Instant) -> Option<Scroll> {
+        let deceleration = i64::from(self.config.fling_deceleration.max(1));
+        let fling = self.fling.as_mut()?;
+
+        let elapsed = i64::from(now.millis_since(fling.updated_at));
+        if elapsed == 0 {
+            return None;
+        }
+        fling.updated_at = now;
+
+        let (vx, vy) = (i64::from(fling.velocity.0), i64::from(fling.velocity.1));
+        let speed = i64::from(isqrt((vx * vx + vy * vy) as u32));
+
+        // Time remaining until the fling comes to rest, in milliseconds
+        let remaining = speed * 1_000 / deceleration;
+        let elapsed = elapsed.min(remaining);
+        let new_speed = speed - deceleration * elapsed / 1_000;
+
+        let (nx, ny) = if speed == 0 || elapsed == remaining {
+            (0, 0)
+        } else {
+            (vx * new_speed / speed, vy * new_speed / speed)
+        };
+
+        // Distance travelled at the average velocity across the interval
+        fling.residual.0 += (vx + nx) * elapsed / 2;
+        fling.residual.1 += (vy + ny) * elapsed / 2;
+        let scroll = Scroll {
+            dx: (fling.residual.0 / 1_000) as i32,
+            dy: (fling.residual.1 / 1_000) as i32,
+        };
+        fling.residual.0 %= 1_000;
+        fling.residual.1 %= 1_000;
+        fling.velocity = (nx as i32, ny as i32);
+
+        if fling.velocity == (0, 0) {
+            self.fling = None;
+        }
+
+        if scroll == Scroll::default() {
+            None
+        } else {
+            Some(scroll)
+        }
+    }
+
+    /// Begin a fling if the given drag was moving quickly enough when its
+    /// contact was lifted
+    fn begin_fling(&self, drag: &Drag, now: Instant) -> Option<Fling> {
+        let latest = drag.history.latest()?;
+        if !drag.scrolling || now.millis_since(latest.time) > FLING_WINDOW_MS {
+            return None;
+        }
+
+        // Scroll deltas oppose the motion of the contact
+        let (vx, vy) = drag.history.velocity()?;
+        let mut velocity = (
+            (-vx).clamp(-MAX_FLING_SPEED, MAX_FLING_SPEED),
+            (-vy).clamp(-MAX_FLING_SPEED, MAX_FLING_SPEED),
+        );
+        match drag.axis {
+            Some(AxisLock::Horizontal) => velocity.1 = 0,
+            Some(AxisLock::Vertical) => velocity.0 = 0,
+            _ => {}
+        }
+
+        let speed = isqrt((velocity.0 * velocity.0 + velocity.1 * velocity.1) as u32);
+        if speed <= self.config.fling_threshold {
+            return None;
+        }
+
+        Some(Fling {
+            velocity,
+            residual: (0, 0),
+            updated_at: now,
+        })
+    }
+}
+
+/// Resolve the axis to which a drag from `origin` to `position` is locked
+fn lock_axis(lock: AxisLock, origin: Point, position: Point) -> AxisLock {
+    if lock != AxisLock::Auto {
+        return lock;
+    }
+
+    let dx = origin.x.max(position.x) - origin.x.min(position.x);
+    let dy = origin.y.max(position.y) - origin.y.min(position.y);
+    if dx > dy {
+        AxisLock::Horizontal
+    } else {
+        AxisLock::Vertical
+    }
+}
+
+/// Axis along which a [Wheel] is turned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WheelAxis {
+    /// The wheel is turned by horizontal drags
+    Horizontal,
+    /// The wheel is turned by vertical drags
+    Vertical,
+}
+
+/// Configuration for a [Wheel]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WheelConfig {
+    /// Region of the panel in which a contact must be placed to turn the wheel
+    pub strip: Rect,
+    /// Axis along which drags turn the wheel
+    pub axis: WheelAxis,
+    /// Distance a contact must move along the axis for each tick
+    pub detent: u16,
+}
+
+impl WheelConfig {
+    /// Create a new configuration for a wheel along the given strip, using
+    /// the default detent distance
+    pub const fn new(strip: Rect, axis: WheelAxis) -> Self {
+        Self {
+            strip,
+            axis,
+            detent: 20,
+        }
+    }
+}
+
+/// State of the contact turning a wheel
+#[derive(Debug, Clone, Copy)]
+struct Turn {
+    /// ID of the contact
+    touch_id: u8,
+    /// Position along the axis at which the most recent tick was emitted
+    last: i32,
+}
+
+/// Converts drags along an edge strip of the panel into discrete wheel ticks
+///
+/// Ticks are positive when the contact moves towards lower coordinates, as
+/// with [Scroll]. A contact placed within the strip continues to turn the
+/// wheel until it is lifted, even if it leaves the strip; contacts placed
+/// outside of it are ignored.
+#[derive(Debug, Clone)]
+pub struct Wheel {
+    /// Wheel configuration
+    config: WheelConfig,
+    /// Contact currently turning the wheel, if any
+    turn: Option<Turn>,
+}
+
+impl Wheel {
+    /// Create a new wheel using the provided configuration
+    pub fn new(config: WheelConfig) -> Self {
+        Self { config, turn: None }
+    }
+
+    /// Abandon any turn in progress
+    pub fn reset(&mut self) {
+        self.turn = None;
+    }
+
+    /// The configuration of the wheel
+    pub fn config(&self) -> &WheelConfig {
+        &self.config
+    }
+
+    /// Replace the configuration of the wheel, taking effect from the next
+    /// update
+    pub fn set_config(&mut self, config: WheelConfig) {
+        self.config = config;
+    }
+
+    /// Is a contact currently turning the wheel?
+    pub fn is_turning(&self) -> bool {
+        self.turn.is_some()
+    }
+
+    /// Update the wheel with an event emitted by a
+    /// [Tracker](crate::tracker::Tracker), returning the number of ticks it
+    /// was turned by, if any
+    pub fn update(&mut self, event: &TouchEvent) -> Option<i32> {
+        match (*event, self.turn.as_mut()) {
+            (TouchEvent::Down(record), None) => {
+                let position = Point::from(&record);
+                if self.config.strip.contains(position) {
+                    self.turn = Some(Turn {
+                        touch_id: record.touch_id,
+                        last: along_axis(self.config.axis, position),
+                    });
+                }
+
+                None
+            }
+            (TouchEvent::Move(record), Some(turn)) if record.touch_id == turn.touch_id => {
+                let detent = i32::from(self.config.detent.max(1));
+                let position = along_axis(self.config.axis, Point::from(&record));
+
+                let ticks = (turn.last - position) / detent;
+                if ticks == 0 {
+                    return None;
+                }
+                turn.last -= ticks * detent;
+
+                Some(ticks)
+            }
+            (TouchEvent::Up(record, _), Some(turn)) if record.touch_id == turn.touch_id => {
+                self.turn = None;
+
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Coordinate of a point along the given axis
+fn along_axis(axis: WheelAxis, point: Point) -> i32 {
+    match axis {
+        WheelAxis::Horizontal => i32::from(point.x),
+        WheelAxis::Vertical => i32::from(point.y),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tracker::ContactStats, TouchRecord};
+
+    fn update(scroller: &mut Scroller, event: TouchEvent, millis: u32) -> Option<Scroll> {
+        scroller.update(&event, Instant::from_millis(millis))
+    }
+
+    fn down(x: u16, y: u16) -> TouchEvent {
+        TouchEvent::Down(TouchRecord::new(1, x, y))
+    }
+
+    fn moved(x: u16, y: u16) -> TouchEvent {
+        TouchEvent::Move(TouchRecord::new(1, x, y))
+    }
+
+    fn up(x: u16, y: u16) -> TouchEvent {
+        TouchEvent::Up(TouchRecord::new(1, x, y), ContactStats::default())
+    }
+
+    #[test]
+    fn scrolls_once_beyond_slop() {
+        let mut scroller = Scroller::new(ScrollConfig::default());
+
+        assert_eq!(update(&mut scroller, down(100, 100), 0), None);
+        assert_eq!(update(&mut scroller, moved(102, 103), 10), None);
+        assert!(!scroller.is_scrolling());
+
+        assert_eq!(
+            update(&mut scroller, moved(103, 120), 20),
+            Some(Scroll { dx: 0, dy: -20 })
+        );
+        assert_eq!(
+            update(&mut scroller, moved(110, 115), 30),
+            Some(Scroll { dx: 0, dy: 5 })
+        );
+        assert!(scroller.is_scrolling());
+    }
+
+    #[test]
+    fn scrolls_freely_along_both_axes() {
+        let mut scroller = Scroller::new(ScrollConfig {
+            slop: 0,
+            axis_lock: AxisLock::Free,
+            ..ScrollConfig::default()
+        });
+
+        update(&mut scroller, down(100, 100), 0);
+
+        assert_eq!(
+            update(&mut scroller, moved(90, 95), 10),
+            Some(Scroll { dx: 10, dy: 5 })
+        );
+    }
+
+    #[test]
+    fn ignores_further_contacts() {
+        let mut scroller = Scroller::new(ScrollConfig {
+            slop: 0,
+            ..ScrollConfig::default()
+        });
+        let other = TouchRecord::new(2, 50, 50);
+
+        update(&mut scroller, down(100, 100), 0);
+        update(&mut scroller, TouchEvent::Down(other), 10);
+
+        assert_eq!(
+            update(
+                &mut scroller,
+                TouchEvent::Move(TouchRecord::new(2, 80, 50)),
+                20
+            ),
+            None
+        );
+        assert_eq!(update(&mut scroller, up(100, 100), 30), None);
+        assert!(!scroller.is_scrolling());
+    }
+
+    /// Drag a contact upwards at 2000 units per second, lifting it at 40ms
+    fn fling(scroller: &mut Scroller) {
+        update(scroller, down(100, 200), 0);
+        update(scroller, moved(100, 180), 10);
+        update(scroller, moved(100, 160), 20);
+        update(scroller, moved(100, 140), 30);
+        update(scroller, up(100, 140), 40);
+    }
+
+    #[test]
+    fn flings_until_coming_to_rest() {
+        let mut scroller = Scroller::new(ScrollConfig::default());
+        fling(&mut scroller);
+        assert!(scroller.is_flinging());
+
+        assert_eq!(
+            scroller.poll(Instant::from_millis(140)),
+            Some(Scroll { dx: 0, dy: 192 })
+        );
+        assert_eq!(scroller.poll(Instant::from_millis(140)), None);
+        assert_eq!(
+            scroller.poll(Instant::from_millis(10_000)),
+            Some(Scroll { dx: 0, dy: 1_141 })
+        );
+        assert!(!scroller.is_flinging());
+        assert_eq!(scroller.poll(Instant::from_millis(10_100)), None);
+    }
+
+    #[test]
+    fn stops_fling_when_contact_placed() {
+        let mut scroller = Scroller::new(ScrollConfig::default());
+        fling(&mut scroller);
+
+        update(&mut scroller, down(100, 100), 50);
+
+        assert!(!scroller.is_flinging());
+        assert_eq!(scroller.poll(Instant::from_millis(100)), None);
+    }
+
+    #[test]
+    fn does_not_fling_slow_or_paused_drags() {
+        let mut scroller = Scroller::new(ScrollConfig::default());
+
+        update(&mut scroller, down(100, 200), 0);
+        update(&mut scroller, moved(100, 180), 100);
+        update(&mut scroller, up(100, 180), 150);
+        assert!(!scroller.is_flinging());
+
+        update(&mut scroller, down(100, 200), 200);
+        update(&mut scroller, moved(100, 160), 210);
+        update(&mut scroller, up(100, 160), 320);
+        assert!(!scroller.is_flinging());
+    }
+
+    #[test]
+    fn ticks_wheel_along_strip() {
+        let strip = Rect::new(Point::new(280, 0), 40, 240);
+        let mut wheel = Wheel::new(WheelConfig::new(strip, WheelAxis::Vertical));
+
+        assert_eq!(wheel.update(&down(300, 100)), None);
+        assert!(wheel.is_turning());
+        assert_eq!(wheel.update(&moved(300, 85)), None);
+        assert_eq!(wheel.update(&moved(300, 75)), Some(1));
+        assert_eq!(wheel.update(&moved(250, 32)), Some(2));
+        assert_eq!(wheel.update(&moved(250, 100)), Some(-3));
+        assert_eq!(wheel.update(&up(250, 100)), None);
+        assert!(!wheel.is_turning());
+    }
+
+    #[test]
+    fn ignores_contacts_placed_outside_strip() {
+        let strip = Rect::new(Point::new(0, 220), 320, 20);
+        let mut wheel = Wheel::new(WheelConfig::new(strip, WheelAxis::Horizontal));
+
+        wheel.update(&down(100, 100));
+
+        assert!(!wheel.is_turning());
+        assert_eq!(wheel.update(&moved(40, 100)), None);
+    }
+}
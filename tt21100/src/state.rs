@@ -0,0 +1,578 @@
+//! State shared by the blocking and async drivers
+//!
+//! Everything which does not involve the bus is handled here, so that messages
+//! read by either driver are parsed and validated identically.
+
+use core::fmt::Debug;
+
+#[cfg(feature = "report-processing")]
+use crate::config::{Cooldown, MissedReports, ResetDetector};
+#[cfg(feature = "instrumentation")]
+use crate::instrumentation;
+#[cfg(feature = "trace")]
+use crate::MAX_MESSAGE_LEN;
+use crate::{
+    geom::{OrientationProvider, Transform},
+    parse_message,
+    Config,
+    Error,
+    Event,
+    ParseMode,
+    Parsed,
+    TouchRecord,
+    Touches,
+};
+
+/// State of a driver, independent of how it communicates with the device
+pub(crate) struct State {
+    /// Driver configuration
+    pub(crate) config: Config,
+    /// Number of touch records rejected due to their coordinates, during the
+    /// cool-down period following a report of a large object, or while a palm
+    /// was being rejected
+    rejected_records: u32,
+    /// Number of touch reports dropped due to the noise affecting them
+    #[cfg(feature = "report-processing")]
+    noisy_reports: u32,
+    /// Synthetic event waiting to be read, if any
+    injected: Option<Event>,
+    /// State of the cool-down period following a report of a large object
+    #[cfg(feature = "report-processing")]
+    cooldown: Cooldown,
+    /// Detection of spontaneous resets of the device
+    #[cfg(feature = "report-processing")]
+    resets: ResetDetector,
+    /// Detection of touch reports which were not read before being
+    /// overwritten by the next
+    #[cfg(feature = "report-processing")]
+    missed: MissedReports,
+    /// Has any bus transaction been performed?
+    transacted: bool,
+    /// Time spent sampling the device without reading an event, in
+    /// microseconds
+    quiet_us: u32,
+    /// Has the device been initialized, either explicitly or by successfully
+    /// reading a message from it?
+    pub(crate) initialized: bool,
+    /// Has the device been put to sleep?
+    pub(crate) asleep: bool,
+    /// Is the device being held in reset while in deep sleep?
+    pub(crate) held_in_reset: bool,
+    /// Most recent message read from the device
+    #[cfg(feature = "trace")]
+    frame: [u8; MAX_MESSAGE_LEN],
+    /// Length of the most recent message read from the device
+    #[cfg(feature = "trace")]
+    frame_len: usize,
+    /// Clock used to measure the duration of bus transactions, in microseconds
+    #[cfg(feature = "instrumentation")]
+    pub(crate) clock: Option<fn() -> u32>,
+    /// Measured durations of bus transactions
+    #[cfg(feature = "instrumentation")]
+    pub(crate) timings: instrumentation::BusTimings,
+}
+
+impl State {
+    pub(crate) fn new(config: Config) -> Self {
+        Self {
+            config,
+            rejected_records: 0,
+            #[cfg(feature = "report-processing")]
+            noisy_reports: 0,
+            injected: None,
+            #[cfg(feature = "report-processing")]
+            cooldown: Cooldown::default(),
+            #[cfg(feature = "report-processing")]
+            resets: ResetDetector::default(),
+            #[cfg(feature = "report-processing")]
+            missed: MissedReports::default(),
+            transacted: false,
+            quiet_us: 0,
+            initialized: false,
+            asleep: false,
+            held_in_reset: false,
+            #[cfg(feature = "trace")]
+            frame: [0; MAX_MESSAGE_LEN],
+            #[cfg(feature = "trace")]
+            frame_len: 0,
+            #[cfg(feature = "instrumentation")]
+            clock: None,
+            #[cfg(feature = "instrumentation")]
+            timings: instrumentation::BusTimings::default(),
+        }
+    }
+
+    /// Discard any state derived from earlier reports, ahead of initializing
+    /// the device again
+    pub(crate) fn reset(&mut self) {
+        self.initialized = false;
+        self.injected = None;
+        #[cfg(feature = "report-processing")]
+        {
+            self.cooldown = Cooldown::default();
+            self.resets.forget();
+            self.missed.forget();
+        }
+    }
+
+    /// Discard any injected event and forget the previous report, ahead of
+    /// discarding the messages queued on the device
+    pub(crate) fn flush(&mut self) {
+        self.injected = None;
+        // The timestamps of the discarded reports are never seen, so cannot be
+        // compared against those which follow
+        #[cfg(feature = "report-processing")]
+        {
+            self.resets.forget();
+            self.missed.forget();
+        }
+    }
+
+    pub(crate) fn has_injected(&self) -> bool {
+        self.injected.is_some()
+    }
+
+    pub(crate) fn inject(&mut self, event: Event) -> Option<Event> {
+        self.injected.replace(event)
+    }
+
+    /// Take the injected event, if any, validating it as though it had been
+    /// read from the device
+    pub(crate) fn take_injected(
+        &mut self,
+        orientation: &mut dyn OrientationProvider,
+    ) -> Option<Event> {
+        let event = self.injected.take()?;
+
+        Some(self.validate(event, orientation))
+    }
+
+    /// Gap to wait ahead of the next bus transaction, in microseconds
+    pub(crate) fn transaction_gap_us(&mut self) -> Option<u32> {
+        let gap = Some(self.config.transaction_gap_us).filter(|&gap| self.transacted && gap > 0);
+        self.transacted = true;
+
+        gap
+    }
+
+    /// Interval at which to sample the device, given how long it has been
+    /// since an event was last read
+    pub(crate) fn poll_interval_us(&self) -> u32 {
+        if self.quiet_us >= self.config.idle_after_us {
+            self.config.idle_poll_interval_us
+        } else {
+            self.config.poll_interval_us
+        }
+    }
+
+    /// Record the outcome of sampling the device while polling: either an
+    /// event was read, or the device was quiet for another `interval_us`
+    pub(crate) fn record_sample(&mut self, read: bool, interval_us: u32) {
+        self.quiet_us = if read {
+            0
+        } else {
+            self.quiet_us.saturating_add(interval_us)
+        };
+    }
+
+    /// Parse and validate a message read from the device
+    ///
+    /// `message` holds as much of the message as was read, beginning with its
+    /// length. Returns `None` if the message was skipped, rather than resulting
+    /// in an event, and [Error::NoDataAvailable] only when the device had no
+    /// message queued. When a reset of the device is detected it is recorded
+    /// and [Error::DeviceReset] returned, after which the driver must
+    /// initialize the device again.
+    pub(crate) fn process<E>(
+        &mut self,
+        message_length: usize,
+        message: &[u8],
+        orientation: &mut dyn OrientationProvider,
+    ) -> Result<Option<Event>, Error<E>>
+    where
+        E: Debug,
+    {
+        #[cfg(feature = "trace")]
+        {
+            self.frame_len = message.len().min(MAX_MESSAGE_LEN);
+            self.frame[0..][..self.frame_len].copy_from_slice(&message[0..][..self.frame_len]);
+        }
+
+        #[cfg(feature = "report-processing")]
+        {
+            if self.config.detect_resets && message_length == 0 {
+                self.resets.record_reset();
+                return Err(Error::DeviceReset);
+            }
+        }
+
+        // Until communication with the device has been confirmed, only messages
+        // of the expected lengths are accepted
+        let parsing = if self.initialized {
+            self.config.parsing
+        } else {
+            ParseMode::Strict
+        };
+        let event = match parse_message(message_length, message, parsing)? {
+            Parsed::Event(event) => event,
+            Parsed::Empty => {
+                self.initialized = true;
+                return Err(Error::NoDataAvailable);
+            }
+            Parsed::Skipped => return Ok(None),
+        };
+        self.initialized = true;
+        #[cfg(feature = "report-processing")]
+        {
+            if self.config.detect_resets && self.resets.update(&event) {
+                self.resets.record_reset();
+                self.missed.forget();
+                return Err(Error::DeviceReset);
+            }
+            self.missed.update(&event);
+            if self.is_noisy(&event) {
+                self.noisy_reports = self.noisy_reports.wrapping_add(1);
+                return Ok(None);
+            }
+        }
+
+        Ok(Some(self.validate(event, orientation)))
+    }
+
+    #[cfg(feature = "trace")]
+    pub(crate) fn last_frame(&self) -> &[u8] {
+        &self.frame[0..][..self.frame_len]
+    }
+
+    #[cfg(feature = "instrumentation")]
+    pub(crate) fn start_timing(&self) -> Option<u32> {
+        self.clock.map(|clock| clock())
+    }
+
+    #[cfg(feature = "instrumentation")]
+    pub(crate) fn record_timing(
+        &mut self,
+        transaction: instrumentation::Transaction,
+        start: Option<u32>,
+    ) {
+        if let (Some(clock), Some(start)) = (self.clock, start) {
+            self.timings
+                .record(transaction, clock().wrapping_sub(start));
+        }
+    }
+
+    pub(crate) fn rejected_records(&self) -> u32 {
+        self.rejected_records
+    }
+
+    #[cfg(feature = "report-processing")]
+    pub(crate) fn noisy_reports(&self) -> u32 {
+        self.noisy_reports
+    }
+
+    #[cfg(feature = "report-processing")]
+    pub(crate) fn resets(&self) -> u32 {
+        self.resets.count()
+    }
+
+    #[cfg(feature = "report-processing")]
+    pub(crate) fn missed_reports(&self) -> u32 {
+        self.missed.count()
+    }
+
+    // -----------------------------------------------------------------------
+    // PRIVATE
+
+    fn validate(&mut self, event: Event, orientation: &mut dyn OrientationProvider) -> Event {
+        match event {
+            #[cfg(feature = "report-processing")]
+            Event::Touch { report, touches } => {
+                let cooling = self
+                    .cooldown
+                    .update(&report, self.config.large_object_cooldown_us);
+                if self.config.reject_palms && report.large_object != 0 {
+                    self.rejected_records =
+                        self.rejected_records.wrapping_add(touches.len() as u32);
+                    self.cooldown.set_contacts(&Touches::new());
+
+                    return Event::PalmRejected(report);
+                }
+                let transform = self.transform(orientation);
+                let touches: Touches = touches
+                    .into_iter()
+                    .filter_map(|record| self.validate_record(record, cooling, transform))
+                    .collect();
+                self.cooldown.set_contacts(&touches);
+
+                Event::Touch { report, touches }
+            }
+            #[cfg(not(feature = "report-processing"))]
+            Event::Touch { report, touches } => {
+                let transform = self.transform(orientation);
+                let touches: Touches = touches
+                    .into_iter()
+                    .filter_map(|record| self.validate_record(record, transform))
+                    .collect();
+
+                Event::Touch { report, touches }
+            }
+            event => event,
+        }
+    }
+
+    /// Should the event be dropped due to the noise affecting it?
+    #[cfg(feature = "report-processing")]
+    fn is_noisy(&self, event: &Event) -> bool {
+        let threshold = match self.config.noise_threshold {
+            Some(threshold) => threshold,
+            None => return false,
+        };
+
+        event.touches().next().is_some() && event.noise() >= threshold
+    }
+
+    #[cfg(feature = "report-processing")]
+    fn validate_record(
+        &mut self,
+        record: TouchRecord,
+        cooling: bool,
+        transform: Transform,
+    ) -> Option<TouchRecord> {
+        let validated = self
+            .config
+            .validate(record)
+            .filter(|record| !cooling || self.cooldown.is_known(record.touch_id));
+
+        self.count_rejected(validated.map(|record| self.config.map(record, transform)))
+    }
+
+    #[cfg(not(feature = "report-processing"))]
+    fn validate_record(
+        &mut self,
+        record: TouchRecord,
+        transform: Transform,
+    ) -> Option<TouchRecord> {
+        let validated = self.config.validate(record);
+
+        self.count_rejected(validated.map(|record| self.config.map(record, transform)))
+    }
+
+    /// Transform applied to the coordinates of each touch record, following
+    /// the current orientation of the display
+    fn transform(&self, orientation: &mut dyn OrientationProvider) -> Transform {
+        self.config
+            .transform
+            .then(orientation.orientation().transform())
+    }
+
+    /// Count the record as rejected if it failed validation
+    fn count_rejected(&mut self, validated: Option<TouchRecord>) -> Option<TouchRecord> {
+        if validated.is_none() {
+            self.rejected_records = self.rejected_records.wrapping_add(1);
+        }
+
+        validated
+    }
+}
+
+#[cfg(all(test, feature = "report-processing"))]
+mod tests {
+    use super::*;
+    use crate::{geom::NoRotation, tests::touch_message, NoiseEffect, TouchReport};
+
+    fn process(
+        state: &mut State,
+        report: TouchReport,
+        records: &[TouchRecord],
+    ) -> Result<Option<Event>, Error<()>> {
+        let (message, length) = touch_message(report, records);
+
+        state.process(length, &message[0..][..length], &mut NoRotation)
+    }
+
+    fn report(time_stamp: u16, record_num: u8, report_counter: u8) -> TouchReport {
+        TouchReport {
+            report_counter,
+            ..TouchReport::new(time_stamp, record_num)
+        }
+    }
+
+    #[test]
+    fn detects_reset_from_zero_length() {
+        let mut state = State::new(Config {
+            detect_resets: true,
+            ..Config::default()
+        });
+
+        let result = state.process::<()>(0, &[0, 0], &mut NoRotation);
+
+        assert!(matches!(result, Err(Error::DeviceReset)));
+        assert_eq!(state.resets(), 1);
+    }
+
+    #[test]
+    fn detects_reset_from_timestamp_while_held() {
+        let mut state = State::new(Config {
+            detect_resets: true,
+            ..Config::default()
+        });
+        let records = [TouchRecord::new(1, 10, 10)];
+
+        assert!(matches!(
+            process(&mut state, report(1000, 1, 0), &records),
+            Ok(Some(_))
+        ));
+        let result = process(&mut state, report(10, 1, 1), &records);
+
+        assert!(matches!(result, Err(Error::DeviceReset)));
+        assert_eq!(state.resets(), 1);
+    }
+
+    #[test]
+    fn ignores_timestamp_after_release() {
+        let mut state = State::new(Config {
+            detect_resets: true,
+            ..Config::default()
+        });
+
+        assert!(matches!(
+            process(&mut state, report(1000, 0, 0), &[]),
+            Ok(Some(_))
+        ));
+        let result = process(&mut state, report(10, 1, 1), &[TouchRecord::new(1, 10, 10)]);
+
+        assert!(matches!(result, Ok(Some(_))));
+        assert_eq!(state.resets(), 0);
+    }
+
+    #[test]
+    fn counts_missed_reports() {
+        let mut state = State::new(Config::default());
+        let records = [TouchRecord::new(1, 10, 10)];
+
+        for counter in [0, 1, 3, 0] {
+            assert!(matches!(
+                process(&mut state, report(0, 1, counter), &records),
+                Ok(Some(_))
+            ));
+        }
+
+        assert_eq!(state.missed_reports(), 1);
+    }
+
+    #[test]
+    fn drops_noisy_reports() {
+        let mut state = State::new(Config {
+            noise_threshold: Some(NoiseEffect::Moderate),
+            ..Config::default()
+        });
+        let records = [TouchRecord::new(1, 10, 10)];
+        let noisy = TouchReport {
+            noise_effect: 3,
+            ..TouchReport::new(0, 1)
+        };
+        let quiet = TouchReport {
+            noise_effect: 2,
+            ..TouchReport::new(0, 1)
+        };
+
+        assert!(matches!(process(&mut state, noisy, &records), Ok(None)));
+        assert!(matches!(process(&mut state, quiet, &records), Ok(Some(_))));
+        assert_eq!(state.noisy_reports(), 1);
+    }
+
+    #[test]
+    fn keeps_noisy_reports_without_records() {
+        let mut state = State::new(Config {
+            noise_threshold: Some(NoiseEffect::Low),
+            ..Config::default()
+        });
+        let noisy = TouchReport {
+            noise_effect: 7,
+            ..TouchReport::new(0, 0)
+        };
+
+        assert!(matches!(process(&mut state, noisy, &[]), Ok(Some(_))));
+        assert_eq!(state.noisy_reports(), 0);
+    }
+
+    #[test]
+    fn rejects_palms() {
+        let mut state = State::new(Config {
+            reject_palms: true,
+            ..Config::default()
+        });
+        let records = [TouchRecord::new(1, 10, 10), TouchRecord::new(2, 20, 20)];
+        let palm = TouchReport {
+            large_object: 1,
+            ..TouchReport::new(0, 2)
+        };
+
+        let result = process(&mut state, palm, &records);
+
+        assert!(matches!(result, Ok(Some(Event::PalmRejected(_)))));
+        assert_eq!(state.rejected_records(), 2);
+    }
+
+    #[test]
+    fn keeps_large_objects_unless_rejecting_palms() {
+        let mut state = State::new(Config::default());
+        let records = [TouchRecord::new(1, 10, 10)];
+        let palm = TouchReport {
+            large_object: 1,
+            ..TouchReport::new(0, 1)
+        };
+
+        match process(&mut state, palm, &records) {
+            Ok(Some(event)) => assert_eq!(event.touches().count(), 1),
+            _ => panic!("expected a touch event"),
+        }
+    }
+
+    #[test]
+    fn rejects_new_contacts_after_large_objects() {
+        let mut state = State::new(Config {
+            large_object_cooldown_us: 5_000,
+            ..Config::default()
+        });
+        let held = TouchRecord::new(1, 10, 10);
+        let placed = TouchRecord::new(2, 20, 20);
+        let palm = TouchReport {
+            large_object: 1,
+            ..TouchReport::new(0, 1)
+        };
+
+        assert!(matches!(
+            process(&mut state, TouchReport::new(0, 1), &[held]),
+            Ok(Some(_))
+        ));
+        assert!(matches!(process(&mut state, palm, &[held]), Ok(Some(_))));
+
+        // Contacts already accepted remain while new ones are rejected
+        match process(&mut state, report(10, 2, 0), &[held, placed]) {
+            Ok(Some(event)) => assert!(event.touches().eq([held])),
+            _ => panic!("expected a touch event"),
+        }
+        assert_eq!(state.rejected_records(), 1);
+
+        match process(&mut state, report(50, 2, 0), &[held, placed]) {
+            Ok(Some(event)) => assert!(event.touches().eq([held, placed])),
+            _ => panic!("expected a touch event"),
+        }
+    }
+
+    #[test]
+    fn accepts_new_contacts_without_cooldown() {
+        let mut state = State::new(Config::default());
+        let palm = TouchReport {
+            large_object: 1,
+            ..TouchReport::new(0, 0)
+        };
+
+        assert!(matches!(process(&mut state, palm, &[]), Ok(Some(_))));
+        match process(&mut state, report(10, 1, 0), &[TouchRecord::new(2, 20, 20)]) {
+            Ok(Some(event)) => assert_eq!(event.touches().count(), 1),
+            _ => panic!("expected a touch event"),
+        }
+    }
+}
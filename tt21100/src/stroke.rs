@@ -0,0 +1,511 @@
+//! Capture of strokes, for signature pads and annotation
+//!
+//! Captured strokes may be simplified in place to reduce the number of points
+//! which must be stored or transmitted, using either [simplify] or
+//! [simplify_radial].
+
+use crate::{geom::Point, time::Instant, tracker::TouchEvent, TouchRecord};
+
+/// A single point along a stroke
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StrokePoint {
+    /// Position of the contact
+    pub position: Point,
+    /// Time at which the contact was at this position
+    pub time: Instant,
+    /// Pressure of the contact
+    pub pressure: u8,
+}
+
+impl StrokePoint {
+    fn from_record(record: &TouchRecord, time: Instant) -> Self {
+        Self {
+            position: Point::from(record),
+            time,
+            pressure: record.pressure,
+        }
+    }
+}
+
+/// Greatest number of segments awaiting subdivision by [simplify]
+///
+/// When more are required, the points of the segment being subdivided are
+/// kept as they are.
+const SIMPLIFY_DEPTH: usize = 32;
+
+/// Records strokes as polylines into caller-provided buffers
+///
+/// A stroke begins when a contact is placed on the panel and ends when it is
+/// lifted. Only one stroke is drawn at a time; contacts placed while a stroke
+/// is in progress, such as a hand resting on the panel, are ignored.
+///
+/// The points of every stroke are stored one after another in the `points`
+/// buffer, while the `ends` buffer records where each completed stroke ends.
+/// Once either buffer is full, no further points or strokes are recorded.
+#[derive(Debug)]
+pub struct StrokeRecorder<'a> {
+    /// Points of every stroke, in the order they were recorded
+    points: &'a mut [StrokePoint],
+    /// Number of points recorded
+    recorded: usize,
+    /// Index one past the last point of each completed stroke
+    ends: &'a mut [usize],
+    /// Number of completed strokes
+    strokes: usize,
+    /// ID of the contact drawing the current stroke, if any
+    pen: Option<u8>,
+    /// Have any points or strokes been dropped for lack of space?
+    full: bool,
+}
+
+impl<'a> StrokeRecorder<'a> {
+    /// Create a new recorder, storing points and the ends of strokes into the
+    /// given buffers
+    pub fn new(points: &'a mut [StrokePoint], ends: &'a mut [usize]) -> Self {
+        Self {
+            points,
+            recorded: 0,
+            ends,
+            strokes: 0,
+            pen: None,
+            full: false,
+        }
+    }
+
+    /// Discard every recorded stroke, including any in progress
+    pub fn clear(&mut self) {
+        self.recorded = 0;
+        self.strokes = 0;
+        self.pen = None;
+        self.full = false;
+    }
+
+    /// Number of completed strokes
+    pub fn len(&self) -> usize {
+        self.strokes
+    }
+
+    /// Have no strokes been completed?
+    pub fn is_empty(&self) -> bool {
+        self.strokes == 0
+    }
+
+    /// Have any points or strokes been dropped for lack of space?
+    pub fn is_full(&self) -> bool {
+        self.full
+    }
+
+    /// Is a stroke currently being drawn?
+    pub fn is_drawing(&self) -> bool {
+        self.pen.is_some()
+    }
+
+    /// The points of the completed stroke with the given index
+    pub fn stroke(&self, index: usize) -> Option<&[StrokePoint]> {
+        let (start, end) = self.bounds(index)?;
+
+        Some(&self.points[start..end])
+    }
+
+    /// The points of the completed stroke with the given index, for
+    /// modification in place
+    pub fn stroke_mut(&mut self, index: usize) -> Option<&mut [StrokePoint]> {
+        let (start, end) = self.bounds(index)?;
+
+        Some(&mut self.points[start..end])
+    }
+
+    /// The completed strokes, in the order they were drawn
+    pub fn strokes(&self) -> impl Iterator<Item = &[StrokePoint]> {
+        (0..self.strokes).filter_map(move |index| self.stroke(index))
+    }
+
+    /// The points of the stroke currently being drawn, if any
+    pub fn current(&self) -> Option<&[StrokePoint]> {
+        self.pen?;
+
+        Some(&self.points[self.stroke_start(self.strokes)..self.recorded])
+    }
+
+    /// Simplify every completed stroke in place using [simplify], freeing
+    /// space for further strokes
+    ///
+    /// Any stroke in progress is left as it is.
+    pub fn simplify(&mut self, tolerance: u16) {
+        let mut read = 0;
+        let mut write = 0;
+        for index in 0..self.strokes {
+            let end = self.ends[index];
+            let len = simplify(&mut self.points[read..end], tolerance);
+
+            self.points.copy_within(read..read + len, write);
+            write += len;
+            self.ends[index] = write;
+            read = end;
+        }
+
+        self.points.copy_within(read..self.recorded, write);
+        self.recorded = write + (self.recorded - read);
+        self.full = false;
+    }
+
+    /// Update the recorder with an event emitted by a
+    /// [Tracker](crate::tracker::Tracker), returning the index of the stroke
+    /// it completes, if any
+    pub fn update(&mut self, event: &TouchEvent, now: Instant) -> Option<usize> {
+        match (*event, self.pen) {
+            (TouchEvent::Down(record), None) => {
+                if self.strokes >= self.ends.len() {
+                    self.full = true;
+                    return None;
+                }
+
+                self.pen = Some(record.touch_id);
+                self.push(StrokePoint::from_record(&record, now));
+
+                None
+            }
+            (TouchEvent::Move(record), Some(pen)) if record.touch_id == pen => {
+                self.push(StrokePoint::from_record(&record, now));
+
+                None
+            }
+            (TouchEvent::Up(record, _), Some(pen)) if record.touch_id == pen => {
+                let position = Point::from(&record);
+                let last = self.current().and_then(|points| points.last());
+                if last.map_or(true, |last| last.position != position) {
+                    self.push(StrokePoint::from_record(&record, now));
+                }
+
+                self.pen = None;
+                self.ends[self.strokes] = self.recorded;
+                self.strokes += 1;
+
+                Some(self.strokes - 1)
+            }
+            _ => None,
+        }
+    }
+
+    fn push(&mut self, point: StrokePoint) {
+        match self.points.get_mut(self.recorded) {
+            Some(slot) => {
+                *slot = point;
+                self.recorded += 1;
+            }
+            None => self.full = true,
+        }
+    }
+
+    fn stroke_start(&self, index: usize) -> usize {
+        match index {
+            0 => 0,
+            n => self.ends[n - 1],
+        }
+    }
+
+    fn bounds(&self, index: usize) -> Option<(usize, usize)> {
+        if index >= self.strokes {
+            return None;
+        }
+
+        Some((self.stroke_start(index), self.ends[index]))
+    }
+}
+
+/// Simplify a stroke in place using the Ramer–Douglas–Peucker algorithm,
+/// returning the number of points kept
+///
+/// Points are removed where doing so moves the stroke by no more than
+/// `tolerance`; the first and last points are always kept. The kept points
+/// are moved to the start of the slice, in their original order.
+pub fn simplify(points: &mut [StrokePoint], tolerance: u16) -> usize {
+    if points.len() <= 2 {
+        return points.len();
+    }
+
+    let tolerance = i64::from(tolerance);
+    let tolerance = tolerance * tolerance;
+
+    // Segments are subdivided from the start of the stroke onwards, so every
+    // kept point is written at or before the position it was read from
+    let mut pending = [0; SIMPLIFY_DEPTH];
+    pending[0] = points.len() - 1;
+    let mut depth = 1;
+
+    let mut anchor = 0;
+    let mut write = 1;
+    while depth > 0 {
+        let floater = pending[depth - 1];
+        let (start, end) = (points[anchor].position, points[floater].position);
+
+        let farthest = (anchor + 1..floater)
+            .map(|index| (index, distance_squared(points[index].position, start, end)))
+            .max_by_key(|&(_, distance)| distance)
+            .filter(|&(_, distance)| distance > tolerance);
+
+        match farthest {
+            Some((index, _)) if depth < SIMPLIFY_DEPTH => {
+                pending[depth] = index;
+                depth += 1;
+            }
+            farthest => {
+                // Keep every point of the segment if it could not be
+                // subdivided any further
+                let from = if farthest.is_some() {
+                    anchor + 1
+                } else {
+                    floater
+                };
+                points.copy_within(from..=floater, write);
+                write += floater + 1 - from;
+
+                anchor = floater;
+                depth -= 1;
+            }
+        }
+    }
+
+    write
+}
+
+/// Simplify a stroke in place by removing points which lie within
+/// `tolerance` of the previously kept point, returning the number of points
+/// kept
+///
+/// This is cheaper than [simplify], but generally keeps more points for the
+/// same tolerance. The first and last points are always kept, and the kept
+/// points are moved to the start of the slice in their original order.
+pub fn simplify_radial(points: &mut [StrokePoint], tolerance: u16) -> usize {
+    if points.len() <= 2 {
+        return points.len();
+    }
+
+    let last = points.len() - 1;
+    let tolerance = u32::from(tolerance);
+    let tolerance = tolerance * tolerance;
+
+    let mut write = 1;
+    for read in 1..last {
+        if points[read]
+            .position
+            .distance_squared(points[write - 1].position)
+            > tolerance
+        {
+            points[write] = points[read];
+            write += 1;
+        }
+    }
+
+    // The last point replaces the last kept point if the two are too close
+    if write > 1
+        && points[last]
+            .position
+            .distance_squared(points[write - 1].position)
+            <= tolerance
+    {
+        write -= 1;
+    }
+    points[write] = points[last];
+
+    write + 1
+}
+
+/// Square of the distance from `point` to the segment from `start` to `end`
+fn distance_squared(point: Point, start: Point, end: Point) -> i64 {
+    let (px, py) = (i64::from(point.x), i64::from(point.y));
+    let (sx, sy) = (i64::from(start.x), i64::from(start.y));
+    let (dx, dy) = (i64::from(end.x) - sx, i64::from(end.y) - sy);
+
+    let length_squared = dx * dx + dy * dy;
+    if length_squared == 0 {
+        return i64::from(point.distance_squared(start));
+    }
+
+    // Beyond either end of the segment the nearest point is that end
+    let projection = (px - sx) * dx + (py - sy) * dy;
+    if projection <= 0 {
+        return i64::from(point.distance_squared(start));
+    }
+    if projection >= length_squared {
+        return i64::from(point.distance_squared(end));
+    }
+
+    let cross = i128::from((px - sx) * dy - (py - sy) * dx);
+
+    (cross * cross / i128::from(length_squared)) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tracker::ContactStats;
+
+    fn down(id: u8, x: u16, y: u16) -> TouchEvent {
+        TouchEvent::Down(TouchRecord::new(id, x, y))
+    }
+
+    fn moved(id: u8, x: u16, y: u16) -> TouchEvent {
+        TouchEvent::Move(TouchRecord::new(id, x, y))
+    }
+
+    fn up(id: u8, x: u16, y: u16) -> TouchEvent {
+        TouchEvent::Up(TouchRecord::new(id, x, y), ContactStats::default())
+    }
+
+    fn positions(points: &[StrokePoint]) -> impl Iterator<Item = (u16, u16)> + '_ {
+        points
+            .iter()
+            .map(|point| (point.position.x, point.position.y))
+    }
+
+    #[test]
+    fn records_strokes_between_down_and_up() {
+        let mut points = [StrokePoint::default(); 8];
+        let mut ends = [0; 4];
+        let mut recorder = StrokeRecorder::new(&mut points, &mut ends);
+
+        assert_eq!(
+            recorder.update(&down(1, 10, 10), Instant::from_millis(0)),
+            None
+        );
+        assert_eq!(
+            recorder.update(&moved(1, 20, 10), Instant::from_millis(10)),
+            None
+        );
+        assert!(recorder.is_drawing());
+        assert!(recorder
+            .current()
+            .map_or(false, |points| positions(points).eq([(10, 10), (20, 10)])));
+        assert_eq!(
+            recorder.update(&up(1, 20, 10), Instant::from_millis(20)),
+            Some(0)
+        );
+
+        recorder.update(&down(1, 50, 50), Instant::from_millis(100));
+        assert_eq!(
+            recorder.update(&up(1, 60, 50), Instant::from_millis(110)),
+            Some(1)
+        );
+
+        assert_eq!(recorder.len(), 2);
+        assert!(!recorder.is_drawing());
+        assert!(recorder.current().is_none());
+        assert!(recorder
+            .stroke(0)
+            .map_or(false, |points| positions(points).eq([(10, 10), (20, 10)])));
+        assert!(recorder
+            .stroke(1)
+            .map_or(false, |points| points[1].time == Instant::from_millis(110)));
+        assert!(recorder.stroke(2).is_none());
+    }
+
+    #[test]
+    fn ignores_contacts_placed_during_stroke() {
+        let mut points = [StrokePoint::default(); 8];
+        let mut ends = [0; 4];
+        let mut recorder = StrokeRecorder::new(&mut points, &mut ends);
+        let now = Instant::from_millis(0);
+
+        recorder.update(&down(1, 10, 10), now);
+        recorder.update(&down(2, 90, 90), now);
+        recorder.update(&moved(2, 95, 90), now);
+        assert_eq!(recorder.update(&up(2, 95, 90), now), None);
+        recorder.update(&up(1, 10, 10), now);
+
+        assert!(recorder.strokes().map(<[StrokePoint]>::len).eq([1]));
+    }
+
+    #[test]
+    fn stops_recording_once_full() {
+        let mut points = [StrokePoint::default(); 3];
+        let mut ends = [0; 1];
+        let mut recorder = StrokeRecorder::new(&mut points, &mut ends);
+        let now = Instant::from_millis(0);
+
+        recorder.update(&down(1, 10, 10), now);
+        for x in [20, 30, 40] {
+            recorder.update(&moved(1, x, 10), now);
+        }
+        recorder.update(&up(1, 40, 10), now);
+        assert!(recorder.is_full());
+
+        recorder.update(&down(1, 50, 50), now);
+        assert!(!recorder.is_drawing());
+        assert_eq!(recorder.len(), 1);
+
+        recorder.clear();
+        assert!(recorder.is_empty());
+        assert!(!recorder.is_full());
+    }
+
+    fn stroke(positions: &[(u16, u16)]) -> [StrokePoint; 8] {
+        let mut points = [StrokePoint::default(); 8];
+        for (point, &(x, y)) in points.iter_mut().zip(positions) {
+            point.position = Point::new(x, y);
+        }
+
+        points
+    }
+
+    #[test]
+    fn simplifies_straight_runs_of_points() {
+        let mut points = stroke(&[(0, 0), (10, 10), (20, 20), (30, 30)]);
+        let len = simplify(&mut points[..4], 1);
+        assert!(positions(&points[..len]).eq([(0, 0), (30, 30)]));
+
+        let mut points = stroke(&[(0, 0), (10, 0), (20, 0), (20, 10), (20, 20)]);
+        let len = simplify(&mut points[..5], 1);
+        assert!(positions(&points[..len]).eq([(0, 0), (20, 0), (20, 20)]));
+    }
+
+    #[test]
+    fn keeps_points_beyond_tolerance() {
+        let mut points = stroke(&[(0, 0), (10, 1), (20, 0)]);
+        assert_eq!(simplify(&mut points[..3], 2), 2);
+
+        let mut points = stroke(&[(0, 0), (10, 1), (20, 0)]);
+        assert_eq!(simplify(&mut points[..3], 0), 3);
+    }
+
+    #[test]
+    fn simplifies_points_close_to_previous() {
+        let mut points = stroke(&[(0, 0), (1, 0), (2, 0), (10, 0), (20, 0)]);
+        let len = simplify_radial(&mut points[..5], 3);
+        assert!(positions(&points[..len]).eq([(0, 0), (10, 0), (20, 0)]));
+
+        // The last point is kept in place of a kept point close to it
+        let mut points = stroke(&[(0, 0), (1, 0), (2, 0), (10, 0), (11, 0)]);
+        let len = simplify_radial(&mut points[..5], 3);
+        assert!(positions(&points[..len]).eq([(0, 0), (11, 0)]));
+    }
+
+    #[test]
+    fn compacts_recorded_strokes() {
+        let mut points = [StrokePoint::default(); 16];
+        let mut ends = [0; 4];
+        let mut recorder = StrokeRecorder::new(&mut points, &mut ends);
+        let now = Instant::from_millis(0);
+
+        recorder.update(&down(1, 0, 0), now);
+        for x in [10, 20, 30] {
+            recorder.update(&moved(1, x, 0), now);
+        }
+        recorder.update(&up(1, 30, 0), now);
+        recorder.update(&down(1, 0, 10), now);
+        recorder.update(&moved(1, 10, 20), now);
+        recorder.update(&up(1, 20, 30), now);
+        recorder.update(&down(1, 50, 50), now);
+        recorder.update(&moved(1, 51, 50), now);
+
+        recorder.simplify(1);
+
+        assert!(recorder.strokes().map(<[StrokePoint]>::len).eq([2, 2]));
+        assert!(recorder
+            .stroke(1)
+            .map_or(false, |points| positions(points).eq([(0, 10), (20, 30)])));
+        assert!(recorder
+            .current()
+            .map_or(false, |points| positions(points).eq([(50, 50), (51, 50)])));
+    }
+}
@@ -0,0 +1,31 @@
+//! Timekeeping
+
+/// A point in time, in milliseconds since some arbitrary epoch
+///
+/// Instants wrap around after roughly 49 days, so the time elapsed between
+/// two instants is always computed using wrapping arithmetic.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct Instant(u32);
+
+impl Instant {
+    /// Create an instant from a number of milliseconds since the epoch
+    pub const fn from_millis(millis: u32) -> Self {
+        Self(millis)
+    }
+
+    /// Number of milliseconds since the epoch
+    pub const fn as_millis(&self) -> u32 {
+        self.0
+    }
+
+    /// Milliseconds elapsed since an earlier instant
+    pub fn millis_since(&self, earlier: Instant) -> u32 {
+        self.0.wrapping_sub(earlier.0)
+    }
+
+    /// The instant `millis` milliseconds after this one
+    pub fn add_millis(&self, millis: u32) -> Instant {
+        Self(self.0.wrapping_add(millis))
+    }
+}
@@ -0,0 +1,333 @@
+//! Compact, versioned format for captured traces of raw frames
+//!
+//! A trace begins with a header consisting of the [MAGIC] bytes followed by
+//! the [VERSION] of the format. Each frame which follows consists of:
+//!
+//! - the time elapsed since the previous frame in milliseconds, or the
+//!   timestamp of the frame itself if it is the first, encoded as an unsigned
+//!   LEB128 varint
+//! - the length of the message as a single byte
+//! - the message, exactly as read from the device
+//!
+//! Traces can be recorded on target using a [TraceWriter] and the driver's
+//! `last_frame` method, and replayed using a [ReplayDevice] or decoded on the
+//! host using a [TraceReader].
+//!
+//! [ReplayDevice]: crate::replay::ReplayDevice
+
+/// Bytes identifying a trace
+pub const MAGIC: [u8; 4] = *b"TT21";
+
+/// Version of the trace format
+pub const VERSION: u8 = 1;
+
+/// Length of the header at the start of every trace, in bytes
+pub const HEADER_LEN: usize = MAGIC.len() + 1;
+
+/// Longest encoding of a single varint, in bytes
+const MAX_VARINT_LEN: usize = 5;
+
+/// Any error which may occur while reading or writing a trace
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub enum TraceError {
+    /// There is not enough space remaining in the buffer to write the frame
+    BufferFull,
+    /// The trace does not begin with a valid header
+    InvalidHeader,
+    /// The trace was written using an unsupported version of the format
+    UnsupportedVersion(u8),
+    /// The trace ended part way through a frame
+    Truncated,
+    /// The message is too long to be stored in a trace
+    MessageTooLong(usize),
+}
+
+/// A single frame of a trace
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct TraceFrame<'a> {
+    /// Time at which the frame was captured, in milliseconds
+    pub timestamp_ms: u32,
+    /// The message, exactly as read from the device
+    pub message: &'a [u8],
+}
+
+/// Writes frames to a trace held in a byte buffer
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct TraceWriter<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+    last_timestamp_ms: u32,
+}
+
+impl<'a> TraceWriter<'a> {
+    /// Begin a new trace in the given buffer, writing its header
+    pub fn new(buffer: &'a mut [u8]) -> Result<Self, TraceError> {
+        if buffer.len() < HEADER_LEN {
+            return Err(TraceError::BufferFull);
+        }
+
+        buffer[0..][..MAGIC.len()].copy_from_slice(&MAGIC);
+        buffer[MAGIC.len()] = VERSION;
+
+        Ok(Self {
+            buffer,
+            len: HEADER_LEN,
+            last_timestamp_ms: 0,
+        })
+    }
+
+    /// Append a frame to the trace
+    ///
+    /// If there is not enough space remaining for the frame, nothing is written
+    /// and [TraceError::BufferFull] is returned.
+    pub fn record(&mut self, timestamp_ms: u32, message: &[u8]) -> Result<(), TraceError> {
+        if message.len() > usize::from(u8::MAX) {
+            return Err(TraceError::MessageTooLong(message.len()));
+        }
+
+        let mut delta = [0u8; MAX_VARINT_LEN];
+        let delta_len = encode_varint(
+            timestamp_ms.wrapping_sub(self.last_timestamp_ms),
+            &mut delta,
+        );
+
+        let frame_len = delta_len + 1 + message.len();
+        if self.buffer.len() - self.len < frame_len {
+            return Err(TraceError::BufferFull);
+        }
+
+        let frame = &mut self.buffer[self.len..][..frame_len];
+        frame[0..][..delta_len].copy_from_slice(&delta[0..][..delta_len]);
+        frame[delta_len] = message.len() as u8;
+        frame[delta_len + 1..].copy_from_slice(message);
+
+        self.len += frame_len;
+        self.last_timestamp_ms = timestamp_ms;
+
+        Ok(())
+    }
+
+    /// Number of bytes written to the trace, including its header
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Have no frames been written to the trace?
+    pub fn is_empty(&self) -> bool {
+        self.len == HEADER_LEN
+    }
+
+    /// The trace written so far
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer[0..][..self.len]
+    }
+}
+
+/// Reads the frames of a trace
+///
+/// Iteration stops after the first error is returned.
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "debug", derive(Debug))]
+pub struct TraceReader<'a> {
+    data: &'a [u8],
+    timestamp_ms: u32,
+    failed: bool,
+}
+
+impl<'a> TraceReader<'a> {
+    /// Begin reading a trace, validating its header
+    pub fn new(data: &'a [u8]) -> Result<Self, TraceError> {
+        if data.len() < HEADER_LEN || data[0..][..MAGIC.len()] != MAGIC {
+            return Err(TraceError::InvalidHeader);
+        }
+
+        match data[MAGIC.len()] {
+            VERSION => Ok(Self {
+                data: &data[HEADER_LEN..],
+                timestamp_ms: 0,
+                failed: false,
+            }),
+            version => Err(TraceError::UnsupportedVersion(version)),
+        }
+    }
+
+    fn read_frame(&mut self) -> Result<TraceFrame<'a>, TraceError> {
+        let (delta, delta_len) = decode_varint(self.data).ok_or(TraceError::Truncated)?;
+        let data = &self.data[delta_len..];
+
+        let (length, data) = data.split_first().ok_or(TraceError::Truncated)?;
+        let length = usize::from(*length);
+        if data.len() < length {
+            return Err(TraceError::Truncated);
+        }
+        let (message, rest) = data.split_at(length);
+
+        self.data = rest;
+        self.timestamp_ms = self.timestamp_ms.wrapping_add(delta);
+
+        Ok(TraceFrame {
+            timestamp_ms: self.timestamp_ms,
+            message,
+        })
+    }
+}
+
+impl<'a> Iterator for TraceReader<'a> {
+    type Item = Result<TraceFrame<'a>, TraceError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.data.is_empty() {
+            return None;
+        }
+
+        let frame = self.read_frame();
+        self.failed = frame.is_err();
+
+        Some(frame)
+    }
+}
+
+/// Encode a value as an unsigned LEB128 varint, returning its length
+fn encode_varint(mut value: u32, buffer: &mut [u8; MAX_VARINT_LEN]) -> usize {
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            buffer[len] = byte;
+            return len + 1;
+        }
+
+        buffer[len] = byte | 0x80;
+        len += 1;
+    }
+}
+
+/// Decode an unsigned LEB128 varint, returning its value and length
+fn decode_varint(data: &[u8]) -> Option<(u32, usize)> {
+    let mut value = 0u32;
+    for (i, byte) in data.iter().take(MAX_VARINT_LEN).enumerate() {
+        value |= u32::from(byte & 0x7F) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_varints() {
+        for (value, encoded) in [
+            (0, &[0x00][..]),
+            (127, &[0x7F]),
+            (128, &[0x80, 0x01]),
+            (300, &[0xAC, 0x02]),
+            (u32::MAX, &[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]),
+        ] {
+            let mut buffer = [0; MAX_VARINT_LEN];
+            let len = encode_varint(value, &mut buffer);
+
+            assert_eq!(&buffer[0..][..len], encoded);
+            assert_eq!(decode_varint(encoded), Some((value, len)));
+        }
+
+        assert_eq!(decode_varint(&[0x80]), None);
+        assert_eq!(decode_varint(&[0x80; 6]), None);
+    }
+
+    #[test]
+    fn records_frames_after_header() {
+        let mut buffer = [0; 32];
+        let mut writer = TraceWriter::new(&mut buffer).unwrap();
+        assert!(writer.is_empty());
+        assert_eq!(writer.as_bytes(), b"TT21\x01");
+
+        assert!(writer.record(1_000, &[2, 0]).is_ok());
+        assert!(writer.record(1_010, &[3, 0, 9]).is_ok());
+
+        assert!(!writer.is_empty());
+        assert_eq!(
+            &writer.as_bytes()[HEADER_LEN..],
+            [0xE8, 0x07, 2, 2, 0, 10, 3, 3, 0, 9]
+        );
+    }
+
+    #[test]
+    fn reads_recorded_frames() {
+        let mut buffer = [0; 32];
+        let mut writer = TraceWriter::new(&mut buffer).unwrap();
+        assert!(writer.record(1_000, &[2, 0]).is_ok());
+        assert!(writer.record(1_300, &[3, 0, 9]).is_ok());
+
+        let mut reader = TraceReader::new(writer.as_bytes()).unwrap();
+        assert!(
+            reader.next()
+                == Some(Ok(TraceFrame {
+                    timestamp_ms: 1_000,
+                    message: &[2, 0],
+                }))
+        );
+        assert!(
+            reader.next()
+                == Some(Ok(TraceFrame {
+                    timestamp_ms: 1_300,
+                    message: &[3, 0, 9],
+                }))
+        );
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn rejects_invalid_headers() {
+        assert!(matches!(
+            TraceReader::new(b"TT2"),
+            Err(TraceError::InvalidHeader)
+        ));
+        assert!(matches!(
+            TraceReader::new(b"TT22\x01"),
+            Err(TraceError::InvalidHeader)
+        ));
+        assert!(matches!(
+            TraceReader::new(b"TT21\x02"),
+            Err(TraceError::UnsupportedVersion(2))
+        ));
+    }
+
+    #[test]
+    fn stops_at_truncated_frame() {
+        let mut reader = TraceReader::new(b"TT21\x01\x00\x02\x02\x00\x05\x03\x03").unwrap();
+
+        assert!(matches!(reader.next(), Some(Ok(_))));
+        assert!(matches!(reader.next(), Some(Err(TraceError::Truncated))));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn leaves_trace_unchanged_when_full() {
+        let mut buffer = [0; HEADER_LEN + 4];
+        assert!(matches!(
+            TraceWriter::new(&mut buffer[0..][..HEADER_LEN - 1]),
+            Err(TraceError::BufferFull)
+        ));
+
+        let mut writer = TraceWriter::new(&mut buffer).unwrap();
+        assert!(writer.record(0, &[2, 0]).is_ok());
+        assert!(matches!(
+            writer.record(1, &[2, 0]),
+            Err(TraceError::BufferFull)
+        ));
+        assert!(matches!(
+            writer.record(1, &[0; 256]),
+            Err(TraceError::MessageTooLong(256))
+        ));
+        assert_eq!(writer.len(), HEADER_LEN + 4);
+    }
+}
@@ -0,0 +1,713 @@
+//! Tracking of individual contacts across touch events
+
+use crate::{geom::Point, time::Instant, Event, Phase, TouchRecord, Touches, MAX_TOUCHES};
+
+/// Configuration for a [Tracker]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrackerConfig {
+    /// Distance a contact may move while still being considered stationary
+    pub stationary_radius: u16,
+    /// Time a contact must remain within `stationary_radius` of a position
+    /// to be considered stationary, in milliseconds
+    pub stationary_ms: u32,
+    /// Distance a contact must move from its previously delivered position
+    /// before a [TouchEvent::Move] is emitted
+    pub min_move_distance: u16,
+    /// Minimum time between successive [TouchEvent::Move]s for a single
+    /// contact, in milliseconds
+    ///
+    /// Moves occurring within this period are coalesced, with only the newest
+    /// position being delivered once the period has elapsed.
+    pub move_interval_ms: u32,
+    /// Track contacts which are hovering above the panel
+    ///
+    /// By default only contacts touching the panel are tracked, so a contact
+    /// whose tip is lifted emits a [TouchEvent::Up] even though the device
+    /// continues to report it, and a [TouchEvent::Down] once it touches the
+    /// panel again.
+    pub track_hover: bool,
+}
+
+impl Default for TrackerConfig {
+    fn default() -> Self {
+        Self {
+            stationary_radius: 8,
+            stationary_ms: 300,
+            min_move_distance: 1,
+            move_interval_ms: 0,
+            track_hover: false,
+        }
+    }
+}
+
+/// A change in the state of a tracked contact
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TouchEvent {
+    /// A new contact was reported
+    Down(TouchRecord),
+    /// A contact moved
+    Move(TouchRecord),
+    /// A contact was lifted; carries the last record reported for it, along
+    /// with statistics describing the contact's lifetime
+    Up(TouchRecord, ContactStats),
+}
+
+/// Statistics describing the lifetime of a contact
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ContactStats {
+    /// Total distance travelled by the contact
+    pub travel: u32,
+    /// Time for which the contact has been down, in milliseconds
+    pub duration_ms: u32,
+}
+
+/// Iterator over the [TouchEvent]s resulting from a single update of a
+/// [Tracker]
+#[derive(Debug, Clone)]
+pub struct TouchEvents {
+    events: [Option<TouchEvent>; 2 * MAX_TOUCHES],
+    len: usize,
+    index: usize,
+}
+
+impl TouchEvents {
+    fn new() -> Self {
+        Self {
+            events: [None; 2 * MAX_TOUCHES],
+            len: 0,
+            index: 0,
+        }
+    }
+
+    fn push(&mut self, event: TouchEvent) {
+        if let Some(slot) = self.events.get_mut(self.len) {
+            *slot = Some(event);
+            self.len += 1;
+        }
+    }
+}
+
+impl Iterator for TouchEvents {
+    type Item = TouchEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let event = self.events.get(self.index).copied().flatten();
+        if event.is_some() {
+            self.index += 1;
+        }
+
+        event
+    }
+}
+
+/// Opaque handle identifying a single contact for as long as it is tracked
+///
+/// Unlike the touch ID reported by the device, which is reused once a contact
+/// is lifted, a handle is never shared by two contacts tracked in succession.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ContactHandle(u32);
+
+/// A single entry in a contact's [History]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    /// Reported position
+    pub position: Point,
+    /// Time at which the position was reported
+    pub time: Instant,
+}
+
+/// Ring buffer holding the `N` most recently reported positions of a contact
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct History<const N: usize> {
+    /// Stored samples
+    samples: [Sample; N],
+    /// Index of the oldest sample
+    head: usize,
+    /// Number of stored samples
+    len: usize,
+}
+
+impl<const N: usize> History<N> {
+    pub(crate) fn new() -> Self {
+        Self {
+            samples: [Sample::default(); N],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, sample: Sample) {
+        if N == 0 {
+            return;
+        }
+
+        if self.len < N {
+            self.samples[(self.head + self.len) % N] = sample;
+            self.len += 1;
+        } else {
+            self.samples[self.head] = sample;
+            self.head = (self.head + 1) % N;
+        }
+    }
+
+    /// Number of samples currently stored
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Are there no samples stored?
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The oldest sample stored
+    pub fn oldest(&self) -> Option<&Sample> {
+        self.iter().next()
+    }
+
+    /// The most recent sample stored
+    pub fn latest(&self) -> Option<&Sample> {
+        if self.len == 0 {
+            return None;
+        }
+
+        Some(&self.samples[(self.head + self.len - 1) % N])
+    }
+
+    /// Iterate over the stored samples, from oldest to most recent
+    pub fn iter(&self) -> impl Iterator<Item = &Sample> {
+        (0..self.len).map(move |i| &self.samples[(self.head + i) % N])
+    }
+
+    /// Average velocity across the stored samples, in units per second
+    ///
+    /// Returns `None` if fewer than two samples are stored, or if no time
+    /// elapsed between the oldest and most recent samples.
+    pub fn velocity(&self) -> Option<(i32, i32)> {
+        let (oldest, latest) = (self.oldest()?, self.latest()?);
+
+        let elapsed = latest.time.millis_since(oldest.time) as i32;
+        if elapsed == 0 {
+            return None;
+        }
+
+        let dx = i32::from(latest.position.x) - i32::from(oldest.position.x);
+        let dy = i32::from(latest.position.y) - i32::from(oldest.position.y);
+
+        Some((dx * 1_000 / elapsed, dy * 1_000 / elapsed))
+    }
+}
+
+/// A contact which is currently being tracked
+///
+/// The `N` most recently reported positions of the contact are retained in
+/// its [History].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Contact<const N: usize> {
+    /// Handle identifying the contact
+    handle: ContactHandle,
+    /// Most recent touch record reported for the contact
+    pub record: TouchRecord,
+    /// Time at which the contact was first reported
+    pub down_at: Instant,
+    /// Time at which the contact was most recently reported
+    pub updated_at: Instant,
+    /// Position around which the contact has remained
+    anchor: Point,
+    /// Time at which the contact arrived at its anchor position
+    anchored_at: Instant,
+    /// Recently reported positions of the contact
+    history: History<N>,
+    /// Position included in the most recent event emitted for the contact
+    delivered: Point,
+    /// Time at which the most recent event was emitted for the contact
+    delivered_at: Instant,
+    /// Has the contact moved since the most recent event was emitted?
+    pending: bool,
+    /// Total distance travelled by the contact
+    travel: u32,
+}
+
+impl<const N: usize> Contact<N> {
+    fn new(handle: ContactHandle, record: TouchRecord, now: Instant) -> Self {
+        let mut history = History::new();
+        history.push(Sample {
+            position: Point::from(&record),
+            time: now,
+        });
+
+        Self {
+            handle,
+            record,
+            down_at: now,
+            updated_at: now,
+            anchor: Point::from(&record),
+            anchored_at: now,
+            history,
+            delivered: Point::from(&record),
+            delivered_at: now,
+            pending: false,
+            travel: 0,
+        }
+    }
+
+    /// Handle identifying the contact
+    pub fn handle(&self) -> ContactHandle {
+        self.handle
+    }
+
+    /// ID of the contact, as reported by the device
+    pub fn touch_id(&self) -> u8 {
+        self.record.touch_id
+    }
+
+    /// Most recently reported position of the contact
+    pub fn position(&self) -> Point {
+        Point::from(&self.record)
+    }
+
+    /// Is the contact touching the panel, or hovering above it?
+    pub fn phase(&self) -> Phase {
+        self.record.phase()
+    }
+
+    /// Recently reported positions of the contact
+    pub fn history(&self) -> &History<N> {
+        &self.history
+    }
+
+    /// Statistics describing the lifetime of the contact so far
+    pub fn stats(&self, now: Instant) -> ContactStats {
+        ContactStats {
+            travel: self.travel,
+            duration_ms: now.millis_since(self.down_at),
+        }
+    }
+
+    /// Milliseconds for which the contact has remained within the stationary
+    /// radius of its current position
+    pub fn stationary_ms(&self, now: Instant) -> u32 {
+        now.millis_since(self.anchored_at)
+    }
+
+    /// Update the contact with a new record, returning whether it has moved
+    /// far enough for a [TouchEvent::Move] to be emitted
+    fn update(&mut self, record: TouchRecord, now: Instant, config: &TrackerConfig) -> bool {
+        let position = Point::from(&record);
+        self.travel = self
+            .travel
+            .saturating_add(self.position().distance(position));
+
+        if self.anchor.distance_squared(position) > u32::from(config.stationary_radius).pow(2) {
+            self.anchor = position;
+            self.anchored_at = now;
+        }
+
+        self.record = record;
+        self.updated_at = now;
+        self.history.push(Sample {
+            position,
+            time: now,
+        });
+
+        let min_distance = u32::from(config.min_move_distance.max(1));
+        self.pending = self.delivered.distance_squared(position) >= min_distance.pow(2);
+
+        self.flush(now, config)
+    }
+
+    /// Mark the contact's current position as delivered if it has moved and
+    /// the move interval has elapsed, returning whether it was delivered
+    fn flush(&mut self, now: Instant, config: &TrackerConfig) -> bool {
+        if !self.pending || now.millis_since(self.delivered_at) < config.move_interval_ms {
+            return false;
+        }
+
+        self.delivered = self.position();
+        self.delivered_at = now;
+        self.pending = false;
+
+        true
+    }
+}
+
+/// A compact snapshot of the current state of the panel
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Most recent record reported for each tracked contact
+    pub contacts: [Option<TouchRecord>; MAX_TOUCHES],
+    /// State of the buttons; bit `n` is set while button `n` is pressed
+    pub buttons: u8,
+    /// Time at which the most recent event was processed, if any
+    pub updated_at: Option<Instant>,
+}
+
+/// Tracks individual contacts across successive touch events
+///
+/// The `N` most recently reported positions of each contact are retained.
+#[derive(Debug, Clone)]
+pub struct Tracker<const N: usize> {
+    /// Tracker configuration
+    config: TrackerConfig,
+    /// Contacts currently being tracked
+    contacts: [Option<Contact<N>>; MAX_TOUCHES],
+    /// Most recently reported state of the buttons
+    buttons: u8,
+    /// Time at which the most recent event was processed
+    updated_at: Option<Instant>,
+    /// Handle to be assigned to the next new contact
+    next_handle: u32,
+}
+
+impl<const N: usize> Tracker<N> {
+    /// Create a new tracker using the provided configuration
+    pub fn new(config: TrackerConfig) -> Self {
+        Self {
+            config,
+            contacts: Default::default(),
+            buttons: 0,
+            updated_at: None,
+            next_handle: 0,
+        }
+    }
+
+    /// Forget all tracked contacts
+    pub fn reset(&mut self) {
+        self.contacts = Default::default();
+        self.buttons = 0;
+        self.updated_at = None;
+    }
+
+    /// The configuration of the tracker
+    pub fn config(&self) -> &TrackerConfig {
+        &self.config
+    }
+
+    /// Replace the configuration of the tracker
+    ///
+    /// Contacts currently being tracked are retained, and are subject to the
+    /// new configuration from the next update.
+    pub fn set_config(&mut self, config: TrackerConfig) {
+        self.config = config;
+    }
+
+    /// Update the tracked contacts with an event read from the device,
+    /// returning the resulting changes in their state
+    ///
+    /// Contacts which are not present in a touch event, or which are hovering
+    /// above the panel unless [TrackerConfig::track_hover] is set, are
+    /// considered to have been lifted, and are forgotten.
+    pub fn update(&mut self, event: &Event, now: Instant) -> TouchEvents {
+        let mut events = TouchEvents::new();
+        self.updated_at = Some(now);
+
+        let track_hover = self.config.track_hover;
+        let records = match event {
+            Event::Touch { touches, .. } => {
+                let mut touches = *touches;
+                touches.retain(|record| track_hover || record.phase() == Phase::Touch);
+                touches
+            }
+            Event::PalmRejected(_) => Touches::new(),
+            Event::Button(record) => {
+                self.buttons = record.btn_val & 0x0F;
+                return events;
+            }
+        };
+
+        for slot in self.contacts.iter_mut() {
+            if let Some(contact) = slot {
+                let present = records
+                    .iter()
+                    .any(|record| record.touch_id == contact.touch_id());
+                if !present {
+                    events.push(TouchEvent::Up(contact.record, contact.stats(now)));
+                    *slot = None;
+                }
+            }
+        }
+
+        for record in records.iter() {
+            let config = self.config;
+            if let Some(contact) = self.contact_mut(record.touch_id) {
+                if contact.update(*record, now, &config) {
+                    events.push(TouchEvent::Move(*record));
+                }
+            } else if let Some(slot) = self.contacts.iter_mut().find(|slot| slot.is_none()) {
+                let handle = ContactHandle(self.next_handle);
+                self.next_handle = self.next_handle.wrapping_add(1);
+
+                *slot = Some(Contact::new(handle, *record, now));
+                events.push(TouchEvent::Down(*record));
+            }
+        }
+
+        events
+    }
+
+    /// Deliver any moves which were coalesced while waiting for the move
+    /// interval to elapse
+    ///
+    /// This should be called periodically when
+    /// [TrackerConfig::move_interval_ms] is non-zero, as otherwise the
+    /// newest position of a contact is not delivered until the device next
+    /// reports it.
+    pub fn poll(&mut self, now: Instant) -> TouchEvents {
+        let mut events = TouchEvents::new();
+
+        let config = self.config;
+        for contact in self.contacts.iter_mut().flatten() {
+            if contact.flush(now, &config) {
+                events.push(TouchEvent::Move(contact.record));
+            }
+        }
+
+        events
+    }
+
+    /// A snapshot of the current state of the panel
+    ///
+    /// Allows render loops to sample the state once per frame, rather than
+    /// consuming each event as it occurs.
+    pub fn latest_state(&self) -> Snapshot {
+        let mut contacts = [None; MAX_TOUCHES];
+        for (snapshot, contact) in contacts.iter_mut().zip(self.contacts.iter()) {
+            *snapshot = contact.as_ref().map(|contact| contact.record);
+        }
+
+        Snapshot {
+            contacts,
+            buttons: self.buttons,
+            updated_at: self.updated_at,
+        }
+    }
+
+    /// Iterate over the contacts currently being tracked
+    pub fn contacts(&self) -> impl Iterator<Item = &Contact<N>> {
+        self.contacts.iter().flatten()
+    }
+
+    /// The tracked contact with the given ID, if any
+    pub fn contact(&self, touch_id: u8) -> Option<&Contact<N>> {
+        self.contacts()
+            .find(|contact| contact.touch_id() == touch_id)
+    }
+
+    /// The tracked contact identified by the given handle, if it has not yet
+    /// been lifted
+    pub fn contact_by_handle(&self, handle: ContactHandle) -> Option<&Contact<N>> {
+        self.contacts().find(|contact| contact.handle() == handle)
+    }
+
+    /// Has the contact with the given ID remained within the stationary radius
+    /// for at least the configured amount of time?
+    pub fn is_stationary(&self, touch_id: u8, now: Instant) -> bool {
+        self.contact(touch_id).map_or(false, |contact| {
+            contact.stationary_ms(now) >= self.config.stationary_ms
+        })
+    }
+
+    fn contact_mut(&mut self, touch_id: u8) -> Option<&mut Contact<N>> {
+        self.contacts
+            .iter_mut()
+            .flatten()
+            .find(|contact| contact.touch_id() == touch_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ButtonRecord;
+
+    fn update(tracker: &mut Tracker<4>, records: &[TouchRecord], millis: u32) -> TouchEvents {
+        tracker.update(&Event::touch(0, records), Instant::from_millis(millis))
+    }
+
+    #[test]
+    fn reports_down_move_and_up() {
+        let mut tracker = Tracker::<4>::new(TrackerConfig::default());
+        let placed = TouchRecord::new(1, 10, 10);
+        let moved = TouchRecord::new(1, 13, 14);
+
+        assert!(update(&mut tracker, &[placed], 0).eq([TouchEvent::Down(placed)]));
+        assert!(update(&mut tracker, &[moved], 10).eq([TouchEvent::Move(moved)]));
+        assert!(update(&mut tracker, &[], 30).eq([TouchEvent::Up(
+            moved,
+            ContactStats {
+                travel: 5,
+                duration_ms: 30,
+            },
+        )]));
+        assert_eq!(tracker.contacts().count(), 0);
+    }
+
+    #[test]
+    fn ignores_unmoved_contacts() {
+        let mut tracker = Tracker::<4>::new(TrackerConfig::default());
+        let record = TouchRecord::new(1, 10, 10);
+
+        update(&mut tracker, &[record], 0);
+
+        assert_eq!(update(&mut tracker, &[record], 10).count(), 0);
+    }
+
+    #[test]
+    fn tracks_contacts_independently() {
+        let mut tracker = Tracker::<4>::new(TrackerConfig::default());
+        let first = TouchRecord::new(1, 10, 10);
+        let second = TouchRecord::new(2, 50, 50);
+
+        update(&mut tracker, &[first], 0);
+        let events = update(&mut tracker, &[second], 10);
+
+        assert!(events.eq([
+            TouchEvent::Up(
+                first,
+                ContactStats {
+                    travel: 0,
+                    duration_ms: 10,
+                },
+            ),
+            TouchEvent::Down(second),
+        ]));
+    }
+
+    #[test]
+    fn releases_hovering_contacts() {
+        let mut tracker = Tracker::<4>::new(TrackerConfig::default());
+        let record = TouchRecord::new(1, 10, 10);
+        let hovering = record.with_phase(Phase::Hover);
+
+        update(&mut tracker, &[record], 0);
+
+        assert!(matches!(
+            update(&mut tracker, &[hovering], 10).next(),
+            Some(TouchEvent::Up(..))
+        ));
+        assert_eq!(update(&mut tracker, &[hovering], 20).count(), 0);
+    }
+
+    #[test]
+    fn coalesces_moves_within_interval() {
+        let mut tracker = Tracker::<4>::new(TrackerConfig {
+            move_interval_ms: 20,
+            ..TrackerConfig::default()
+        });
+        let moved = TouchRecord::new(1, 20, 10);
+
+        update(&mut tracker, &[TouchRecord::new(1, 10, 10)], 0);
+
+        assert_eq!(update(&mut tracker, &[moved], 10).count(), 0);
+        assert!(tracker
+            .poll(Instant::from_millis(20))
+            .eq([TouchEvent::Move(moved)]));
+    }
+
+    #[test]
+    fn classifies_stationary_contacts() {
+        let mut tracker = Tracker::<4>::new(TrackerConfig::default());
+
+        update(&mut tracker, &[TouchRecord::new(1, 10, 10)], 0);
+        update(&mut tracker, &[TouchRecord::new(1, 14, 12)], 200);
+
+        assert!(!tracker.is_stationary(1, Instant::from_millis(200)));
+        assert!(tracker.is_stationary(1, Instant::from_millis(300)));
+
+        update(&mut tracker, &[TouchRecord::new(1, 40, 10)], 400);
+
+        assert!(!tracker.is_stationary(1, Instant::from_millis(400)));
+        assert!(tracker.is_stationary(1, Instant::from_millis(700)));
+        assert!(!tracker.is_stationary(2, Instant::from_millis(700)));
+    }
+
+    #[test]
+    fn keeps_most_recent_samples() {
+        let mut history = History::<3>::new();
+        for i in 0..5 {
+            history.push(Sample {
+                position: Point::new(i * 10, 0),
+                time: Instant::from_millis(u32::from(i) * 100),
+            });
+        }
+
+        assert_eq!(history.len(), 3);
+        assert!(history
+            .iter()
+            .map(|sample| sample.position.x)
+            .eq([20, 30, 40]));
+        assert_eq!(history.oldest().map(|sample| sample.position.x), Some(20));
+        assert_eq!(history.latest().map(|sample| sample.position.x), Some(40));
+        assert_eq!(history.velocity(), Some((100, 0)));
+    }
+
+    #[test]
+    fn requires_elapsed_time_for_velocity() {
+        let mut history = History::<4>::new();
+        assert!(history.is_empty());
+        assert_eq!(history.velocity(), None);
+
+        let sample = Sample {
+            position: Point::new(10, 10),
+            time: Instant::from_millis(0),
+        };
+        history.push(sample);
+        history.push(sample);
+
+        assert_eq!(history.velocity(), None);
+    }
+
+    #[test]
+    fn records_history_of_tracked_contacts() {
+        let mut tracker = Tracker::<4>::new(TrackerConfig::default());
+
+        update(&mut tracker, &[TouchRecord::new(1, 10, 10)], 0);
+        update(&mut tracker, &[TouchRecord::new(1, 10, 60)], 100);
+
+        let history = tracker.contact(1).map(|contact| contact.history());
+        assert_eq!(history.map(History::len), Some(2));
+        assert_eq!(history.and_then(History::velocity), Some((0, 500)));
+    }
+
+    #[test]
+    fn snapshots_latest_state() {
+        let mut tracker = Tracker::<4>::new(TrackerConfig::default());
+        assert_eq!(tracker.latest_state(), Snapshot::default());
+
+        let first = TouchRecord::new(1, 10, 10);
+        let second = TouchRecord::new(2, 50, 50);
+        update(&mut tracker, &[first], 0);
+        update(&mut tracker, &[first.with_pressure(10), second], 10);
+        tracker.update(
+            &Event::Button(ButtonRecord::new(0, 0b0101)),
+            Instant::from_millis(20),
+        );
+
+        let snapshot = tracker.latest_state();
+        assert_eq!(snapshot.contacts[0], Some(first.with_pressure(10)));
+        assert_eq!(snapshot.contacts[1], Some(second));
+        assert!(snapshot.contacts[2..].iter().all(Option::is_none));
+        assert_eq!(snapshot.buttons, 0b0101);
+        assert_eq!(snapshot.updated_at, Some(Instant::from_millis(20)));
+    }
+
+    #[test]
+    fn accumulates_travel_of_contacts() {
+        let mut tracker = Tracker::<4>::new(TrackerConfig::default());
+
+        update(&mut tracker, &[TouchRecord::new(1, 0, 0)], 100);
+        update(&mut tracker, &[TouchRecord::new(1, 3, 4)], 150);
+        update(&mut tracker, &[TouchRecord::new(1, 3, 10)], 200);
+
+        let stats = tracker
+            .contact(1)
+            .map(|contact| contact.stats(Instant::from_millis(250)));
+        assert_eq!(
+            stats,
+            Some(ContactStats {
+                travel: 11,
+                duration_ms: 150,
+            })
+        );
+    }
+}